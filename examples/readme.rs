@@ -1,17 +1,15 @@
-use voicevox_dyn::{AccelerationMode, VoiceVox};
-
-const SPEAKER_ID: u32 = 4;
+use voicevox_dyn::{styles::ZUNDAMON_NORMAL, AccelerationMode, VoiceVox};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let threads = std::thread::available_parallelism()?.get() as u16;
 
     let mut vv = VoiceVox::load()?;
     vv.init(AccelerationMode::Auto, threads, false)?;
-    vv.load_model(SPEAKER_ID)?;
+    vv.load_model(ZUNDAMON_NORMAL)?;
 
-    let wav = vv.tts("こんにちは", SPEAKER_ID, Default::default())?;
+    let wav = vv.tts("こんにちは", ZUNDAMON_NORMAL, Default::default())?;
 
-    std::fs::write("audio.wav", wav.as_slice())?;
+    std::fs::write("audio.wav", &wav)?;
 
     Ok(())
 }
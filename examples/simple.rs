@@ -1,7 +1,5 @@
 use tracing::info;
-use voicevox_dyn::{AccelerationMode, VoiceVox};
-
-const SPEAKER_ID: u32 = 4;
+use voicevox_dyn::{styles::ZUNDAMON_NORMAL, AccelerationMode, VoiceVox};
 
 fn main() -> color_eyre::Result<()> {
     tracing_subscriber::fmt::init();
@@ -10,13 +8,13 @@ fn main() -> color_eyre::Result<()> {
 
     let mut vv = VoiceVox::load()?;
     vv.init(AccelerationMode::Auto, threads, false)?;
-    vv.load_model(SPEAKER_ID)?;
+    vv.load_model(ZUNDAMON_NORMAL)?;
 
     let now = std::time::Instant::now();
-    let wav = vv.tts("ステキだね", SPEAKER_ID, Default::default())?;
+    let wav = vv.tts("ステキだね", ZUNDAMON_NORMAL, Default::default())?;
     info!("tts took {:?}", now.elapsed());
 
-    std::fs::write("audio.wav", wav.as_slice())?;
+    std::fs::write("audio.wav", &wav)?;
 
     Ok(())
 }
@@ -0,0 +1,228 @@
+use std::{sync::mpsc, thread::JoinHandle};
+
+use crate::{
+    pending::{pending, Completion},
+    Error, Pending, PendingSynthesis, StyleId, SynthesisOptions, VoiceVox, Wav,
+};
+
+/// A thread that owns a [`VoiceVox`] and makes the calls sent to it one after another.
+///
+/// Every call returns a [`Pending`] result, which can be waited for in sync code or awaited in
+/// async code with any runtime. Send calls from other threads with a [`TtsClient`].
+///
+/// Dropping the actor finishes the calls sent before and stops the thread.
+///
+/// ```no_run
+/// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, TtsActor, VoiceVox};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let actor = TtsActor::spawn(VoiceVox::builder().build()?)?;
+/// let client = actor.client();
+/// std::thread::spawn(move || {
+///     client.load_model(ZUNDAMON_NORMAL).wait()?;
+///     let wav = client.synthesize("こんにちは", ZUNDAMON_NORMAL, &Default::default()).wait()?;
+///     std::fs::write("speech.wav", wav.to_bytes())?;
+///     Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TtsActor {
+    client: TtsClient,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Sends calls to a [`TtsActor`], created with [`TtsActor::client`].
+#[derive(Debug, Clone)]
+pub struct TtsClient {
+    sender: mpsc::Sender<Command>,
+}
+
+enum Command {
+    Synthesize {
+        text: String,
+        style_id: StyleId,
+        opts: Box<SynthesisOptions>,
+        reply: Completion<Wav>,
+    },
+    LoadModel {
+        style_id: StyleId,
+        reply: Completion<()>,
+    },
+    /// Any other call, which completes its own reply.
+    Run(Box<dyn FnOnce(&VoiceVox) + Send>),
+    /// Stops the thread, completing the reply once the [`VoiceVox`] is dropped.
+    Shutdown(Option<Completion<()>>),
+}
+
+impl std::fmt::Debug for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Command::Synthesize { text, style_id, .. } => f
+                .debug_struct("Synthesize")
+                .field("text", text)
+                .field("style_id", style_id)
+                .finish_non_exhaustive(),
+            Command::LoadModel { style_id, .. } => f
+                .debug_struct("LoadModel")
+                .field("style_id", style_id)
+                .finish_non_exhaustive(),
+            Command::Run(_) => f.write_str("Run"),
+            Command::Shutdown(_) => f.write_str("Shutdown"),
+        }
+    }
+}
+
+impl TtsActor {
+    /// Starts the thread that owns `vv`.
+    pub fn spawn(vv: VoiceVox) -> Result<Self, Error> {
+        let (sender, receiver) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("voicevox-actor".to_owned())
+            .spawn(move || {
+                let mut done = None;
+                for command in receiver {
+                    match command {
+                        Command::Synthesize {
+                            text,
+                            style_id,
+                            opts,
+                            reply,
+                        } => reply.complete(vv.tts_with_options(text, style_id, &opts)),
+                        Command::LoadModel { style_id, reply } => {
+                            reply.complete(vv.load_model(style_id))
+                        }
+                        Command::Run(call) => call(&vv),
+                        Command::Shutdown(reply) => {
+                            done = reply;
+                            break;
+                        }
+                    }
+                }
+                drop(vv);
+                debug!("The voicevox actor shut down.");
+                if let Some(done) = done {
+                    done.complete(Ok(()));
+                }
+            })
+            .map_err(Error::io("failed to spawn the voicevox actor"))?;
+        Ok(Self {
+            client: TtsClient { sender },
+            thread: Some(thread),
+        })
+    }
+
+    /// A client for sending calls from other threads.
+    pub fn client(&self) -> TtsClient {
+        self.client.clone()
+    }
+
+    /// See [`TtsClient::synthesize`].
+    pub fn synthesize(
+        &self,
+        text: impl Into<String>,
+        style_id: StyleId,
+        opts: &SynthesisOptions,
+    ) -> PendingSynthesis {
+        self.client.synthesize(text, style_id, opts)
+    }
+
+    /// See [`TtsClient::load_model`].
+    pub fn load_model(&self, style_id: StyleId) -> Pending<()> {
+        self.client.load_model(style_id)
+    }
+
+    /// Finishes the calls sent before and stops the thread, waiting for it.
+    pub fn shutdown(self) {
+        drop(self);
+    }
+
+    /// Lets the thread run on its own, it stops once every client is dropped or
+    /// [`TtsClient::shutdown`] is called.
+    pub(crate) fn detach(mut self) -> TtsClient {
+        self.thread = None;
+        self.client.clone()
+    }
+}
+
+impl Drop for TtsActor {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.client.shutdown();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl TtsClient {
+    /// Synthesizes `text` with [`VoiceVox::tts_with_options`] once the calls sent before are
+    /// done.
+    pub fn synthesize(
+        &self,
+        text: impl Into<String>,
+        style_id: StyleId,
+        opts: &SynthesisOptions,
+    ) -> PendingSynthesis {
+        let (reply, pending) = pending();
+        self.send(Command::Synthesize {
+            text: text.into(),
+            style_id,
+            opts: Box::new(opts.clone()),
+            reply,
+        });
+        pending
+    }
+
+    /// Loads the model of `style_id` with [`VoiceVox::load_model`] once the calls sent before
+    /// are done.
+    pub fn load_model(&self, style_id: StyleId) -> Pending<()> {
+        let (reply, pending) = pending();
+        self.send(Command::LoadModel { style_id, reply });
+        pending
+    }
+
+    /// Runs `call` with the [`VoiceVox`] once the calls sent before are done.
+    pub(crate) fn run<T: Send + 'static>(
+        &self,
+        call: impl FnOnce(&VoiceVox) -> Result<T, Error> + Send + 'static,
+    ) -> Pending<T> {
+        let (reply, pending) = pending();
+        self.send(Command::Run(Box::new(move |vv| reply.complete(call(vv)))));
+        pending
+    }
+
+    /// Stops the actor once the calls sent before are done. Calls sent afterwards fail.
+    pub fn shutdown(&self) {
+        self.send(Command::Shutdown(None));
+    }
+
+    /// Same as [`TtsClient::shutdown`], completing once the thread dropped the [`VoiceVox`].
+    pub(crate) fn shutdown_and_wait(&self) -> Pending<()> {
+        let (reply, pending) = pending();
+        self.send(Command::Shutdown(Some(reply)));
+        pending
+    }
+
+    fn send(&self, command: Command) {
+        if let Err(mpsc::SendError(command)) = self.sender.send(command) {
+            let e = || {
+                Error::io("the tts actor was shut down")(std::io::Error::other(
+                    "the voicevox actor thread is not running",
+                ))
+            };
+            match command {
+                Command::Synthesize { reply, .. } => reply.complete(Err(e())),
+                Command::LoadModel { reply, .. } => reply.complete(Err(e())),
+                // The actor has already stopped.
+                Command::Shutdown(reply) => {
+                    if let Some(reply) = reply {
+                        reply.complete(Ok(()));
+                    }
+                }
+                // Dropping the call drops its reply, which completes it with an error.
+                Command::Run(_) => {}
+            }
+        }
+    }
+}
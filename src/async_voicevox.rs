@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use crate::{
+    AudioQuery, Error, StyleId, SynthesisOptions, TtsActor, TtsClient, TtsOptions, VoiceVox,
+    VoiceVoxInfo, Wav, WavBytes,
+};
+
+/// A cloneable handle whose methods are all async and run on one dedicated thread that owns
+/// the [`VoiceVox`], so an inference never blocks the thread of an async runtime.
+///
+/// The futures need no particular runtime. Unlike
+/// [`nonblocking::VoiceVox`](crate::nonblocking::VoiceVox) no feature is required and the
+/// calls never run concurrently, they are made one after another in the order they were
+/// made. The thread stops once every clone is dropped, or with [`AsyncVoiceVox::shutdown`].
+///
+/// ```no_run
+/// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, AsyncVoiceVox, VoiceVox};
+///
+/// async fn greet(vv: &AsyncVoiceVox) -> Result<Vec<u8>, voicevox_dyn::Error> {
+///     vv.load_model(ZUNDAMON_NORMAL).await?;
+///     let wav = vv.tts("こんにちは", ZUNDAMON_NORMAL, Default::default()).await?;
+///     Ok(wav.into_vec())
+/// }
+///
+/// # fn main() -> Result<(), voicevox_dyn::Error> {
+/// let vv = AsyncVoiceVox::new(VoiceVox::builder().build()?)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AsyncVoiceVox {
+    inner: Arc<Inner>,
+}
+
+/// Stops the thread once the last clone is dropped, without waiting for it, as waiting would
+/// block the async runtime until the call being made is done.
+#[derive(Debug)]
+struct Inner {
+    client: TtsClient,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        self.client.shutdown();
+    }
+}
+
+impl AsyncVoiceVox {
+    /// Starts the thread that owns `vv`.
+    pub fn new(vv: VoiceVox) -> Result<Self, Error> {
+        Ok(Self {
+            inner: Arc::new(Inner {
+                client: TtsActor::spawn(vv)?.detach(),
+            }),
+        })
+    }
+
+    /// Stops the thread once the calls made before are done and waits for it to drop the
+    /// [`VoiceVox`], e.g. before initializing voicevox again. Calls made afterwards by any
+    /// clone fail.
+    ///
+    /// Dropping every clone stops the thread as well, but does not wait for it.
+    pub async fn shutdown(&self) -> Result<(), Error> {
+        self.inner.client.shutdown_and_wait().await
+    }
+
+    /// See [`VoiceVox::load_model`].
+    pub async fn load_model(&self, style_id: StyleId) -> Result<(), Error> {
+        self.inner.client.load_model(style_id).await
+    }
+
+    /// See [`VoiceVox::load_models`].
+    ///
+    /// The models that failed to load are reported with [`Error::ModelLoad`].
+    pub async fn load_models(&self, style_ids: &[StyleId]) -> Result<(), Error> {
+        let style_ids = style_ids.to_vec();
+        self.inner
+            .client
+            .run(move |vv| vv.load_models(&style_ids).map_err(Error::ModelLoad))
+            .await
+    }
+
+    /// See [`VoiceVox::is_model_loaded`].
+    pub async fn is_model_loaded(&self, style_id: StyleId) -> Result<bool, Error> {
+        self.inner
+            .client
+            .run(move |vv| Ok(vv.is_model_loaded(style_id)))
+            .await
+    }
+
+    /// See [`VoiceVox::info`].
+    pub async fn info(&self) -> Result<VoiceVoxInfo, Error> {
+        self.inner.client.run(|vv| Ok(vv.info())).await
+    }
+
+    /// See [`VoiceVox::tts`].
+    pub async fn tts(
+        &self,
+        text: impl Into<String>,
+        style_id: StyleId,
+        opts: TtsOptions,
+    ) -> Result<WavBytes, Error> {
+        let text = text.into();
+        self.inner
+            .client
+            .run(move |vv| vv.tts(text, style_id, opts))
+            .await
+    }
+
+    /// See [`VoiceVox::tts_wav`].
+    pub async fn tts_wav(
+        &self,
+        text: impl Into<String>,
+        style_id: StyleId,
+        opts: TtsOptions,
+    ) -> Result<Wav, Error> {
+        let text = text.into();
+        self.inner
+            .client
+            .run(move |vv| vv.tts_wav(text, style_id, opts))
+            .await
+    }
+
+    /// See [`VoiceVox::tts_with_options`].
+    pub async fn tts_with_options(
+        &self,
+        text: impl Into<String>,
+        style_id: StyleId,
+        opts: &SynthesisOptions,
+    ) -> Result<Wav, Error> {
+        self.inner.client.synthesize(text, style_id, opts).await
+    }
+
+    /// See [`VoiceVox::tts_ssml`].
+    pub async fn tts_ssml(
+        &self,
+        ssml: impl Into<String>,
+        style_id: StyleId,
+        opts: TtsOptions,
+    ) -> Result<Wav, Error> {
+        let ssml = ssml.into();
+        self.inner
+            .client
+            .run(move |vv| vv.tts_ssml(ssml, style_id, opts))
+            .await
+    }
+
+    /// See [`VoiceVox::audio_query`].
+    pub async fn audio_query(
+        &self,
+        text: impl Into<String>,
+        style_id: StyleId,
+        opts: TtsOptions,
+    ) -> Result<AudioQuery, Error> {
+        let text = text.into();
+        self.inner
+            .client
+            .run(move |vv| vv.audio_query(text, style_id, opts))
+            .await
+    }
+
+    /// See [`VoiceVox::synthesis`].
+    pub async fn synthesis(
+        &self,
+        query: &AudioQuery,
+        style_id: StyleId,
+        opts: TtsOptions,
+    ) -> Result<WavBytes, Error> {
+        let query = query.clone();
+        self.inner
+            .client
+            .run(move |vv| vv.synthesis(&query, style_id, opts))
+            .await
+    }
+}
@@ -0,0 +1,389 @@
+use std::{
+    ops::{Range, RangeInclusive},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The intermediate representation voicevox synthesizes speech from.
+///
+/// Created with [`VoiceVox::audio_query`](crate::VoiceVox::audio_query), can be modified
+/// and then passed to [`VoiceVox::synthesis`](crate::VoiceVox::synthesis).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioQuery {
+    pub accent_phrases: Vec<AccentPhrase>,
+    /// Speed of the speech, `1.0` is normal speed.
+    pub speed_scale: f32,
+    /// Pitch shift of the speech, `0.0` is no shift.
+    pub pitch_scale: f32,
+    /// Strength of the intonation, `1.0` is normal intonation.
+    pub intonation_scale: f32,
+    /// Volume of the speech, `1.0` is normal volume.
+    pub volume_scale: f32,
+    /// Length of the silence before the speech in seconds.
+    pub pre_phoneme_length: f32,
+    /// Length of the silence after the speech in seconds.
+    pub post_phoneme_length: f32,
+    pub output_sampling_rate: u32,
+    pub output_stereo: bool,
+    /// The query in AquesTalk-style kana notation.
+    #[serde(default)]
+    pub kana: String,
+}
+
+/// A phoneme and when it is spoken, see [`AudioQuery::labels`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhonemeLabel {
+    /// The phoneme as voicevox names it, e.g. `k`, `a`, `N` or `pau`.
+    /// Unvoiced vowels are upper case.
+    pub phoneme: String,
+    /// When the phoneme starts, relative to the start of the speech.
+    pub start: Duration,
+    /// When the phoneme ends, relative to the start of the speech.
+    pub end: Duration,
+}
+
+/// Times in HTK label files are in units of 100 ns.
+fn htk_time(time: Duration) -> u128 {
+    (time.as_nanos() + 50) / 100
+}
+
+/// Voicevox synthesizes 256 samples at 24 kHz per frame, phonemes last whole frames.
+const FRAMES_PER_SECOND: f64 = 24000.0 / 256.0;
+
+impl AudioQuery {
+    /// The length of the speech synthesized from this query, computed from the lengths of its
+    /// phonemes without synthesizing it.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use voicevox_dyn::{AccentPhrase, AudioQuery, Mora};
+    ///
+    /// let mora = Mora {
+    ///     text: "ア".to_owned(),
+    ///     consonant: None,
+    ///     consonant_length: None,
+    ///     vowel: "a".to_owned(),
+    ///     vowel_length: 0.2,
+    ///     pitch: 5.5,
+    /// };
+    /// let query = AudioQuery {
+    ///     accent_phrases: vec![AccentPhrase {
+    ///         moras: vec![mora],
+    ///         accent: 1,
+    ///         pause_mora: None,
+    ///         is_interrogative: false,
+    ///     }],
+    ///     speed_scale: 2.0,
+    ///     pitch_scale: 0.0,
+    ///     intonation_scale: 1.0,
+    ///     volume_scale: 1.0,
+    ///     pre_phoneme_length: 0.1,
+    ///     post_phoneme_length: 0.1,
+    ///     output_sampling_rate: 24000,
+    ///     output_stereo: false,
+    ///     kana: String::new(),
+    /// };
+    /// assert!(query.duration().abs_diff(Duration::from_millis(200)) < Duration::from_millis(20));
+    /// ```
+    pub fn duration(&self) -> Duration {
+        self.labels()
+            .last()
+            .map_or(Duration::ZERO, |label| label.end)
+    }
+
+    /// When each phoneme is spoken, including the pauses `pau` before, after and within
+    /// the speech.
+    pub fn labels(&self) -> Vec<PhonemeLabel> {
+        let moras = self
+            .accent_phrases
+            .iter()
+            .flat_map(|phrase| phrase.moras.iter().chain(&phrase.pause_mora));
+        let phonemes = moras.flat_map(|mora| {
+            let consonant = mora.consonant.as_deref().zip(mora.consonant_length);
+            consonant
+                .into_iter()
+                .chain([(&*mora.vowel, mora.vowel_length)])
+        });
+        let phonemes = [("pau", self.pre_phoneme_length)]
+            .into_iter()
+            .chain(phonemes)
+            .chain([("pau", self.post_phoneme_length)]);
+
+        let mut frames = 0.0;
+        phonemes
+            .map(|(phoneme, length)| {
+                let start = frames;
+                frames += self.frames(length);
+                PhonemeLabel {
+                    phoneme: phoneme.to_owned(),
+                    start: Duration::from_secs_f64(start / FRAMES_PER_SECOND),
+                    end: Duration::from_secs_f64(frames / FRAMES_PER_SECOND),
+                }
+            })
+            .collect()
+    }
+
+    /// Formats [`AudioQuery::labels`] as an HTK label file (`.lab`), as used by lip sync tools
+    /// and HTS, with one `start end phoneme` line per phoneme in units of 100 ns.
+    ///
+    /// ```
+    /// # use voicevox_dyn::{AccentPhrase, AudioQuery, Mora};
+    /// # let mora = Mora {
+    /// #     text: "カ".to_owned(),
+    /// #     consonant: Some("k".to_owned()),
+    /// #     consonant_length: Some(0.064),
+    /// #     vowel: "a".to_owned(),
+    /// #     vowel_length: 0.128,
+    /// #     pitch: 5.5,
+    /// # };
+    /// # let query = AudioQuery {
+    /// #     accent_phrases: vec![AccentPhrase {
+    /// #         moras: vec![mora],
+    /// #         accent: 1,
+    /// #         pause_mora: None,
+    /// #         is_interrogative: false,
+    /// #     }],
+    /// #     speed_scale: 1.0,
+    /// #     pitch_scale: 0.0,
+    /// #     intonation_scale: 1.0,
+    /// #     volume_scale: 1.0,
+    /// #     pre_phoneme_length: 0.1,
+    /// #     post_phoneme_length: 0.1,
+    /// #     output_sampling_rate: 24000,
+    /// #     output_stereo: false,
+    /// #     kana: String::new(),
+    /// # };
+    /// // The speech of "カ" with 0.1 seconds of silence around it, rounded to whole frames.
+    /// assert_eq!(
+    ///     query.to_lab(),
+    ///     "0 960000 pau\n960000 1600000 k\n1600000 2880000 a\n2880000 3840000 pau\n"
+    /// );
+    /// ```
+    pub fn to_lab(&self) -> String {
+        self.labels()
+            .iter()
+            .map(|label| {
+                format!(
+                    "{} {} {}\n",
+                    htk_time(label.start),
+                    htk_time(label.end),
+                    label.phoneme
+                )
+            })
+            .collect()
+    }
+
+    /// When each accent phrase is spoken, relative to the start of the synthesized speech,
+    /// excluding the pause after it.
+    ///
+    /// Accent phrases are roughly words with their particles, so this allows highlighting
+    /// the text while it is spoken.
+    pub fn accent_phrase_times(&self) -> Vec<Range<Duration>> {
+        let mut frames = self.frames(self.pre_phoneme_length);
+        self.accent_phrases
+            .iter()
+            .map(|phrase| {
+                let start = frames;
+                for mora in &phrase.moras {
+                    frames += self.frames(mora.consonant_length.unwrap_or(0.0));
+                    frames += self.frames(mora.vowel_length);
+                }
+                let end = frames;
+                if let Some(pause) = &phrase.pause_mora {
+                    frames += self.frames(pause.vowel_length);
+                }
+                Duration::from_secs_f64(start / FRAMES_PER_SECOND)
+                    ..Duration::from_secs_f64(end / FRAMES_PER_SECOND)
+            })
+            .collect()
+    }
+
+    /// The number of frames a phoneme of `length` seconds lasts.
+    fn frames(&self, length: f32) -> f64 {
+        (length as f64 / self.speed_scale as f64 * FRAMES_PER_SECOND).round()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccentPhrase {
+    pub moras: Vec<Mora>,
+    /// Index of the accented mora, starting at 1.
+    pub accent: usize,
+    /// The pause following this accent phrase, if any.
+    pub pause_mora: Option<Mora>,
+    #[serde(default)]
+    pub is_interrogative: bool,
+}
+
+impl AccentPhrase {
+    /// Shifts the pitch of the voiced moras by `semitones`, see [`Mora::shift_pitch`].
+    pub fn shift_pitch(&mut self, semitones: f32) {
+        for mora in &mut self.moras {
+            mora.shift_pitch(semitones);
+        }
+    }
+
+    /// Sets the pitch of the voiced moras to their average, which makes the phrase monotone.
+    pub fn flatten(&mut self) {
+        let pitches: Vec<f32> = self.voiced().map(|mora| mora.pitch).collect();
+        if pitches.is_empty() {
+            return;
+        }
+        let average = pitches.iter().sum::<f32>() / pitches.len() as f32;
+        for mora in &mut self.moras {
+            if mora.is_voiced() {
+                mora.set_pitch(average);
+            }
+        }
+    }
+
+    /// Flattens the phrase and then shifts each voiced mora by the semitones of `contour` at
+    /// its position.
+    ///
+    /// The contour is stretched over the whole phrase and interpolated between its points,
+    /// so `&[0.0, 3.0, 0.0]` rises by three semitones towards the middle of the phrase and
+    /// falls back at the end, however many moras it has.
+    ///
+    /// ```
+    /// use voicevox_dyn::{AccentPhrase, Mora};
+    ///
+    /// let mora = |pitch| Mora {
+    ///     text: "ア".to_owned(),
+    ///     consonant: None,
+    ///     consonant_length: None,
+    ///     vowel: "a".to_owned(),
+    ///     vowel_length: 0.1,
+    ///     pitch,
+    /// };
+    /// let mut phrase = AccentPhrase {
+    ///     moras: vec![mora(5.0), mora(6.0), mora(5.0)],
+    ///     accent: 2,
+    ///     pause_mora: None,
+    ///     is_interrogative: false,
+    /// };
+    /// phrase.apply_contour(&[0.0, 12.0]);
+    /// let pitches: Vec<f32> = phrase.moras.iter().map(|mora| mora.pitch).collect();
+    /// assert_eq!(pitches, [5.0 + 1.0 / 3.0, 5.0 + 1.0 / 3.0 + 2f32.ln() / 2.0, 5.0 + 1.0 / 3.0 + 2f32.ln()]);
+    /// ```
+    pub fn apply_contour(&mut self, contour: &[f32]) {
+        self.flatten();
+        let Some(&last) = contour.last() else {
+            return;
+        };
+        let steps = self.moras.len().saturating_sub(1).max(1) as f32;
+        for (i, mora) in self.moras.iter_mut().enumerate() {
+            let position = i as f32 / steps * (contour.len() - 1) as f32;
+            let index = position.floor() as usize;
+            let semitones = match contour.get(index + 1) {
+                Some(next) => {
+                    let fraction = position - index as f32;
+                    contour[index] + (next - contour[index]) * fraction
+                }
+                None => last,
+            };
+            mora.shift_pitch(semitones);
+        }
+    }
+
+    fn voiced(&self) -> impl Iterator<Item = &Mora> {
+        self.moras.iter().filter(|mora| mora.is_voiced())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mora {
+    pub text: String,
+    pub consonant: Option<String>,
+    /// Length of the consonant in seconds.
+    pub consonant_length: Option<f32>,
+    pub vowel: String,
+    /// Length of the vowel in seconds.
+    pub vowel_length: f32,
+    /// Pitch of the mora, `0.0` for unvoiced moras.
+    ///
+    /// The pitch is the natural logarithm of the frequency in Hz, so adding `ln 2` raises it
+    /// by an octave.
+    pub pitch: f32,
+}
+
+impl Mora {
+    /// The pitches the VOICEVOX editor allows for voiced moras, the pitch helpers clamp to it.
+    pub const PITCH_RANGE: RangeInclusive<f32> = 3.0..=6.5;
+
+    /// Whether the mora has a pitch, unvoiced moras and pauses do not.
+    pub fn is_voiced(&self) -> bool {
+        self.pitch > 0.0
+    }
+
+    /// Sets the pitch clamped to [`Mora::PITCH_RANGE`], leaving unvoiced moras unvoiced.
+    pub fn set_pitch(&mut self, pitch: f32) {
+        if self.is_voiced() {
+            self.pitch = pitch.clamp(*Self::PITCH_RANGE.start(), *Self::PITCH_RANGE.end());
+        }
+    }
+
+    /// Raises the pitch by `semitones`, or lowers it if negative, see [`Mora::set_pitch`].
+    pub fn shift_pitch(&mut self, semitones: f32) {
+        self.set_pitch(self.pitch + semitones / 12.0 * std::f32::consts::LN_2);
+    }
+}
+
+/// High level speech parameters, applied to an [`AudioQuery`] by
+/// [`VoiceVox::tts_with_params`](crate::VoiceVox::tts_with_params).
+///
+/// The defaults are the ones voicevox uses.
+///
+/// ```
+/// use voicevox_dyn::SpeechParams;
+///
+/// let params = SpeechParams {
+///     speed: 1.2,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeechParams {
+    /// Speed of the speech, `1.0` is normal speed.
+    pub speed: f32,
+    /// Pitch shift of the speech, `0.0` is no shift. Sensible values are around `-0.15..=0.15`.
+    pub pitch: f32,
+    /// Strength of the intonation, `1.0` is normal intonation and `0.0` is monotone.
+    pub intonation: f32,
+    /// Volume of the speech, `1.0` is normal volume.
+    pub volume: f32,
+    /// Silence before the speech.
+    pub pre_silence: Duration,
+    /// Silence after the speech.
+    pub post_silence: Duration,
+    /// Synthesizes two identical channels instead of one, see [`Wav::pan`](crate::Wav::pan)
+    /// for placing the speech in the stereo field.
+    pub stereo: bool,
+}
+
+impl Default for SpeechParams {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            pitch: 0.0,
+            intonation: 1.0,
+            volume: 1.0,
+            pre_silence: Duration::from_millis(100),
+            post_silence: Duration::from_millis(100),
+            stereo: false,
+        }
+    }
+}
+
+impl SpeechParams {
+    /// Overwrites the corresponding fields of `query`.
+    pub fn apply(&self, query: &mut AudioQuery) {
+        query.speed_scale = self.speed;
+        query.pitch_scale = self.pitch;
+        query.intonation_scale = self.intonation;
+        query.volume_scale = self.volume;
+        query.pre_phoneme_length = self.pre_silence.as_secs_f32();
+        query.post_phoneme_length = self.post_silence.as_secs_f32();
+        query.output_stereo = self.stereo;
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    collections::HashMap,
+    future::poll_fn,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    task::{Poll, Waker},
+};
+
+use crate::{Error, StyleId, SynthesisOptions, VoiceVox, VoiceVoxBuilder, VoiceVoxHandle, Wav};
+
+impl VoiceVox {
+    /// Downloads, loads and initializes voicevox on a background thread, e.g. so the window of
+    /// an app can open right away while voicevox warms up.
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, VoiceVox};
+    ///
+    /// # fn main() -> Result<(), voicevox_dyn::Error> {
+    /// let init = VoiceVox::init_in_background(VoiceVox::builder().load_models([ZUNDAMON_NORMAL]))?;
+    /// // Open the window, this call waits until voicevox is ready.
+    /// let wav = init.tts_with_options("準備できたのだ", ZUNDAMON_NORMAL, &Default::default())?;
+    /// let handle = init.wait()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn init_in_background(builder: VoiceVoxBuilder) -> Result<InitHandle, Error> {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::Loading {
+                wakers: HashMap::new(),
+                next_waiter: 0,
+            }),
+            done: Condvar::new(),
+        });
+        {
+            let shared = shared.clone();
+            std::thread::Builder::new()
+                .name("voicevox-init".to_owned())
+                .spawn(move || {
+                    let state = match builder.build() {
+                        Ok(vv) => State::Ready(vv.into_handle()),
+                        Err(e) => {
+                            error!("Initializing voicevox in the background failed: {}", e);
+                            State::Failed {
+                                message: e.to_string(),
+                                error: Some(e),
+                            }
+                        }
+                    };
+                    if let State::Loading { wakers, .. } =
+                        std::mem::replace(&mut *shared.lock(), state)
+                    {
+                        wakers.into_values().for_each(Waker::wake);
+                    }
+                    shared.done.notify_all();
+                })
+                .map_err(Error::io("failed to spawn the voicevox init thread"))?;
+        }
+        Ok(InitHandle {
+            shared,
+            queue_early_calls: true,
+        })
+    }
+}
+
+/// Voicevox being initialized on a background thread, created with
+/// [`VoiceVox::init_in_background`].
+///
+/// If initialization fails, the first call to [`InitHandle::wait`] or [`InitHandle::ready`]
+/// returns the error. Later calls and synthesis return an [`Error::Io`] with its message.
+#[derive(Debug)]
+pub struct InitHandle {
+    shared: Arc<Shared>,
+    queue_early_calls: bool,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    done: Condvar,
+}
+
+#[derive(Debug)]
+enum State {
+    Loading {
+        /// The wakers of the [`InitHandle::ready`] futures by their id.
+        wakers: HashMap<u64, Waker>,
+        next_waiter: u64,
+    },
+    Ready(VoiceVoxHandle),
+    Failed {
+        message: String,
+        /// Taken by the first caller, the others get an error with the message.
+        error: Option<Error>,
+    },
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl State {
+    /// The result of the initialization, `None` while it is still running.
+    fn result(&mut self) -> Option<Result<VoiceVoxHandle, Error>> {
+        match self {
+            State::Loading { .. } => None,
+            State::Ready(handle) => Some(Ok(handle.clone())),
+            State::Failed { message, error } => {
+                Some(Err(error.take().unwrap_or_else(|| State::failed(message))))
+            }
+        }
+    }
+
+    /// The error returned for a failed initialization once the original was taken.
+    fn failed(message: &str) -> Error {
+        Error::io(format!(
+            "Initializing voicevox in the background failed: {}",
+            message
+        ))(std::io::Error::other(message.to_owned()))
+    }
+}
+
+/// Removes the waker of a [`InitHandle::ready`] future when it is dropped, e.g. cancelled.
+struct Waiter<'a> {
+    shared: &'a Shared,
+    id: u64,
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        if let State::Loading { wakers, .. } = &mut *self.shared.lock() {
+            wakers.remove(&self.id);
+        }
+    }
+}
+
+impl InitHandle {
+    /// Whether voicevox was initialized successfully.
+    pub fn is_ready(&self) -> bool {
+        matches!(*self.shared.lock(), State::Ready(_))
+    }
+
+    /// Whether synthesizing before voicevox is ready waits for it, which is the default, or
+    /// returns [`Error::NotInitialized`].
+    pub fn queue_early_calls(mut self, queue: bool) -> Self {
+        self.queue_early_calls = queue;
+        self
+    }
+
+    /// Blocks until voicevox is initialized.
+    pub fn wait(&self) -> Result<VoiceVoxHandle, Error> {
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(result) = state.result() {
+                return result;
+            }
+            state = self
+                .shared
+                .done
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Waits until voicevox is initialized without blocking, with any async runtime.
+    pub async fn ready(&self) -> Result<VoiceVoxHandle, Error> {
+        let id = match &mut *self.shared.lock() {
+            State::Loading { next_waiter, .. } => {
+                *next_waiter += 1;
+                *next_waiter
+            }
+            _ => 0,
+        };
+        let waiter = Waiter {
+            shared: &self.shared,
+            id,
+        };
+        poll_fn(|cx| {
+            let mut state = waiter.shared.lock();
+            if let Some(result) = state.result() {
+                return Poll::Ready(result);
+            }
+            if let State::Loading { wakers, .. } = &mut *state {
+                match wakers.get_mut(&waiter.id) {
+                    Some(waker) if waker.will_wake(cx.waker()) => {}
+                    Some(waker) => *waker = cx.waker().clone(),
+                    None => {
+                        wakers.insert(waiter.id, cx.waker().clone());
+                    }
+                }
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// See [`VoiceVox::tts_with_options`]. Waits until voicevox is ready, unless disabled with
+    /// [`InitHandle::queue_early_calls`].
+    pub fn tts_with_options(
+        &self,
+        text: impl AsRef<str>,
+        style_id: StyleId,
+        opts: &SynthesisOptions,
+    ) -> Result<Wav, Error> {
+        let handle = {
+            let mut state = self.shared.lock();
+            loop {
+                match &*state {
+                    State::Ready(handle) => break handle.clone(),
+                    State::Loading { .. } if self.queue_early_calls => {
+                        state = self
+                            .shared
+                            .done
+                            .wait(state)
+                            .unwrap_or_else(PoisonError::into_inner);
+                    }
+                    // The error of a failed initialization is left for `wait`.
+                    State::Failed { message, .. } => return Err(State::failed(message)),
+                    State::Loading { .. } => return Err(Error::NotInitialized),
+                }
+            }
+        };
+        handle.tts_with_options(text, style_id, opts)
+    }
+}
@@ -0,0 +1,278 @@
+//! Measures how fast voicevox synthesizes on this machine, e.g. to choose the number of CPU
+//! threads or to compare voicevox core versions.
+//!
+//! [`run`] synthesizes a [`CORPUS`] of Japanese sentences with every [`BenchSetup`] and reports
+//! the latency percentiles and the real-time factor of each.
+//!
+//! ```no_run
+//! use voicevox_dyn::{
+//!     bench::{self, BenchConfig, BenchSetup},
+//!     styles::ZUNDAMON_NORMAL,
+//!     VoiceVox,
+//! };
+//!
+//! # fn main() -> Result<(), voicevox_dyn::Error> {
+//! let mut vv = VoiceVox::quickstart(ZUNDAMON_NORMAL)?;
+//! let config = BenchConfig::new(ZUNDAMON_NORMAL)
+//!     .iterations(3)
+//!     .setup(BenchSetup::default().cpu_num_threads(2))
+//!     .setup(BenchSetup::default().cpu_num_threads(4));
+//! let report = bench::run(&mut vv, &config)?;
+//! print!("{}", report);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use crate::{AccelerationMode, Error, StyleId, TtsOptions, VoiceVox, Wav};
+
+/// The sentences synthesized by default, from a short reply to a long explanation, so that
+/// both the fixed cost of a call and the cost per character show up.
+pub const CORPUS: &[&str] = &[
+    "はい。",
+    "こんにちは。",
+    "今日はいい天気ですね。",
+    "明日の会議は午前十時から始まります。",
+    "駅までの道を教えていただけますか？",
+    "音声合成の速さは、文章の長さとモデルの大きさによって変わります。",
+    "この文章は、ベンチマークのために用意された少し長めの文章で、句読点をいくつか含んでいます。",
+    "吾輩は猫である。名前はまだ無い。どこで生れたかとんと見当がつかぬ。何でも薄暗いじめじめした所でニャーニャー泣いていた事だけは記憶している。",
+];
+
+/// What [`run`] synthesizes and with which settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchConfig {
+    style_id: StyleId,
+    corpus: Vec<String>,
+    iterations: usize,
+    warmup: bool,
+    setups: Vec<BenchSetup>,
+}
+
+/// Settings voicevox is initialized with for a run of the corpus, `None` keeping the one it was
+/// initialized with before [`run`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BenchSetup {
+    pub acceleration_mode: Option<AccelerationMode>,
+    pub cpu_num_threads: Option<u16>,
+}
+
+/// The results of [`run`], one for each [`BenchSetup`].
+///
+/// Displays as a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchReport {
+    pub results: Vec<BenchResult>,
+}
+
+/// The measurements of a [`BenchSetup`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    pub setup: BenchSetup,
+    /// Whether inference ran on the CPU or GPU, which [`AccelerationMode::Auto`] decides.
+    pub acceleration: Option<AccelerationMode>,
+    /// The number of CPU threads voicevox was initialized with, `0` letting it decide.
+    pub cpu_num_threads: u16,
+    /// The number of syntheses measured.
+    pub samples: usize,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+    /// The time spent synthesizing divided by the duration of the synthesized audio. Below 1
+    /// synthesizes faster than the audio plays.
+    pub real_time_factor: f64,
+}
+
+impl BenchConfig {
+    /// Synthesizes [`CORPUS`] with `style_id` once with the current settings, after warming
+    /// up.
+    pub fn new(style_id: StyleId) -> Self {
+        Self {
+            style_id,
+            corpus: CORPUS.iter().map(|&text| text.to_owned()).collect(),
+            iterations: 1,
+            warmup: true,
+            setups: Vec::new(),
+        }
+    }
+
+    /// The texts to synthesize instead of [`CORPUS`].
+    pub fn corpus(mut self, corpus: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.corpus = corpus.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// How often every text is synthesized, 1 by default.
+    pub fn iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Whether [`VoiceVox::warmup`] runs before measuring a setup, true by default, so the
+    /// first synthesis does not distort the results.
+    pub fn warmup(mut self, warmup: bool) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    /// Adds a setup to measure. Without any, only the current settings are measured.
+    pub fn setup(mut self, setup: BenchSetup) -> Self {
+        self.setups.push(setup);
+        self
+    }
+}
+
+impl BenchSetup {
+    pub fn acceleration_mode(mut self, acceleration_mode: AccelerationMode) -> Self {
+        self.acceleration_mode = Some(acceleration_mode);
+        self
+    }
+
+    pub fn cpu_num_threads(mut self, cpu_num_threads: u16) -> Self {
+        self.cpu_num_threads = Some(cpu_num_threads);
+        self
+    }
+}
+
+/// Synthesizes the corpus of `config` with every setup and measures how long each synthesis
+/// takes.
+///
+/// Changing the settings initializes voicevox again like [`VoiceVox::set_cpu_threads`], which
+/// affects every [`VoiceVox`] loaded from the same library. The previous settings are restored
+/// afterwards. Syntheses bypass the [`SynthesisCache`](crate::SynthesisCache).
+pub fn run(vv: &mut VoiceVox, config: &BenchConfig) -> Result<BenchReport, Error> {
+    if config.corpus.is_empty() || config.iterations == 0 {
+        return Err(Error::InvalidInput(
+            "the benchmark needs at least one text and iteration".to_owned(),
+        ));
+    }
+    vv.check_initialized()?;
+    let previous = vv
+        .init_settings(|settings| (settings.acceleration_mode, settings.cpu_num_threads))
+        .ok_or(Error::NotInitialized)?;
+
+    let default_setup = [BenchSetup::default()];
+    let setups = if config.setups.is_empty() {
+        &default_setup[..]
+    } else {
+        &config.setups
+    };
+    let mut results = Vec::with_capacity(setups.len());
+    let mut result = Ok(());
+    for &setup in setups {
+        let settings = (
+            setup
+                .acceleration_mode
+                .map_or(previous.0, AccelerationMode::as_raw),
+            setup.cpu_num_threads.unwrap_or(previous.1),
+        );
+        match apply(vv, settings).and_then(|()| measure(vv, config, setup)) {
+            Ok(measured) => results.push(measured),
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+
+    if vv.is_initialized() {
+        let restored = apply(vv, previous);
+        result = result.and(restored);
+    }
+    result.map(|()| BenchReport { results })
+}
+
+/// Initializes voicevox again with the raw acceleration mode and number of CPU threads of
+/// `settings` unless it already uses them.
+fn apply(vv: &mut VoiceVox, settings: (i32, u16)) -> Result<(), Error> {
+    let current =
+        vv.init_settings(|settings| (settings.acceleration_mode, settings.cpu_num_threads));
+    if current == Some(settings) {
+        return Ok(());
+    }
+    info!(
+        "Initializing voicevox again with acceleration mode {} and {} CPU threads for the benchmark.",
+        settings.0, settings.1
+    );
+    vv.reconfigure(AccelerationMode::from_raw(settings.0), Some(settings.1))
+}
+
+fn measure(vv: &VoiceVox, config: &BenchConfig, setup: BenchSetup) -> Result<BenchResult, Error> {
+    if config.warmup {
+        vv.warmup(config.style_id)?;
+    }
+
+    let mut latencies = Vec::with_capacity(config.corpus.len() * config.iterations);
+    let mut audio = Duration::ZERO;
+    for _ in 0..config.iterations {
+        for text in &config.corpus {
+            let start = Instant::now();
+            let output = vv.tts_raw(text, config.style_id, TtsOptions::default())?;
+            latencies.push(start.elapsed());
+            audio += Wav::parse_output(&output)?.duration();
+        }
+    }
+
+    let total: Duration = latencies.iter().sum();
+    latencies.sort_unstable();
+    let result = BenchResult {
+        setup,
+        acceleration: vv.info().acceleration,
+        cpu_num_threads: vv
+            .init_settings(|settings| settings.cpu_num_threads)
+            .unwrap_or_default(),
+        samples: latencies.len(),
+        mean: total / latencies.len() as u32,
+        p50: percentile(&latencies, 50),
+        p90: percentile(&latencies, 90),
+        p99: percentile(&latencies, 99),
+        max: latencies[latencies.len() - 1],
+        real_time_factor: total.as_secs_f64() / audio.as_secs_f64().max(f64::EPSILON),
+    };
+    debug!("Benchmark result: {:?}", result);
+    Ok(result)
+}
+
+/// The nearest-rank percentile of the sorted, non-empty `latencies`.
+fn percentile(latencies: &[Duration], percent: usize) -> Duration {
+    let rank = (latencies.len() * percent).div_ceil(100).max(1);
+    latencies[rank - 1]
+}
+
+impl std::fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<8} {:>7} {:>9} {:>9} {:>9} {:>9} {:>9} {:>6}",
+            "device", "threads", "mean", "p50", "p90", "p99", "max", "rtf"
+        )?;
+        for result in &self.results {
+            let device = match result.acceleration {
+                Some(AccelerationMode::Gpu) => "gpu",
+                Some(_) => "cpu",
+                None => "-",
+            };
+            let threads = match result.cpu_num_threads {
+                0 => "auto".to_owned(),
+                threads => threads.to_string(),
+            };
+            let ms = |duration: Duration| format!("{:.1}ms", duration.as_secs_f64() * 1000.0);
+            writeln!(
+                f,
+                "{:<8} {:>7} {:>9} {:>9} {:>9} {:>9} {:>9} {:>6.3}",
+                device,
+                threads,
+                ms(result.mean),
+                ms(result.p50),
+                ms(result.p90),
+                ms(result.p99),
+                ms(result.max),
+                result.real_time_factor
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+/// Writes bits most significant bit first.
+pub(crate) struct BitWriter {
+    bytes: Vec<u8>,
+    current: u64,
+    len: u32,
+}
+
+impl BitWriter {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            bytes: Vec::with_capacity(capacity),
+            current: 0,
+            len: 0,
+        }
+    }
+
+    /// Writes the lowest `bits` bits of `value`, `bits` must be at most 32.
+    pub(crate) fn write(&mut self, value: u32, bits: u32) {
+        self.current = (self.current << bits) | (value as u64 & ((1 << bits) - 1));
+        self.len += bits;
+        while self.len >= 8 {
+            self.len -= 8;
+            self.bytes.push((self.current >> self.len) as u8);
+        }
+        self.current &= (1 << self.len) - 1;
+    }
+
+    /// The completely written bytes.
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Pads the written bits with zeros to the next byte.
+    pub(crate) fn align(&mut self) {
+        if self.len > 0 {
+            self.write(0, 8 - self.len);
+        }
+    }
+
+    /// Pads the written bits with zeros to the next byte and returns all bytes.
+    pub(crate) fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+
+    /// Pads the written bits with zeros to `len` bytes.
+    #[cfg(feature = "mp3")]
+    pub(crate) fn finish(self, len: usize) -> Vec<u8> {
+        let mut bytes = self.into_bytes();
+        bytes.resize(len, 0);
+        bytes
+    }
+}
@@ -0,0 +1,9 @@
+//! The blocking API, also available at the crate root.
+//!
+//! Every call blocks the current thread until voicevox is done, which takes hundreds of
+//! milliseconds for synthesis. The async API is in `nonblocking`, with the `async` feature.
+
+pub use crate::{
+    InitHandle, LongText, Pending, PendingSynthesis, SynthesisQueue, TtsActor, TtsClient,
+    TtsStream, Utterance, VoiceVox, VoiceVoxHandle, VoiceVoxPool,
+};
@@ -0,0 +1,394 @@
+use std::{ffi::OsString, path::PathBuf};
+
+use crate::{
+    download_path, download_to, find_dict_dir, library_path, AccelerationMode, Error, InitOptions,
+    ModelEvictionPolicy, StyleId, VoiceVox,
+};
+
+/// Configures downloading, loading and initializing voicevox in one place.
+///
+/// Created with [`VoiceVox::builder`].
+///
+/// ```no_run
+/// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, AccelerationMode, VoiceVox};
+///
+/// # fn main() -> Result<(), voicevox_dyn::Error> {
+/// let vv = VoiceVox::builder()
+///     .acceleration_mode(AccelerationMode::Cpu)
+///     .cpu_num_threads(4)
+///     .load_models([ZUNDAMON_NORMAL])
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct VoiceVoxBuilder {
+    dir: Option<PathBuf>,
+    version: Option<String>,
+    device: Option<Device>,
+    args: Vec<OsString>,
+    acceleration_mode: AccelerationMode,
+    cpu_num_threads: u16,
+    gpu_memory_limit_mb: Option<u32>,
+    dict_dir: Option<PathBuf>,
+    load_all_models: bool,
+    models: Vec<u32>,
+    auto_init: bool,
+    auto_load_models: bool,
+    fast_init: bool,
+    memory_profile: MemoryProfile,
+}
+
+impl Default for VoiceVoxBuilder {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            version: None,
+            device: None,
+            args: Vec::new(),
+            acceleration_mode: AccelerationMode::Auto,
+            cpu_num_threads: 0,
+            gpu_memory_limit_mb: None,
+            dict_dir: None,
+            load_all_models: false,
+            models: Vec::new(),
+            auto_init: true,
+            auto_load_models: false,
+            fast_init: false,
+            memory_profile: MemoryProfile::Default,
+        }
+    }
+}
+
+/// The runtime the voicevox downloader fetches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Device {
+    Cpu,
+    Cuda,
+    DirectMl,
+}
+
+impl Device {
+    fn as_arg(self) -> &'static str {
+        match self {
+            Device::Cpu => "cpu",
+            Device::Cuda => "cuda",
+            Device::DirectMl => "directml",
+        }
+    }
+}
+
+/// How much memory voicevox may use for models, see [`VoiceVoxBuilder::memory_profile`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MemoryProfile {
+    /// Loads the models as configured.
+    #[default]
+    Default,
+    /// Keeps only as many models loaded as the requested styles need, for low-end devices that
+    /// can not hold all models in memory. No smaller or quantized models are used, as voicevox
+    /// core 0.14 does not ship any.
+    Low,
+}
+
+/// A step of [`VoiceVoxBuilder::build_with_progress`], e.g. for a loading screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadStage {
+    /// Voicevox is being downloaded, which can take minutes. Skipped if it already was.
+    Downloading,
+    /// The core library is being loaded.
+    LoadingLibrary,
+    /// Voicevox is being initialized, which can take several seconds.
+    Initializing,
+    /// The model of `style_id` is being loaded, model `index` of `count`.
+    LoadingModel {
+        style_id: StyleId,
+        index: usize,
+        count: usize,
+    },
+    /// Voicevox is ready.
+    Done,
+}
+
+impl VoiceVoxBuilder {
+    /// The directory voicevox is downloaded to and loaded from.
+    /// Defaults to the directory of the executable.
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// The voicevox core release to download, e.g. `"0.14.4"`. Defaults to the latest release.
+    ///
+    /// Has no effect if voicevox has already been downloaded into [`VoiceVoxBuilder::dir`].
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// The runtime to download. Defaults to the CPU runtime.
+    ///
+    /// Has no effect if voicevox has already been downloaded into [`VoiceVoxBuilder::dir`].
+    pub fn device(mut self, device: Device) -> Self {
+        self.device = Some(device);
+        self
+    }
+
+    /// Additional arguments passed to the voicevox downloader.
+    ///
+    /// See [`VoiceVox::load_with_args`].
+    pub fn downloader_args<S: Into<OsString>>(mut self, args: impl IntoIterator<Item = S>) -> Self {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Defaults to [`AccelerationMode::Auto`].
+    ///
+    /// The other options of ONNX Runtime are fixed by voicevox core, see
+    /// [`InitOptions`](crate::InitOptions#onnx-runtime-options).
+    pub fn acceleration_mode(mut self, acceleration_mode: AccelerationMode) -> Self {
+        self.acceleration_mode = acceleration_mode;
+        self
+    }
+
+    /// The number of threads used for inference on the CPU.
+    /// Defaults to `0`, which lets voicevox pick a value for the current machine.
+    ///
+    /// Voicevox core 0.14 derives both the intra-op and the inter-op threads of ONNX Runtime
+    /// from this one setting, they can not be set separately.
+    pub fn cpu_num_threads(mut self, cpu_num_threads: u16) -> Self {
+        self.cpu_num_threads = cpu_num_threads;
+        self
+    }
+
+    /// Uses all cores of the machine but `cores` for inference, e.g. to leave room for the
+    /// thread pool of the app. At least one thread is used. Overrides
+    /// [`VoiceVoxBuilder::cpu_num_threads`].
+    pub fn leave_cores_free(mut self, cores: u16) -> Self {
+        let available = std::thread::available_parallelism().map_or(1, |n| n.get());
+        self.cpu_num_threads = u16::try_from(available)
+            .unwrap_or(u16::MAX)
+            .saturating_sub(cores)
+            .max(1);
+        self
+    }
+
+    /// The most GPU memory voicevox may use, e.g. on a GPU shared with other programs.
+    ///
+    /// The limit would have to be passed to the CUDA or DirectML execution provider, which
+    /// voicevox core 0.14 does not allow, so it can not be enforced. Unless voicevox runs on the
+    /// CPU with [`AccelerationMode::Cpu`], where it uses no GPU memory, [`VoiceVoxBuilder::build`]
+    /// returns [`Error::InvalidInput`] before downloading or loading anything instead of
+    /// ignoring the limit.
+    pub fn gpu_memory_limit_mb(mut self, limit: u32) -> Self {
+        self.gpu_memory_limit_mb = Some(limit);
+        self
+    }
+
+    /// The Open JTalk dictionary directory.
+    /// By default the same locations as in [`VoiceVox::init`] are searched.
+    pub fn dict_dir(mut self, dict_dir: impl Into<PathBuf>) -> Self {
+        self.dict_dir = Some(dict_dir.into());
+        self
+    }
+
+    /// Loads all models during initialization. This is expensive, see [`VoiceVox::init`].
+    pub fn load_all_models(mut self, load_all_models: bool) -> Self {
+        self.load_all_models = load_all_models;
+        self
+    }
+
+    /// Models to load after initialization.
+    pub fn load_models(mut self, speaker_ids: impl IntoIterator<Item = u32>) -> Self {
+        self.models.extend(speaker_ids);
+        self
+    }
+
+    /// Whether [`VoiceVoxBuilder::build`] initializes voicevox and loads the requested models.
+    /// Defaults to `true`.
+    ///
+    /// When disabled, [`VoiceVox::init`] and [`VoiceVox::load_model`] have to be called manually.
+    pub fn auto_init(mut self, auto_init: bool) -> Self {
+        self.auto_init = auto_init;
+        self
+    }
+
+    /// See [`VoiceVox::set_auto_load_models`]. Defaults to `false`.
+    pub fn auto_load_models(mut self, auto_load_models: bool) -> Self {
+        self.auto_load_models = auto_load_models;
+        self
+    }
+
+    /// Returns from [`VoiceVoxBuilder::build`] as soon as voicevox is initialized, loading the
+    /// models requested with [`VoiceVoxBuilder::load_models`] or
+    /// [`VoiceVoxBuilder::load_all_models`] when they are first used instead, see
+    /// [`VoiceVox::set_auto_load_models`]. Disabled by default.
+    ///
+    /// The first synthesis with every style then takes as long as loading its model, and
+    /// errors loading it are only returned then. Use [`VoiceVox::warmup`] to load a model
+    /// ahead of time without blocking the start, e.g. on another thread.
+    ///
+    /// Initialization itself can not be made faster: voicevox core 0.14 loads the Open JTalk
+    /// dictionary during initialization and reads the model files into memory when loading
+    /// them, neither can be deferred or memory-mapped through its API.
+    pub fn fast_init(mut self, fast_init: bool) -> Self {
+        self.fast_init = fast_init;
+        self
+    }
+
+    /// Defaults to [`MemoryProfile::Default`].
+    ///
+    /// With [`MemoryProfile::Low`], [`VoiceVoxBuilder::load_all_models`] is ignored and only
+    /// the models of [`VoiceVoxBuilder::load_models`] are loaded. Other styles are loaded when
+    /// they are first used, unloading the least recently used models so that no more models
+    /// stay loaded than the requested styles need, and at least one, see
+    /// [`ModelEvictionPolicy::Lru`]. Switching between the models of the requested styles
+    /// therefore does not unload anything, while using other speakers does.
+    ///
+    /// This bounds the number of models but does not make them smaller. Voicevox core 0.14 ships
+    /// one full-size variant of every model, with no reduced or quantized variants, and can only
+    /// load a model as a whole, not the parts of a single style.
+    pub fn memory_profile(mut self, memory_profile: MemoryProfile) -> Self {
+        self.memory_profile = memory_profile;
+        self
+    }
+
+    /// Downloads voicevox into [`VoiceVoxBuilder::dir`] if it is not there yet, without loading
+    /// or initializing it, and returns the directory. Useful for provisioning machines and
+    /// Docker images ahead of time.
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::{Device, VoiceVox};
+    ///
+    /// # fn main() -> Result<(), voicevox_dyn::Error> {
+    /// let dir = VoiceVox::builder()
+    ///     .dir("/opt/voicevox")
+    ///     .device(Device::Cuda)
+    ///     .download()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn download(self) -> Result<PathBuf, Error> {
+        let dir = match &self.dir {
+            Some(dir) => dir.clone(),
+            None => download_path()?,
+        };
+        if library_path(&dir).exists() {
+            debug!("Voicevox has already been downloaded into {:?}.", dir);
+        } else {
+            download_to(&dir, self.version.as_deref(), self.download_args())?;
+        }
+        Ok(dir)
+    }
+
+    /// The arguments for the voicevox downloader.
+    fn download_args(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        if let Some(version) = &self.version {
+            args.push(OsString::from("--version"));
+            args.push(OsString::from(version));
+        }
+        if let Some(device) = self.device {
+            args.push(OsString::from("--device"));
+            args.push(OsString::from(device.as_arg()));
+        }
+        args.extend(self.args.iter().cloned());
+        args
+    }
+
+    /// Downloads voicevox if necessary, loads it and, unless disabled with
+    /// [`VoiceVoxBuilder::auto_init`], initializes it and loads the requested models.
+    pub fn build(self) -> Result<VoiceVox, Error> {
+        self.build_with_progress(|_| {})
+    }
+
+    /// Same as [`VoiceVoxBuilder::build`] but calls `progress` at the start of every
+    /// [`LoadStage`].
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, VoiceVox};
+    ///
+    /// # fn main() -> Result<(), voicevox_dyn::Error> {
+    /// let vv = VoiceVox::builder()
+    ///     .load_models([ZUNDAMON_NORMAL])
+    ///     .build_with_progress(|stage| println!("{stage:?}"))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn build_with_progress(
+        self,
+        mut progress: impl FnMut(LoadStage),
+    ) -> Result<VoiceVox, Error> {
+        if let Some(limit) = self
+            .gpu_memory_limit_mb
+            .filter(|_| self.acceleration_mode != AccelerationMode::Cpu)
+        {
+            return Err(Error::InvalidInput(format!(
+                "voicevox core can not limit its GPU memory to {} MB, use the CPU or remove the \
+                 limit",
+                limit
+            )));
+        }
+        let dir = match &self.dir {
+            Some(dir) => dir.clone(),
+            None => download_path()?,
+        };
+        let args = self.download_args();
+
+        if !library_path(&dir).exists() {
+            progress(LoadStage::Downloading);
+        }
+        progress(LoadStage::LoadingLibrary);
+        let mut vv = VoiceVox::load_from(dir, self.version.as_deref(), args)?;
+        let low_memory = self.memory_profile == MemoryProfile::Low;
+        vv.set_auto_load_models(self.auto_load_models || self.fast_init || low_memory);
+        if low_memory {
+            // The styles of a speaker share a model.
+            let max_models = vv
+                .metas()
+                .iter()
+                .filter(|speaker| {
+                    speaker
+                        .styles
+                        .iter()
+                        .any(|style| self.models.contains(&style.id))
+                })
+                .count();
+            vv.set_model_eviction_policy(ModelEvictionPolicy::Lru {
+                max_models: max_models.max(1),
+            });
+        }
+        if !self.auto_init {
+            progress(LoadStage::Done);
+            return Ok(vv);
+        }
+
+        let dict_dir = match self.dict_dir {
+            Some(dict_dir) => dict_dir,
+            None => find_dict_dir(Some(&vv.dir))?,
+        };
+        progress(LoadStage::Initializing);
+        vv.init_with_options(InitOptions::with_dict_dir(
+            self.acceleration_mode,
+            self.cpu_num_threads,
+            self.load_all_models && !self.fast_init && !low_memory,
+            dict_dir,
+        )?)?;
+        if self.fast_init {
+            debug!("Loading {} models on demand.", self.models.len());
+            progress(LoadStage::Done);
+            return Ok(vv);
+        }
+
+        let count = self.models.len();
+        vv.load_models_with_progress(&self.models, |index, style_id| {
+            progress(LoadStage::LoadingModel {
+                style_id,
+                index,
+                count,
+            })
+        })?;
+
+        progress(LoadStage::Done);
+        Ok(vv)
+    }
+}
@@ -0,0 +1,318 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use crate::{
+    text::{Normalizer, TextFilter},
+    AudioQuery, StyleId, TtsOptions,
+};
+
+/// An in-memory cache of synthesized speech, e.g. for a bot repeating the same phrases, see
+/// [`VoiceVox::set_synthesis_cache`](crate::VoiceVox::set_synthesis_cache).
+///
+/// [`VoiceVox::tts`](crate::VoiceVox::tts) and everything built on it look up the text, the
+/// style and the [`TtsOptions`] before synthesizing. The text is compared after
+/// [`Normalizer`] and trimming whitespace, so e.g. full-width and half-width digits hit the
+/// same entry. Once one of the limits is exceeded, the least recently used entries are
+/// removed.
+///
+/// ```no_run
+/// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, SynthesisCache, VoiceVox};
+///
+/// # fn main() -> Result<(), voicevox_dyn::Error> {
+/// let mut vv = VoiceVox::quickstart(ZUNDAMON_NORMAL)?;
+/// vv.set_synthesis_cache(Some(SynthesisCache::new().max_entries(500)));
+/// for _ in 0..3 {
+///     vv.tts("了解です", ZUNDAMON_NORMAL, Default::default())?;
+/// }
+/// let stats = vv.synthesis_cache().unwrap().stats();
+/// assert_eq!((stats.hits, stats.misses), (2, 1));
+/// # Ok(())
+/// # }
+/// ```
+pub struct SynthesisCache {
+    lru: Mutex<Lru<CacheKey, Vec<u8>>>,
+}
+
+/// How a [`SynthesisCache`] or an [`AudioQueryCache`] is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// The number of lookups that found an entry.
+    pub hits: u64,
+    /// The number of lookups that did not find an entry.
+    pub misses: u64,
+    /// The number of entries in the cache.
+    pub entries: usize,
+    /// The size of all entries, only counted by a [`SynthesisCache`].
+    pub bytes: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct CacheKey {
+    text: String,
+    style_id: StyleId,
+    kana: bool,
+    enable_interrogative_upspeak: bool,
+}
+
+impl CacheKey {
+    /// The key of the speech synthesized from `text`.
+    pub(crate) fn synthesis(text: &str, style_id: StyleId, opts: TtsOptions) -> Self {
+        Self::new(text, style_id, opts.kana, opts.enable_interrogative_upspeak)
+    }
+
+    /// The key of the audio query of `text`, which does not depend on
+    /// [`TtsOptions::enable_interrogative_upspeak`].
+    pub(crate) fn audio_query(text: &str, style_id: StyleId, opts: TtsOptions) -> Self {
+        Self::new(text, style_id, opts.kana, false)
+    }
+
+    fn new(text: &str, style_id: StyleId, kana: bool, enable_interrogative_upspeak: bool) -> Self {
+        let text = text.trim();
+        // Normalizing could change the meaning of kana notation.
+        let text = if kana {
+            text.to_owned()
+        } else {
+            Normalizer::new().filter(text).into_owned()
+        };
+        Self {
+            text,
+            style_id,
+            kana,
+            enable_interrogative_upspeak,
+        }
+    }
+}
+
+impl Default for SynthesisCache {
+    fn default() -> Self {
+        Self {
+            lru: Mutex::new(Lru::new(1000, 64 * 1024 * 1024)),
+        }
+    }
+}
+
+impl std::fmt::Debug for SynthesisCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SynthesisCache")
+            .field("stats", &self.stats())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SynthesisCache {
+    /// A cache of at most 1000 entries and 64 MiB.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The maximum number of entries, 1000 by default.
+    pub fn max_entries(mut self, entries: usize) -> Self {
+        self.lru
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .max_entries = entries;
+        self
+    }
+
+    /// The maximum size of all entries in bytes, 64 MiB by default. Wavs larger than this are
+    /// not cached.
+    pub fn max_bytes(mut self, bytes: usize) -> Self {
+        self.lru
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .max_bytes = bytes;
+        self
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.lock().stats()
+    }
+
+    /// Removes every entry, keeping the statistics.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    pub(crate) fn get(&self, key: &CacheKey) -> Option<Vec<u8>> {
+        self.lock().get(key).cloned()
+    }
+
+    pub(crate) fn insert(&self, key: CacheKey, wav: Vec<u8>) {
+        let size = wav.len();
+        self.lock().insert(key, wav, size);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Lru<CacheKey, Vec<u8>>> {
+        self.lru.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// An in-memory cache of [`AudioQuery`]s, see
+/// [`VoiceVox::set_audio_query_cache`](crate::VoiceVox::set_audio_query_cache).
+///
+/// [`VoiceVox::audio_query`](crate::VoiceVox::audio_query) and everything built on it look up
+/// the text, the style and [`TtsOptions::kana`] before analyzing the text, which is most of
+/// the work of creating a query. This makes synthesizing the same text again with different
+/// parameters, e.g. to compare speeds, take only the synthesis. The text is compared like in a
+/// [`SynthesisCache`]. Once the limit is exceeded, the least recently used entries are removed.
+///
+/// ```no_run
+/// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, AudioQueryCache, VoiceVox};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut vv = VoiceVox::quickstart(ZUNDAMON_NORMAL)?;
+/// vv.set_audio_query_cache(Some(AudioQueryCache::new()));
+/// for speed in [0.8, 1.0, 1.2] {
+///     let mut query = vv.audio_query("こんにちは", ZUNDAMON_NORMAL, Default::default())?;
+///     query.speed_scale = speed;
+///     let wav = vv.synthesis(&query, ZUNDAMON_NORMAL, Default::default())?;
+///     std::fs::write(format!("speed-{speed}.wav"), wav.as_slice())?;
+/// }
+/// assert_eq!(vv.audio_query_cache().unwrap().stats().hits, 2);
+/// # Ok(())
+/// # }
+/// ```
+pub struct AudioQueryCache {
+    lru: Mutex<Lru<CacheKey, AudioQuery>>,
+}
+
+impl Default for AudioQueryCache {
+    fn default() -> Self {
+        Self {
+            lru: Mutex::new(Lru::new(1000, usize::MAX)),
+        }
+    }
+}
+
+impl std::fmt::Debug for AudioQueryCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AudioQueryCache")
+            .field("stats", &self.stats())
+            .finish_non_exhaustive()
+    }
+}
+
+impl AudioQueryCache {
+    /// A cache of at most 1000 queries.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The maximum number of queries, 1000 by default.
+    pub fn max_entries(mut self, entries: usize) -> Self {
+        self.lru
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .max_entries = entries;
+        self
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.lock().stats()
+    }
+
+    /// Removes every query, keeping the statistics.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    pub(crate) fn get(&self, key: &CacheKey) -> Option<AudioQuery> {
+        self.lock().get(key).cloned()
+    }
+
+    pub(crate) fn insert(&self, key: CacheKey, query: AudioQuery) {
+        self.lock().insert(key, query, 0);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Lru<CacheKey, AudioQuery>> {
+        self.lru.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A map that removes its least recently used entries once it exceeds its limits.
+#[derive(Debug)]
+pub(crate) struct Lru<K, V> {
+    /// The values with their size and the tick they were last used at.
+    entries: HashMap<K, (V, usize, u64)>,
+    /// The keys by the tick they were last used at, the oldest first.
+    order: BTreeMap<u64, K>,
+    tick: u64,
+    bytes: usize,
+    pub(crate) max_entries: usize,
+    pub(crate) max_bytes: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl<K: Clone + Eq + Hash, V> Lru<K, V> {
+    pub(crate) fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            bytes: 0,
+            max_entries,
+            max_bytes,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Looks up `key` and marks it as the most recently used entry.
+    pub(crate) fn get(&mut self, key: &K) -> Option<&V> {
+        self.tick += 1;
+        let Some((value, _, used)) = self.entries.get_mut(key) else {
+            self.misses += 1;
+            return None;
+        };
+        self.hits += 1;
+        let key = self
+            .order
+            .remove(used)
+            .expect("every entry has a position in the order");
+        *used = self.tick;
+        self.order.insert(self.tick, key);
+        Some(value)
+    }
+
+    /// Inserts `value` of `size` bytes, removing the least recently used entries to make room.
+    pub(crate) fn insert(&mut self, key: K, value: V, size: usize) {
+        if size > self.max_bytes || self.max_entries == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((_, old_size, used)) = self.entries.remove(&key) {
+            self.order.remove(&used);
+            self.bytes -= old_size;
+        }
+        while self.entries.len() >= self.max_entries || self.bytes + size > self.max_bytes {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some((_, old_size, _)) = self.entries.remove(&oldest) {
+                self.bytes -= old_size;
+            }
+        }
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, (value, size, self.tick));
+        self.bytes += size;
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.bytes = 0;
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+            bytes: self.bytes,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crate::Error;
+
+/// Stops synthesis that is in progress, e.g. when the user skips a line, see
+/// [`SynthesisOptions::cancellation`](crate::SynthesisOptions::cancellation).
+///
+/// Clones share the same state, so one clone can be passed to the synthesis and another one
+/// cancelled from a different thread.
+///
+/// ```
+/// use voicevox_dyn::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let clone = token.clone();
+/// clone.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns [`Error::Cancelled`] if the token was cancelled.
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        if self.is_cancelled() {
+            Err(Error::Cancelled {
+                completed: Vec::new(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
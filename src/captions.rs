@@ -0,0 +1,70 @@
+//! Captions for synthesized speech, created with [`VoiceVox::tts_with_captions`](crate::VoiceVox::tts_with_captions).
+//!
+//! ```
+//! use std::time::Duration;
+//! use voicevox_dyn::captions::{to_srt, to_vtt, CaptionSegment};
+//!
+//! let captions = [CaptionSegment {
+//!     text: "こんにちは。".to_owned(),
+//!     start: Duration::from_millis(100),
+//!     end: Duration::from_millis(1250),
+//! }];
+//! assert_eq!(to_srt(&captions), "1\n00:00:00,100 --> 00:00:01,250\nこんにちは。\n\n");
+//! assert_eq!(to_vtt(&captions), "WEBVTT\n\n00:00:00.100 --> 00:00:01.250\nこんにちは。\n\n");
+//! ```
+
+use std::{fmt::Write, time::Duration};
+
+/// A piece of text and when it is spoken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptionSegment {
+    pub text: String,
+    /// When the speech of the text starts, relative to the start of the wav.
+    pub start: Duration,
+    /// When the speech of the text ends, relative to the start of the wav.
+    pub end: Duration,
+}
+
+/// Formats the captions as a SubRip (`.srt`) file.
+pub fn to_srt(captions: &[CaptionSegment]) -> String {
+    let mut srt = String::new();
+    for (i, caption) in captions.iter().enumerate() {
+        let _ = write!(
+            srt,
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            timestamp(caption.start, ','),
+            timestamp(caption.end, ','),
+            caption.text
+        );
+    }
+    srt
+}
+
+/// Formats the captions as a WebVTT (`.vtt`) file.
+pub fn to_vtt(captions: &[CaptionSegment]) -> String {
+    let mut vtt = "WEBVTT\n\n".to_owned();
+    for caption in captions {
+        let _ = write!(
+            vtt,
+            "{} --> {}\n{}\n\n",
+            timestamp(caption.start, '.'),
+            timestamp(caption.end, '.'),
+            caption.text
+        );
+    }
+    vtt
+}
+
+/// Formats `time` as `hh:mm:ss` followed by `separator` and the milliseconds.
+fn timestamp(time: Duration, separator: char) -> String {
+    let millis = time.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
@@ -0,0 +1,159 @@
+use std::{collections::HashMap, time::Duration};
+
+use crate::{Error, SpeechParams, StyleId, SynthesisOptions, VoiceVox, Wav};
+
+/// A line of a [`Dialogue`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub style_id: StyleId,
+    pub text: String,
+    pub params: SpeechParams,
+}
+
+/// A script of lines spoken by different characters, e.g. for visual novels.
+///
+/// ```no_run
+/// use std::collections::HashMap;
+/// use voicevox_dyn::{styles, Dialogue, VoiceVox};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let cast = HashMap::from([
+///     ("ずんだもん".to_owned(), styles::ZUNDAMON_NORMAL),
+///     ("めたん".to_owned(), styles::SHIKOKU_METAN_NORMAL),
+/// ]);
+/// let dialogue = Dialogue::parse("ずんだもん: おはようなのだ。\nめたん：おはよう。", &cast)?;
+///
+/// let vv = VoiceVox::quickstart(styles::ZUNDAMON_NORMAL)?;
+/// let audio = dialogue.synthesize(&vv, &Default::default())?;
+/// for (i, wav) in audio.lines.iter().enumerate() {
+///     std::fs::write(format!("{i}.wav"), wav.to_bytes())?;
+/// }
+/// std::fs::write("dialogue.wav", audio.combined()?.to_bytes())?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dialogue {
+    lines: Vec<Line>,
+    gap: Duration,
+}
+
+impl Default for Dialogue {
+    fn default() -> Self {
+        Self {
+            lines: Vec::new(),
+            gap: Duration::from_millis(300),
+        }
+    }
+}
+
+/// The speech of a [`Dialogue`], see [`Dialogue::synthesize`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogueAudio {
+    /// The speech of every line, in the order of the lines.
+    pub lines: Vec<Wav>,
+    /// The silence between lines in [`DialogueAudio::combined`].
+    pub gap: Duration,
+}
+
+impl DialogueAudio {
+    /// Joins the lines into one track, with [`DialogueAudio::gap`] between them.
+    ///
+    /// Fails if the lines differ in format, e.g. because only some of them are stereo.
+    pub fn combined(&self) -> Result<Wav, Error> {
+        Wav::concat(&self.lines, self.gap)
+    }
+}
+
+impl Dialogue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a line spoken by `style_id` with the default [`SpeechParams`].
+    pub fn line(self, style_id: StyleId, text: impl Into<String>) -> Self {
+        self.line_with_params(style_id, text, SpeechParams::default())
+    }
+
+    /// Appends a line spoken by `style_id` with the given [`SpeechParams`].
+    pub fn line_with_params(
+        mut self,
+        style_id: StyleId,
+        text: impl Into<String>,
+        params: SpeechParams,
+    ) -> Self {
+        self.lines.push(Line {
+            style_id,
+            text: text.into(),
+            params,
+        });
+        self
+    }
+
+    /// The silence between lines when they are combined, 300 ms by default.
+    pub fn gap(mut self, gap: Duration) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    /// Parses a script with one `名前: セリフ` line per line, `:` may also be full-width.
+    ///
+    /// `cast` maps the names to the styles that speak their lines. Empty lines and lines
+    /// starting with `#` are skipped. Returns [`Error::InvalidInput`] naming the line if a
+    /// line has no name or the name is not in `cast`.
+    pub fn parse(script: &str, cast: &HashMap<String, StyleId>) -> Result<Self, Error> {
+        let mut dialogue = Self::new();
+        for (number, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, text) = line.split_once([':', '：']).ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "line {} has no name, expected `名前: セリフ`",
+                    number + 1
+                ))
+            })?;
+            let name = name.trim();
+            let &style_id = cast.get(name).ok_or_else(|| {
+                Error::InvalidInput(format!("line {} is spoken by unknown {name}", number + 1))
+            })?;
+            dialogue = dialogue.line(style_id, text.trim());
+        }
+        Ok(dialogue)
+    }
+
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    /// Synthesizes every line, loading the models of the styles first.
+    ///
+    /// The text of each line is prepared and its speech post-processed as configured in
+    /// `opts`, like with [`VoiceVox::tts_with_options`].
+    pub fn synthesize(
+        &self,
+        vv: &VoiceVox,
+        opts: &SynthesisOptions,
+    ) -> Result<DialogueAudio, Error> {
+        for (i, line) in self.lines.iter().enumerate() {
+            let first = self.lines[..i].iter().all(|l| l.style_id != line.style_id);
+            if first && !vv.is_model_loaded(line.style_id) {
+                vv.load_model(line.style_id)?;
+            }
+        }
+        let lines = self
+            .lines
+            .iter()
+            .map(|line| {
+                let text = opts.prepare_text(&line.text);
+                let wav = vv.tts_with_params(text, line.style_id, opts.tts(), &line.params)?;
+                opts.apply(wav.to_wav()?)
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(DialogueAudio {
+            lines,
+            gap: self.gap,
+        })
+    }
+}
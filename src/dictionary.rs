@@ -0,0 +1,361 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{text::TextFilter, write_file, Error};
+
+/// A word and how it is pronounced, see [`DictionaryManager`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserWord {
+    /// The word as it is written, e.g. `鬼滅`.
+    pub surface: String,
+    /// The pronunciation in katakana, e.g. `キメツ`.
+    pub pronunciation: String,
+}
+
+/// The file format of [`DictionaryManager::open`].
+#[derive(Default, Serialize, Deserialize)]
+struct DictionaryFile {
+    words: Vec<UserWord>,
+}
+
+/// The JSON formats [`DictionaryManager::import`] understands.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ImportFile {
+    Own(DictionaryFile),
+    /// The export of the VOICEVOX editor, words by their id with more fields than are used here.
+    Editor(HashMap<String, UserWord>),
+}
+
+/// Custom pronunciations that are stored in a JSON file and applied to the text before
+/// synthesis.
+///
+/// The dictionary is a [`TextFilter`] that replaces every word with its pronunciation,
+/// longer words first. Clones share the same words, so a dictionary added to
+/// [`SynthesisOptions::filter`](crate::SynthesisOptions::filter) picks up words added later.
+/// As the file is independent of the voicevox installation, it is kept when voicevox is
+/// downloaded again.
+///
+/// Voicevox core 0.14 has no user dictionary, its API for adding words to Open JTalk only
+/// exists since 0.15, so the words can not be loaded into the engine. Replacing them in the
+/// text has two limitations:
+/// - voicevox decides the accent of the pronunciation as it would for any katakana, the
+///   accent of a word can not be set,
+/// - a surface is also replaced where it is part of a longer word, e.g. `日本` in `日本橋`
+///   unless `日本橋` is in the dictionary too.
+///
+/// ```no_run
+/// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, DictionaryManager, SynthesisOptions, VoiceVox};
+///
+/// # fn main() -> Result<(), voicevox_dyn::Error> {
+/// let dictionary = DictionaryManager::open("words.json")?;
+/// dictionary.add("鬼滅", "キメツ")?;
+///
+/// let vv = VoiceVox::quickstart(ZUNDAMON_NORMAL)?;
+/// let opts = SynthesisOptions::new().filter(dictionary.clone());
+/// let wav = vv.tts_with_options("鬼滅の刃", ZUNDAMON_NORMAL, &opts)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DictionaryManager {
+    inner: Arc<RwLock<Dictionary>>,
+}
+
+#[derive(Debug, Default)]
+struct Dictionary {
+    /// Where changes are saved to, if anywhere.
+    path: Option<PathBuf>,
+    /// Sorted by the length of the surface, longest first.
+    words: Vec<UserWord>,
+}
+
+impl DictionaryManager {
+    /// A dictionary that is only kept in memory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the dictionary stored at `path`, or starts an empty one if the file does not
+    /// exist yet. Every change is saved to `path` right away.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file = match std::fs::read(path) {
+            Ok(json) => serde_json::from_slice(&json).map_err(|e| {
+                Error::InvalidInput(format!("invalid dictionary {}: {e}", path.display()))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => DictionaryFile::default(),
+            Err(e) => {
+                return Err(Error::io(format!("failed to read {}", path.display()))(e));
+            }
+        };
+        let dictionary = Self::new();
+        {
+            let mut inner = dictionary.write();
+            for word in file.words {
+                inner.insert(word);
+            }
+            inner.path = Some(path.to_owned());
+        }
+        Ok(dictionary)
+    }
+
+    /// Pronounces `surface` as `pronunciation`, replacing a previous pronunciation.
+    ///
+    /// The pronunciation has to be katakana, hiragana is converted to katakana. Full-width
+    /// latin letters and digits in `surface` are converted to ASCII, as the text is
+    /// normalized the same way before it is filtered.
+    pub fn add(
+        &self,
+        surface: impl Into<String>,
+        pronunciation: impl AsRef<str>,
+    ) -> Result<(), Error> {
+        let word = imported(&surface.into(), pronunciation.as_ref())?;
+        let mut inner = self.write();
+        inner.insert(word);
+        inner.save()
+    }
+
+    /// Adds the words of a dictionary exported from the VOICEVOX editor or engine, returning
+    /// how many were added.
+    ///
+    /// Understood are the JSON export of the editor, the `user.csv` of the engine with one
+    /// MeCab entry per line, simple CSV files with `surface,pronunciation` lines and the files
+    /// of [`DictionaryManager::open`]. Accents are not used, as the dictionary only replaces
+    /// words with their pronunciation. Full-width latin letters and digits in surfaces, which
+    /// the engine stores them as, are converted to ASCII.
+    ///
+    /// Nothing is added if a word is invalid, the error names it.
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::DictionaryManager;
+    ///
+    /// # fn main() -> Result<(), voicevox_dyn::Error> {
+    /// let dictionary = DictionaryManager::open("words.json")?;
+    /// let added = dictionary.import("voicevox_user_dict.json")?;
+    /// println!("imported {added} words");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn import(&self, path: impl AsRef<Path>) -> Result<usize, Error> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(Error::io(format!("failed to read {}", path.display())))?;
+        let words = if contents.trim_start().starts_with('{') {
+            let file = serde_json::from_str(&contents).map_err(|e| {
+                Error::InvalidInput(format!("invalid dictionary {}: {e}", path.display()))
+            })?;
+            let words = match file {
+                ImportFile::Own(file) => file.words,
+                ImportFile::Editor(words) => words.into_values().collect(),
+            };
+            words
+                .into_iter()
+                .map(|word| {
+                    imported(&word.surface, &word.pronunciation)
+                        .map_err(context(format!("invalid word {}", word.surface)))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            parse_csv(&contents)?
+        };
+
+        let added = words.len();
+        let mut inner = self.write();
+        for word in words {
+            inner.insert(word);
+        }
+        inner.save()?;
+        Ok(added)
+    }
+
+    /// Removes `surface`, returning whether it was in the dictionary.
+    pub fn remove(&self, surface: &str) -> Result<bool, Error> {
+        let mut inner = self.write();
+        let len = inner.words.len();
+        inner.words.retain(|word| word.surface != surface);
+        if inner.words.len() == len {
+            return Ok(false);
+        }
+        inner.save()?;
+        Ok(true)
+    }
+
+    /// The pronunciation of `surface`, if it is in the dictionary.
+    pub fn get(&self, surface: &str) -> Option<String> {
+        self.read()
+            .words
+            .iter()
+            .find(|word| word.surface == surface)
+            .map(|word| word.pronunciation.clone())
+    }
+
+    /// Every word in the dictionary.
+    pub fn words(&self) -> Vec<UserWord> {
+        let mut words = self.read().words.clone();
+        words.sort_by(|a, b| a.surface.cmp(&b.surface));
+        words
+    }
+
+    /// The file the dictionary is stored in, if any.
+    pub fn path(&self) -> Option<PathBuf> {
+        self.read().path.clone()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Dictionary> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Dictionary> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Dictionary {
+    fn insert(&mut self, word: UserWord) {
+        self.words.retain(|w| w.surface != word.surface);
+        let len = word.surface.chars().count();
+        let index = self
+            .words
+            .partition_point(|w| w.surface.chars().count() >= len);
+        self.words.insert(index, word);
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut words = self.words.clone();
+        words.sort_by(|a, b| a.surface.cmp(&b.surface));
+        let json = serde_json::to_vec_pretty(&DictionaryFile { words })
+            .map_err(|e| Error::InvalidInput(e.to_string()))?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(Error::io(format!("failed to create {}", dir.display())))?;
+        }
+        // Written next to the dictionary first, so a crash can not leave it half written.
+        let tmp = path.with_extension("json.tmp");
+        write_file(&tmp, &json)?;
+        std::fs::rename(&tmp, path)
+            .map_err(Error::io(format!("failed to write {}", path.display())))
+    }
+}
+
+impl TextFilter for DictionaryManager {
+    fn filter<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let inner = self.read();
+        if inner.words.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        let mut output = String::new();
+        // Everything before this has been copied to `output`.
+        let mut copied = 0;
+        let mut i = 0;
+        while let Some(c) = text[i..].chars().next() {
+            match inner
+                .words
+                .iter()
+                .find(|word| text[i..].starts_with(&word.surface))
+            {
+                Some(word) => {
+                    output.push_str(&text[copied..i]);
+                    output.push_str(&word.pronunciation);
+                    i += word.surface.len();
+                    copied = i;
+                }
+                None => i += c.len_utf8(),
+            }
+        }
+        if copied == 0 {
+            return Cow::Borrowed(text);
+        }
+        output.push_str(&text[copied..]);
+        Cow::Owned(output)
+    }
+}
+
+/// Parses the lines of a `user.csv` of the engine or of a `surface,pronunciation` CSV file.
+fn parse_csv(csv: &str) -> Result<Vec<UserWord>, Error> {
+    let mut words = Vec::new();
+    for (number, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line
+            .split(',')
+            .map(|field| field.trim_matches('"'))
+            .collect();
+        // MeCab entries have the pronunciation in the 13th column.
+        let (surface, pronunciation) = match fields.as_slice() {
+            [surface, _, _, _, _, _, _, _, _, _, _, _, pronunciation, ..] => {
+                (surface, pronunciation)
+            }
+            [surface, pronunciation, ..] => (surface, pronunciation),
+            _ => {
+                return Err(Error::InvalidInput(format!(
+                    "line {} has no pronunciation",
+                    number + 1
+                )))
+            }
+        };
+        match imported(surface, pronunciation) {
+            Ok(word) => words.push(word),
+            // A header such as `surface,pronunciation`.
+            Err(_) if words.is_empty() && pronunciation.is_ascii() => {}
+            Err(e) => return Err(context(format!("line {}", number + 1))(e)),
+        }
+    }
+    Ok(words)
+}
+
+/// Prefixes the message of an [`Error::InvalidInput`] with `context`.
+fn context(context: String) -> impl FnOnce(Error) -> Error {
+    move |e| match e {
+        Error::InvalidInput(message) => Error::InvalidInput(format!("{context}: {message}")),
+        e => e,
+    }
+}
+
+/// Checks a word, converting full-width ASCII in the surface to ASCII.
+fn imported(surface: &str, pronunciation: &str) -> Result<UserWord, Error> {
+    let surface: String = surface
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '！'..='～' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            c => c,
+        })
+        .collect();
+    if surface.is_empty() {
+        return Err(Error::InvalidInput("the word is empty".to_owned()));
+    }
+    Ok(UserWord {
+        surface,
+        pronunciation: to_katakana(pronunciation.trim())?,
+    })
+}
+
+/// Checks that `pronunciation` is kana and converts hiragana to katakana.
+fn to_katakana(pronunciation: &str) -> Result<String, Error> {
+    if pronunciation.is_empty() {
+        return Err(Error::InvalidInput("the pronunciation is empty".to_owned()));
+    }
+    pronunciation
+        .chars()
+        .enumerate()
+        .map(|(position, c)| match c {
+            'ァ'..='ヴ' | 'ー' => Ok(c),
+            'ぁ'..='ゔ' => Ok(char::from_u32(c as u32 + 0x60).unwrap_or(c)),
+            c => Err(Error::InvalidInput(format!(
+                "'{c}' at position {position} of the pronunciation is not kana"
+            ))),
+        })
+        .collect()
+}
@@ -0,0 +1,456 @@
+use std::{path::PathBuf, time::Duration};
+
+use crate::{AudioChunk, StyleId};
+
+#[repr(i32)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum ResultCode {
+    /// Success
+    Ok = 0,
+    /// Failed to load Open JTalk dictionary file
+    NotLoadedOpenjtalkDictError = 1,
+    /// Failed to load the model
+    LoadModelError = 2,
+    /// Failed to get supported device information
+    GetSupportedDevicesError = 3,
+    /// GPU mode is not supported
+    GpuSupportError = 4,
+    /// Failed to load meta information
+    LoadMetasError = 5,
+    /// Status is uninitialized
+    UninitializedStatusError = 6,
+    /// Invalid speaker ID specified
+    InvalidSpeakerIdError = 7,
+    /// Invalid model index specified
+    InvalidModelIndexError = 8,
+    /// Inference failed
+    InferenceError = 9,
+    /// Failed to output context labels
+    ExtractFullContextLabelError = 10,
+    /// Invalid UTF-8 string input
+    InvalidUtf8InputError = 11,
+    /// Failed to parse Aquestalk-style text
+    ParseKanaError = 12,
+    /// Invalid AudioQuery
+    InvalidAudioQueryError = 13,
+}
+
+impl std::fmt::Display for ResultCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ResultCode::Ok => "Success",
+            ResultCode::NotLoadedOpenjtalkDictError => "Failed to load Open JTalk dictionary file",
+            ResultCode::LoadModelError => "Failed to load the model",
+            ResultCode::GetSupportedDevicesError => "Failed to get supported device information",
+            ResultCode::GpuSupportError => "GPU mode is not supported",
+            ResultCode::LoadMetasError => "Failed to load meta information",
+            ResultCode::UninitializedStatusError => "Status is uninitialized",
+            ResultCode::InvalidSpeakerIdError => "Invalid speaker ID specified",
+            ResultCode::InvalidModelIndexError => "Invalid model index specified",
+            ResultCode::InferenceError => "Inference failed",
+            ResultCode::ExtractFullContextLabelError => "Failed to output context labels",
+            ResultCode::InvalidUtf8InputError => "Invalid UTF-8 string input",
+            ResultCode::ParseKanaError => "Failed to parse Aquestalk-style text",
+            ResultCode::InvalidAudioQueryError => "Invalid AudioQuery",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::error::Error for ResultCode {}
+
+/// Errors returned when calling into voicevox.
+///
+/// The variants categorize where an error originates, so callers can react to them
+/// differently. [`Error::is_retryable`] tells whether retrying the operation may succeed.
+#[derive(Debug)]
+pub enum Error {
+    /// Downloading voicevox failed.
+    Download(DownloadError),
+    /// The voicevox core library could not be loaded.
+    LibraryLoad(LibraryLoadError),
+    /// A model was loaded or speech synthesized before [`VoiceVox::init`](crate::VoiceVox::init)
+    /// was called.
+    NotInitialized,
+    /// The voicevox core returned an error.
+    Engine(ResultCode),
+    /// Some models could not be loaded, see [`VoiceVox::load_models`](crate::VoiceVox::load_models).
+    ModelLoad(ModelLoadReport),
+    /// The style id is not part of the metas of the loaded voicevox core.
+    UnknownStyle {
+        style_id: StyleId,
+        /// `(style id, "speaker (style)")` for every available style, sorted by style id.
+        available: Vec<(StyleId, String)>,
+    },
+    /// The input can not be passed to the voicevox core,
+    /// for example because the text contains a NUL byte.
+    InvalidInput(String),
+    /// The voicevox core returned data that could not be understood.
+    InvalidOutput(String),
+    /// The Open JTalk dictionary was not found in any of the searched locations,
+    /// see [`VoiceVox::init`](crate::VoiceVox::init).
+    DictionaryNotFound { tried: Vec<PathBuf> },
+    /// Encoding audio into a compressed format failed.
+    Encode(Box<dyn std::error::Error + Send + Sync>),
+    /// Playing audio failed, e.g. because there is no output device.
+    /// Only returned with the `playback` feature.
+    Playback(Box<dyn std::error::Error + Send + Sync>),
+    /// An io operation outside of downloading failed,
+    /// e.g. because the Open JTalk dictionary does not exist.
+    Io {
+        context: String,
+        source: std::io::Error,
+    },
+    /// The synthesis was stopped with a
+    /// [`CancellationToken`](crate::CancellationToken).
+    Cancelled {
+        /// The chunks of a long text that were synthesized before the cancellation, in order.
+        completed: Vec<AudioChunk>,
+    },
+    /// The synthesis took longer than [`SynthesisOptions::timeout`](crate::SynthesisOptions::timeout).
+    Timeout(Duration),
+}
+
+impl Error {
+    /// Whether retrying the failed operation may succeed, e.g. because the error was
+    /// caused by the network or a transient failure during inference.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Download(e) => e.is_retryable(),
+            Error::Engine(code) => matches!(code, ResultCode::InferenceError),
+            Error::ModelLoad(report) => report.failed.iter().any(|(_, e)| e.is_retryable()),
+            Error::LibraryLoad(_)
+            | Error::NotInitialized
+            | Error::UnknownStyle { .. }
+            | Error::InvalidInput(_)
+            | Error::InvalidOutput(_)
+            | Error::DictionaryNotFound { .. }
+            | Error::Encode(_)
+            | Error::Playback(_)
+            | Error::Io { .. }
+            | Error::Cancelled { .. }
+            | Error::Timeout(_) => false,
+        }
+    }
+
+    /// Adds the chunks synthesized so far to [`Error::Cancelled`], other errors are returned
+    /// unchanged.
+    pub(crate) fn with_completed(self, completed: Vec<AudioChunk>) -> Self {
+        match self {
+            Error::Cancelled { .. } => Error::Cancelled { completed },
+            e => e,
+        }
+    }
+
+    /// Returns a function wrapping an io error with the given context, for use with `map_err`.
+    pub(crate) fn io(context: impl Into<String>) -> impl FnOnce(std::io::Error) -> Self {
+        let context = context.into();
+        move |source| Error::Io { context, source }
+    }
+
+    /// Converts the error into a [`color_eyre::Report`] with suggestions on how to fix it.
+    #[cfg(feature = "eyre")]
+    pub fn into_report(self) -> color_eyre::Report {
+        use color_eyre::Section;
+
+        let suggestion = match &self {
+            Error::Engine(ResultCode::NotLoadedOpenjtalkDictError) => {
+                Some("make sure the Open JTalk dictionary directory exists and is complete")
+            }
+            Error::DictionaryNotFound { .. } => Some(
+                "set `VOICEVOX_OPEN_JTALK_DICT_DIR` or pass the directory to `VoiceVox::init_with_dict_dir`",
+            ),
+            Error::Engine(ResultCode::GpuSupportError) => Some(
+                "use `AccelerationMode::Cpu` or download a GPU runtime with `VoiceVoxBuilder::device`",
+            ),
+            Error::Engine(ResultCode::UninitializedStatusError) | Error::NotInitialized => {
+                Some("call `VoiceVox::init` before loading models or synthesizing speech")
+            }
+            Error::Engine(ResultCode::InvalidSpeakerIdError)
+            | Error::UnknownStyle { .. }
+            | Error::ModelLoad(_) => {
+                Some("see `model/metas.json` in the voicevox directory for the available style ids")
+            }
+            Error::Download(DownloadError::Http(_)) => Some("check your internet connection"),
+            Error::Timeout(_) => Some(
+                "restart the program if `VoiceVox::needs_reinit` stays true, the core is still busy with the synthesis",
+            ),
+            Error::LibraryLoad(_) => Some(
+                "delete the voicevox files to download them again, they may be incomplete or for another platform",
+            ),
+            _ => None,
+        };
+
+        let report = color_eyre::Report::new(self);
+        match suggestion {
+            Some(suggestion) => report.suggestion(suggestion),
+            None => report,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Download(e) => write!(f, "Failed to download voicevox: {}", e),
+            Error::LibraryLoad(e) => write!(f, "{}", e),
+            Error::NotInitialized => write!(
+                f,
+                "Voicevox is not initialized, call `VoiceVox::init` before loading models or synthesizing speech"
+            ),
+            Error::Engine(code) => write!(f, "{}", code),
+            Error::ModelLoad(report) => write!(f, "{}", report),
+            Error::UnknownStyle {
+                style_id,
+                available,
+            } => {
+                write!(f, "Unknown style id {}", style_id)?;
+                // Listing every style is too noisy, so only the closest ids are shown.
+                let mut closest: Vec<_> = available.iter().collect();
+                closest.sort_by_key(|(id, _)| id.abs_diff(*style_id));
+                closest.truncate(5);
+                closest.sort_by_key(|(id, _)| *id);
+                for (i, (id, name)) in closest.into_iter().enumerate() {
+                    let separator = if i == 0 {
+                        ", closest available styles: "
+                    } else {
+                        ", "
+                    };
+                    write!(f, "{}{} {}", separator, id, name)?;
+                }
+                Ok(())
+            }
+            Error::InvalidInput(reason) => write!(f, "Invalid input: {}", reason),
+            Error::InvalidOutput(reason) => write!(f, "Invalid output from voicevox: {}", reason),
+            Error::DictionaryNotFound { tried } => {
+                write!(f, "Could not find the Open JTalk dictionary, tried ")?;
+                for (i, path) in tried.iter().enumerate() {
+                    let separator = if i == 0 { "" } else { ", " };
+                    write!(f, "{}{:?}", separator, path)?;
+                }
+                Ok(())
+            }
+            Error::Encode(e) => write!(f, "Failed to encode audio: {}", e),
+            Error::Playback(e) => write!(f, "Failed to play audio: {}", e),
+            Error::Io { context, .. } => write!(f, "{}", context),
+            Error::Cancelled { .. } => write!(f, "The synthesis was cancelled"),
+            Error::Timeout(timeout) => write!(f, "The synthesis did not finish within {:?}", timeout),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Download(e) => e.source(),
+            Error::LibraryLoad(e) => e.source(),
+            Error::Engine(code) => Some(code),
+            Error::Io { source, .. } => Some(source),
+            Error::Encode(e) | Error::Playback(e) => Some(e.as_ref()),
+            Error::ModelLoad(_)
+            | Error::NotInitialized
+            | Error::UnknownStyle { .. }
+            | Error::InvalidInput(_)
+            | Error::InvalidOutput(_)
+            | Error::DictionaryNotFound { .. }
+            | Error::Cancelled { .. }
+            | Error::Timeout(_) => None,
+        }
+    }
+}
+
+/// See [`Error::Download`].
+#[derive(Debug)]
+pub enum DownloadError {
+    /// Requesting the voicevox downloader failed.
+    Http(Box<ureq::Error>),
+    /// Writing or running the voicevox downloader failed.
+    Io {
+        context: String,
+        source: std::io::Error,
+    },
+    /// The voicevox downloader exited unsuccessfully.
+    Downloader(std::process::ExitStatus),
+    /// Voicevox is not available for this platform.
+    UnsupportedPlatform(String),
+    /// The download directory can not be passed to the voicevox downloader.
+    InvalidPath(PathBuf),
+}
+
+impl DownloadError {
+    /// See [`Error::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            DownloadError::Http(e) => match e.as_ref() {
+                ureq::Error::Status(status, _) => *status == 429 || *status >= 500,
+                ureq::Error::Transport(_) => true,
+            },
+            // The connection may break while the downloader is written to disk,
+            // and the downloader itself fails on network errors.
+            DownloadError::Io { .. } | DownloadError::Downloader(_) => true,
+            DownloadError::UnsupportedPlatform(_) | DownloadError::InvalidPath(_) => false,
+        }
+    }
+
+    /// Returns a function wrapping an io error with the given context, for use with `map_err`.
+    pub(crate) fn io(context: impl Into<String>) -> impl FnOnce(std::io::Error) -> Error {
+        let context = context.into();
+        move |source| Error::Download(DownloadError::Io { context, source })
+    }
+}
+
+impl std::fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DownloadError::Http(e) => write!(f, "{}", e),
+            DownloadError::Io { context, .. } => write!(f, "{}", context),
+            DownloadError::Downloader(status) => {
+                write!(f, "the voicevox downloader exited with {}", status)
+            }
+            DownloadError::UnsupportedPlatform(platform) => {
+                write!(f, "voicevox is not available for {}", platform)
+            }
+            DownloadError::InvalidPath(path) => {
+                write!(f, "the download directory {:?} is not valid UTF-8", path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DownloadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DownloadError::Http(e) => Some(e),
+            DownloadError::Io { source, .. } => Some(source),
+            DownloadError::Downloader(_)
+            | DownloadError::UnsupportedPlatform(_)
+            | DownloadError::InvalidPath(_) => None,
+        }
+    }
+}
+
+/// See [`Error::LibraryLoad`].
+#[derive(Debug)]
+pub enum LibraryLoadError {
+    /// The library could not be opened, e.g. because it does not exist,
+    /// is built for another platform or one of its dependencies is missing.
+    Open {
+        path: PathBuf,
+        source: libloading::Error,
+    },
+    /// A function this crate requires is missing from the library,
+    /// usually because the library is from an incompatible voicevox core release.
+    MissingSymbol {
+        path: PathBuf,
+        symbol: &'static str,
+        source: libloading::Error,
+        /// The version the library reports, if it could be determined.
+        core_version: Option<String>,
+        /// Whether the library provides the `synthesizer` API of voicevox core 0.15 and later,
+        /// which replaced the API this crate uses.
+        synthesizer_api: bool,
+    },
+}
+
+impl std::fmt::Display for LibraryLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LibraryLoadError::Open { path, source } => {
+                write!(
+                    f,
+                    "failed to load voicevox core library {:?}: {}",
+                    path, source
+                )
+            }
+            LibraryLoadError::MissingSymbol {
+                path,
+                symbol,
+                source,
+                core_version,
+                synthesizer_api,
+            } => {
+                write!(f, "the voicevox core library {:?}", path)?;
+                if let Some(version) = core_version {
+                    write!(f, " (version {})", version)?;
+                }
+                write!(f, " is missing `{}`", symbol)?;
+                if *synthesizer_api {
+                    write!(
+                        f,
+                        "; it only provides the `synthesizer` API of voicevox core 0.15 and later, \
+                         but this crate requires the API of voicevox core 0.14. \
+                         Download voicevox core 0.14 into an empty directory instead, \
+                         e.g. with `VoiceVoxBuilder::version(\"0.14.4\")`"
+                    )
+                } else {
+                    write!(f, ", this crate requires voicevox core 0.14: {}", source)
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for LibraryLoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LibraryLoadError::Open { source, .. }
+            | LibraryLoadError::MissingSymbol { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<ureq::Error> for Error {
+    fn from(e: ureq::Error) -> Self {
+        Error::Download(DownloadError::Http(Box::new(e)))
+    }
+}
+
+impl From<DownloadError> for Error {
+    fn from(e: DownloadError) -> Self {
+        Error::Download(e)
+    }
+}
+
+impl From<LibraryLoadError> for Error {
+    fn from(e: LibraryLoadError) -> Self {
+        Error::LibraryLoad(e)
+    }
+}
+
+impl From<ModelLoadReport> for Error {
+    fn from(report: ModelLoadReport) -> Self {
+        Error::ModelLoad(report)
+    }
+}
+
+impl From<ResultCode> for Error {
+    fn from(code: ResultCode) -> Self {
+        Error::Engine(code)
+    }
+}
+
+/// The outcome of [`VoiceVox::load_models`](crate::VoiceVox::load_models) when at least one model failed to load.
+#[derive(Debug, Default)]
+pub struct ModelLoadReport {
+    /// Styles whose models were loaded successfully.
+    pub loaded: Vec<StyleId>,
+    /// Styles whose models failed to load, with the reason.
+    pub failed: Vec<(StyleId, Error)>,
+}
+
+impl std::fmt::Display for ModelLoadReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to load {} of {} models",
+            self.failed.len(),
+            self.failed.len() + self.loaded.len()
+        )?;
+        for (style_id, e) in &self.failed {
+            write!(f, "\n  style {}: {}", style_id, e)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ModelLoadReport {}
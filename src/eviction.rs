@@ -0,0 +1,170 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{Error, SpeakerMeta, StyleId, VoiceVox};
+
+/// Which models a [`VoiceVox`] unloads to keep its memory bounded, e.g. on a server with many
+/// voices, see [`VoiceVox::set_model_eviction_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModelEvictionPolicy {
+    /// Models stay loaded.
+    #[default]
+    Never,
+    /// Keeps at most `max_models` models loaded by unloading the least recently used ones
+    /// before loading another one. Styles that share a model count as one, e.g. all styles of
+    /// Zundamon.
+    Lru { max_models: usize },
+    /// Unloads the models whose styles were not used for the given time before loading another
+    /// one, or when [`VoiceVox::evict_models`] is called.
+    IdleTimeout(Duration),
+}
+
+/// When the loaded models were last used, and which styles they serve.
+#[derive(Debug, Default)]
+pub(crate) struct ModelUsage {
+    state: Mutex<UsageState>,
+}
+
+#[derive(Debug, Default)]
+struct UsageState {
+    last_used: HashMap<StyleId, Instant>,
+    /// The style whose loading loaded the model of a style.
+    model_of: HashMap<StyleId, StyleId>,
+}
+
+impl ModelUsage {
+    fn lock(&self) -> MutexGuard<'_, UsageState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Records that `style_id` was used.
+    pub(crate) fn touch(&self, style_id: StyleId) {
+        self.lock().last_used.insert(style_id, Instant::now());
+    }
+
+    /// Records that loading the model of `style_id` loaded the styles of `model`.
+    pub(crate) fn record_model(&self, style_id: StyleId, model: &[StyleId]) {
+        let mut state = self.lock();
+        for &style in model {
+            state.model_of.insert(style, style_id);
+        }
+    }
+
+    /// Groups the loaded styles of every speaker by their model. Styles whose model was loaded
+    /// without this instance, e.g. during initialization, are grouped by speaker, as voicevox
+    /// core 0.14 keeps the styles of a speaker in one model.
+    fn models(&self, speakers: &[SpeakerMeta], loaded: &[StyleId]) -> Vec<Vec<StyleId>> {
+        let state = self.lock();
+        let mut models: BTreeMap<(bool, StyleId), Vec<StyleId>> = BTreeMap::new();
+        for speaker in speakers {
+            let Some(first) = speaker.styles.first() else {
+                continue;
+            };
+            for style in speaker.styles.iter().filter(|s| loaded.contains(&s.id)) {
+                let model = match state.model_of.get(&style.id) {
+                    Some(&model) => (true, model),
+                    None => (false, first.id),
+                };
+                models.entry(model).or_default().push(style.id);
+            }
+        }
+        models.into_values().collect()
+    }
+
+    /// The models of `models` to keep under `policy`, the most recently used first. `room` is
+    /// the number of models that are about to be loaded.
+    fn keep(
+        &self,
+        policy: ModelEvictionPolicy,
+        models: Vec<Vec<StyleId>>,
+        room: usize,
+    ) -> Vec<Vec<StyleId>> {
+        let now = Instant::now();
+        let mut state = self.lock();
+        // Models loaded without this instance, e.g. during initialization, count as used now.
+        let mut models: Vec<(Vec<StyleId>, Instant)> = models
+            .into_iter()
+            .map(|styles| {
+                let used = styles
+                    .iter()
+                    .filter_map(|style_id| state.last_used.get(style_id).copied())
+                    .max()
+                    .unwrap_or_else(|| *state.last_used.entry(styles[0]).or_insert(now));
+                (styles, used)
+            })
+            .collect();
+        models.sort_by_key(|&(_, used)| std::cmp::Reverse(used));
+        let models = models.into_iter();
+        match policy {
+            ModelEvictionPolicy::Never => models.map(|(styles, _)| styles).collect(),
+            ModelEvictionPolicy::Lru { max_models } => models
+                .take(max_models.saturating_sub(room))
+                .map(|(styles, _)| styles)
+                .collect(),
+            ModelEvictionPolicy::IdleTimeout(timeout) => models
+                .filter(|(_, used)| now.duration_since(*used) < timeout)
+                .map(|(styles, _)| styles)
+                .collect(),
+        }
+    }
+}
+
+impl VoiceVox {
+    /// Unloads models as configured by `policy` to keep the memory bounded, see
+    /// [`ModelEvictionPolicy`]. Models stay loaded by default.
+    ///
+    /// Voicevox core 0.14 can not unload single models, so unloading finalizes and initializes
+    /// it again, then loads the kept models again. This takes about as long as loading the kept
+    /// models and affects every [`VoiceVox`] loaded from the same library. Use it together with
+    /// [`VoiceVox::set_auto_load_models`], so unloaded models are loaded again when needed.
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::{ModelEvictionPolicy, VoiceVox};
+    ///
+    /// # fn main() -> Result<(), voicevox_dyn::Error> {
+    /// let mut vv = VoiceVox::builder().build()?;
+    /// vv.set_auto_load_models(true);
+    /// vv.set_model_eviction_policy(ModelEvictionPolicy::Lru { max_models: 4 });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_model_eviction_policy(&mut self, policy: ModelEvictionPolicy) {
+        self.eviction_policy = policy;
+    }
+
+    /// Unloads the models that [`VoiceVox::set_model_eviction_policy`] does not keep and
+    /// returns their styles, e.g. periodically with [`ModelEvictionPolicy::IdleTimeout`].
+    ///
+    /// If initializing voicevox again fails, calls fail until it is initialized by a new
+    /// [`VoiceVox`].
+    pub fn evict_models(&self) -> Result<Vec<StyleId>, Error> {
+        self.evict_models_for(0)
+    }
+
+    /// Unloads models to make room for loading `room` more models, see
+    /// [`VoiceVox::evict_models`].
+    pub(crate) fn evict_models_for(&self, room: usize) -> Result<Vec<StyleId>, Error> {
+        if self.eviction_policy == ModelEvictionPolicy::Never {
+            return Ok(Vec::new());
+        }
+        let models = self.model_usage.models(&self.metas, &self.loaded_styles());
+        let keep: Vec<StyleId> = self
+            .model_usage
+            .keep(self.eviction_policy, models.clone(), room)
+            .concat();
+        // A style is only unloaded if no other style of its model is kept.
+        let evicted: Vec<StyleId> = models
+            .into_iter()
+            .filter(|styles| !styles.iter().any(|style_id| keep.contains(style_id)))
+            .flatten()
+            .collect();
+        if !evicted.is_empty() {
+            info!("Unloading the models of the styles {:?}.", evicted);
+            self.reinitialize(None, None, &keep)?;
+        }
+        Ok(evicted)
+    }
+}
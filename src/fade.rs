@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use crate::Wav;
+
+/// How the volume changes during [`Wav::fade_in`] and [`Wav::fade_out`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FadeCurve {
+    /// The amplitude changes at a constant rate.
+    #[default]
+    Linear,
+    /// The amplitude changes slowly at silence and quickly at full volume,
+    /// which sounds more even to the ear.
+    Exponential,
+}
+
+impl FadeCurve {
+    /// The gain at `t` in `0.0..=1.0` of the fade.
+    fn gain(self, t: f32) -> f32 {
+        /// Steepness of the exponential curve.
+        const K: f32 = 5.0;
+        match self {
+            FadeCurve::Linear => t,
+            FadeCurve::Exponential => (K * t).exp_m1() / K.exp_m1(),
+        }
+    }
+}
+
+impl Wav {
+    /// Raises the volume from silence over the first `duration`. The result is 16 bit.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use voicevox_dyn::{FadeCurve, Wav};
+    ///
+    /// let wav = Wav::from_samples_i16(1000, 1, &[1000; 6]);
+    /// let faded = wav.fade_in(Duration::from_millis(4), FadeCurve::Linear);
+    /// assert_eq!(faded.samples_i16(), [0, 250, 500, 750, 1000, 1000]);
+    /// ```
+    pub fn fade_in(&self, duration: Duration, curve: FadeCurve) -> Wav {
+        self.fade(duration, curve, false)
+    }
+
+    /// Lowers the volume to silence over the last `duration`. The result is 16 bit.
+    pub fn fade_out(&self, duration: Duration, curve: FadeCurve) -> Wav {
+        self.fade(duration, curve, true)
+    }
+
+    fn fade(&self, duration: Duration, curve: FadeCurve, out: bool) -> Wav {
+        let mut channels = self.channels_f32();
+        let len = channels[0].len();
+        let fade = ((duration.as_secs_f64() * self.sample_rate as f64).round() as usize).min(len);
+        for channel in &mut channels {
+            for i in 0..fade {
+                let gain = curve.gain(i as f32 / fade as f32);
+                if out {
+                    channel[len - 1 - i] *= gain;
+                } else {
+                    channel[i] *= gain;
+                }
+            }
+        }
+        Wav::from_samples_f32(self.sample_rate, self.channels, &Wav::interleave(&channels))
+    }
+}
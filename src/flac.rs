@@ -0,0 +1,435 @@
+//! A small lossless FLAC encoder.
+//!
+//! Every channel is coded independently with the fixed polynomial predictors of
+//! [RFC 9639](https://www.rfc-editor.org/rfc/rfc9639) and rice coded residuals,
+//! which gets speech to roughly half the size of the wav without any dependencies.
+
+use crate::{bits::BitWriter, Error, Wav};
+
+/// The number of samples per channel in a frame, the default of the reference encoder.
+const BLOCK_SIZE: usize = 4096;
+/// Rice partitions are split at most this often.
+const MAX_PARTITION_ORDER: u32 = 8;
+/// The largest rice parameter, the next one marks an escaped partition.
+const MAX_RICE_PARAMETER: u32 = 30;
+
+impl Wav {
+    /// Encodes the wav into a FLAC file, keeping the sampling rate, channels and bits per sample.
+    ///
+    /// Returns [`Error::InvalidInput`] if the wav has more than 8 channels, which FLAC does not
+    /// support.
+    ///
+    /// ```
+    /// use voicevox_dyn::Wav;
+    ///
+    /// let samples: Vec<i16> = (0..24000).map(|i| ((i as f32 / 20.0).sin() * 8000.0) as i16).collect();
+    /// let wav = Wav::from_samples_i16(24000, 1, &samples);
+    /// let flac = wav.encode_flac()?;
+    /// assert_eq!(&flac[..4], b"fLaC");
+    /// assert!(flac.len() < wav.data.len() / 2);
+    /// # Ok::<(), voicevox_dyn::Error>(())
+    /// ```
+    pub fn encode_flac(&self) -> Result<Vec<u8>, Error> {
+        if self.channels > 8 {
+            return Err(Error::InvalidInput(format!(
+                "flac supports at most 8 channels, the wav has {}",
+                self.channels
+            )));
+        }
+        if self.sample_rate >= 1 << 20 {
+            return Err(Error::InvalidInput(format!(
+                "flac does not support a sampling rate of {} Hz",
+                self.sample_rate
+            )));
+        }
+
+        let bits = self.bits_per_sample as u32;
+        let channels = Wav::deinterleave(&self.samples_native(), self.channels);
+        let total = channels[0].len();
+
+        let mut frames = Vec::new();
+        let (mut min_frame, mut max_frame) = (u32::MAX, 0);
+        for (number, start) in (0..total).step_by(BLOCK_SIZE).enumerate() {
+            let end = (start + BLOCK_SIZE).min(total);
+            let frame = self.encode_frame(number as u32, &channels, start..end);
+            min_frame = min_frame.min(frame.len() as u32);
+            max_frame = max_frame.max(frame.len() as u32);
+            frames.extend(frame);
+        }
+        if max_frame == 0 {
+            // An empty stream, the frame sizes are unknown.
+            min_frame = 0;
+        }
+
+        let mut out = BitWriter::with_capacity(42 + frames.len());
+        out.write(u32::from_be_bytes(*b"fLaC"), 32);
+        // The only metadata block is the stream info, of type 0 and 34 bytes.
+        out.write(1, 1);
+        out.write(0, 7);
+        out.write(34, 24);
+        out.write(BLOCK_SIZE as u32, 16);
+        out.write(BLOCK_SIZE as u32, 16);
+        out.write(min_frame, 24);
+        out.write(max_frame, 24);
+        out.write(self.sample_rate, 20);
+        out.write(self.channels as u32 - 1, 3);
+        out.write(bits - 1, 5);
+        out.write((total as u64 >> 32) as u32, 4);
+        out.write(total as u32, 32);
+        let mut bytes = out.into_bytes();
+        bytes.extend_from_slice(&md5(&self.md5_input()));
+        bytes.extend(frames);
+        Ok(bytes)
+    }
+
+    /// The samples at their original size, with 8 bit samples made signed.
+    fn samples_native(&self) -> Vec<i32> {
+        let data = &self.data;
+        match self.bits_per_sample {
+            8 => data.iter().map(|&b| b as i32 - 128).collect(),
+            16 => data
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]) as i32)
+                .collect(),
+            24 => data
+                .chunks_exact(3)
+                .map(|b| i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8)
+                .collect(),
+            32 => data
+                .chunks_exact(4)
+                .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+            bits => panic!("unsupported sample size of {bits} bits"),
+        }
+    }
+
+    /// The bytes the stream info checksum is computed over, which are signed samples in little endian.
+    fn md5_input(&self) -> Vec<u8> {
+        match self.bits_per_sample {
+            8 => self.data.iter().map(|&b| b ^ 0x80).collect(),
+            _ => self.data.clone(),
+        }
+    }
+
+    fn encode_frame(
+        &self,
+        number: u32,
+        channels: &[Vec<i32>],
+        range: std::ops::Range<usize>,
+    ) -> Vec<u8> {
+        let block_size = range.len();
+        let bits = self.bits_per_sample as u32;
+        let mut writer = BitWriter::with_capacity(block_size * self.block_align() as usize + 16);
+
+        // Sync code and fixed block size.
+        writer.write(0xFFF8, 16);
+        let (rate_code, rate_extra) = sample_rate_code(self.sample_rate);
+        // The block size follows the frame number as 16 bits.
+        writer.write(0b0111, 4);
+        writer.write(rate_code, 4);
+        writer.write(self.channels as u32 - 1, 4);
+        writer.write(sample_size_code(bits), 3);
+        writer.write(0, 1);
+        write_utf8(&mut writer, number);
+        writer.write(block_size as u32 - 1, 16);
+        if let Some((value, bits)) = rate_extra {
+            writer.write(value, bits);
+        }
+        let crc = crc8(writer.bytes());
+        writer.write(crc as u32, 8);
+
+        for channel in channels {
+            write_subframe(&mut writer, &channel[range.clone()], bits);
+        }
+        writer.align();
+        let crc = crc16(writer.bytes());
+        writer.write(crc as u32, 16);
+        writer.into_bytes()
+    }
+}
+
+fn write_subframe(writer: &mut BitWriter, samples: &[i32], bits: u32) {
+    // Subframe headers start with a zero bit and end with the wasted bits flag.
+    if samples.iter().all(|&s| s == samples[0]) {
+        writer.write(0, 8);
+        write_signed(writer, samples[0] as i64, bits);
+        return;
+    }
+
+    let verbatim = samples.len() as u64 * bits as u64;
+    let best = (0..=4)
+        .filter(|&order| order < samples.len())
+        .filter_map(|order| {
+            let residuals = fixed_residuals(samples, order);
+            let coding = RiceCoding::new(&residuals, samples.len(), order)?;
+            Some((
+                order as u64 * bits as u64 + coding.bits,
+                order,
+                residuals,
+                coding,
+            ))
+        })
+        .min_by_key(|(len, ..)| *len);
+
+    match best {
+        Some((len, order, residuals, coding)) if len < verbatim => {
+            writer.write(0b0001_0000 | (order as u32) << 1, 8);
+            for &sample in &samples[..order] {
+                write_signed(writer, sample as i64, bits);
+            }
+            coding.write(writer, &residuals);
+        }
+        _ => {
+            writer.write(0b0000_0010, 8);
+            for &sample in samples {
+                write_signed(writer, sample as i64, bits);
+            }
+        }
+    }
+}
+
+/// The residuals of the fixed predictor of `order`, without the warm up samples.
+fn fixed_residuals(samples: &[i32], order: usize) -> Vec<i64> {
+    let mut residuals: Vec<i64> = samples.iter().map(|&s| s as i64).collect();
+    // The predictors of increasing order are repeated differences of the signal.
+    for _ in 0..order {
+        for i in (1..residuals.len()).rev() {
+            residuals[i] -= residuals[i - 1];
+        }
+    }
+    residuals.drain(..order);
+    residuals
+}
+
+/// How the residuals are split into partitions, each with its own rice parameter.
+struct RiceCoding {
+    block_size: usize,
+    order: usize,
+    partition_order: u32,
+    parameters: Vec<u32>,
+    bits: u64,
+}
+
+impl RiceCoding {
+    /// Finds the partitioning with the fewest bits, or `None` if a residual does not fit
+    /// into the 32 bits decoders use for them.
+    fn new(residuals: &[i64], block_size: usize, order: usize) -> Option<Self> {
+        let folded: Vec<u64> = residuals.iter().map(|&r| fold(r)).collect();
+        if folded.iter().any(|&u| u > u32::MAX as u64) {
+            return None;
+        }
+
+        (0..=MAX_PARTITION_ORDER)
+            .take_while(|&p| block_size.is_multiple_of(1 << p) && block_size >> p > order)
+            .map(|partition_order| {
+                let parameters: Vec<u32> = partitions(&folded, block_size, order, partition_order)
+                    .map(best_parameter)
+                    .collect();
+                let bits = partitions(&folded, block_size, order, partition_order)
+                    .zip(&parameters)
+                    .map(|(partition, &k)| rice_bits(partition, k))
+                    .sum();
+                let mut coding = Self {
+                    block_size,
+                    order,
+                    partition_order,
+                    parameters,
+                    bits,
+                };
+                coding.bits += 6 + coding.parameters.len() as u64 * coding.parameter_bits() as u64;
+                coding
+            })
+            .min_by_key(|coding| coding.bits)
+    }
+
+    /// Parameters above 14 need the 5 bit parameters of the second coding method.
+    fn parameter_bits(&self) -> u32 {
+        if self.parameters.iter().any(|&k| k > 14) {
+            5
+        } else {
+            4
+        }
+    }
+
+    fn write(&self, writer: &mut BitWriter, residuals: &[i64]) {
+        let parameter_bits = self.parameter_bits();
+        writer.write(parameter_bits - 4, 2);
+        writer.write(self.partition_order, 4);
+        let folded: Vec<u64> = residuals.iter().map(|&r| fold(r)).collect();
+        let partitions = partitions(&folded, self.block_size, self.order, self.partition_order);
+        for (partition, &k) in partitions.zip(&self.parameters) {
+            writer.write(k, parameter_bits);
+            for &u in partition {
+                let mut quotient = u >> k;
+                while quotient >= 32 {
+                    writer.write(0, 32);
+                    quotient -= 32;
+                }
+                writer.write(1, quotient as u32 + 1);
+                if k > 0 {
+                    writer.write(u as u32, k);
+                }
+            }
+        }
+    }
+}
+
+/// Splits the residuals into `2^partition_order` partitions, the first one is shorter by
+/// the predictor order.
+fn partitions(
+    folded: &[u64],
+    block_size: usize,
+    order: usize,
+    partition_order: u32,
+) -> impl Iterator<Item = &[u64]> {
+    let len = block_size >> partition_order;
+    (0..1usize << partition_order).map(move |i| {
+        let start = (i * len).saturating_sub(order);
+        &folded[start..(i + 1) * len - order]
+    })
+}
+
+/// The rice parameter with the fewest bits, close to the logarithm of the mean.
+fn best_parameter(partition: &[u64]) -> u32 {
+    let sum: u64 = partition.iter().sum();
+    let mean = sum / partition.len().max(1) as u64;
+    let estimate = (u64::BITS - mean.leading_zeros()).min(MAX_RICE_PARAMETER);
+    (estimate.saturating_sub(1)..=(estimate + 1).min(MAX_RICE_PARAMETER))
+        .min_by_key(|&k| rice_bits(partition, k))
+        .unwrap()
+}
+
+fn rice_bits(partition: &[u64], k: u32) -> u64 {
+    partition.iter().map(|&u| u >> k).sum::<u64>() + partition.len() as u64 * (k as u64 + 1)
+}
+
+/// Maps residuals to unsigned numbers, alternating between positive and negative ones.
+fn fold(residual: i64) -> u64 {
+    ((residual << 1) ^ (residual >> 63)) as u64
+}
+
+fn write_signed(writer: &mut BitWriter, value: i64, bits: u32) {
+    writer.write(value as u32, bits);
+}
+
+/// Frame numbers are coded like UTF-8 characters, extended to 31 bits.
+fn write_utf8(writer: &mut BitWriter, value: u32) {
+    let continuation = match value {
+        0..=0x7F => return writer.write(value, 8),
+        0x80..=0x7FF => 1,
+        0x800..=0xFFFF => 2,
+        0x1_0000..=0x1F_FFFF => 3,
+        0x20_0000..=0x3FF_FFFF => 4,
+        _ => 5,
+    };
+    let lead_marker = !(0xFF >> (continuation + 1)) & 0xFF;
+    writer.write(lead_marker | value >> (6 * continuation), 8);
+    for i in (0..continuation).rev() {
+        writer.write(0x80 | (value >> (6 * i)) & 0x3F, 8);
+    }
+}
+
+/// Returns the code of the frame header and the value following it for rates without a code.
+fn sample_rate_code(sample_rate: u32) -> (u32, Option<(u32, u32)>) {
+    match sample_rate {
+        88200 => (0b0001, None),
+        176400 => (0b0010, None),
+        192000 => (0b0011, None),
+        8000 => (0b0100, None),
+        16000 => (0b0101, None),
+        22050 => (0b0110, None),
+        24000 => (0b0111, None),
+        32000 => (0b1000, None),
+        44100 => (0b1001, None),
+        48000 => (0b1010, None),
+        96000 => (0b1011, None),
+        rate if rate % 1000 == 0 && rate / 1000 < 256 => (0b1100, Some((rate / 1000, 8))),
+        rate if rate < 1 << 16 => (0b1101, Some((rate, 16))),
+        rate if rate % 10 == 0 && rate / 10 < 1 << 16 => (0b1110, Some((rate / 10, 16))),
+        // Taken from the stream info.
+        _ => (0b0000, None),
+    }
+}
+
+fn sample_size_code(bits: u32) -> u32 {
+    match bits {
+        8 => 0b001,
+        16 => 0b100,
+        24 => 0b110,
+        // Most decoders reject the newer code for 32 bits, so it is taken from the stream info.
+        _ => 0b000,
+    }
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// The MD5 digest of `bytes` for the stream info, see RFC 1321.
+fn md5(bytes: &[u8]) -> [u8; 16] {
+    const SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+    let constants: Vec<u32> = (0..64)
+        .map(|i| ((i as f64 + 1.0).sin().abs() * 4294967296.0) as u32)
+        .collect();
+
+    let mut message = bytes.to_vec();
+    message.push(0x80);
+    message.resize((message.len() + 8).next_multiple_of(64) - 8, 0);
+    message.extend_from_slice(&(bytes.len() as u64 * 8).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for block in message.chunks_exact(64) {
+        let words: Vec<u32> = block
+            .chunks_exact(4)
+            .map(|w| u32::from_le_bytes(w.try_into().unwrap()))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a
+                .wrapping_add(f)
+                .wrapping_add(constants[i])
+                .wrapping_add(words[g])
+                .rotate_left(SHIFTS[i / 16 * 4 + i % 4]);
+            (a, b, c, d) = (d, b.wrapping_add(rotated), b, c);
+        }
+        for (state, value) in state.iter_mut().zip([a, b, c, d]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0; 16];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
@@ -0,0 +1,128 @@
+use std::{
+    ffi::{c_char, CStr},
+    path::Path,
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use crate::{
+    Error, InitOptions, LibraryLoadError, RawAudioQueryOptions, RawSynthesisOptions, ResultCode,
+    TtsOptions,
+};
+
+/// Function pointers into the voicevox core library.
+#[derive(Clone)]
+pub struct VoiceVoxFns {
+    pub(crate) init: unsafe extern "C" fn(InitOptions) -> ResultCode,
+    pub(crate) finalize: unsafe extern "C" fn(),
+    pub(crate) load_model: unsafe extern "C" fn(u32) -> ResultCode,
+    pub(crate) tts: TtsFn,
+    pub(crate) wav_free: unsafe extern "C" fn(*mut u8),
+    pub(crate) audio_query: AudioQueryFn,
+    pub(crate) audio_query_json_free: unsafe extern "C" fn(*mut c_char),
+    pub(crate) synthesis: SynthesisFn,
+    pub(crate) get_metas_json: unsafe extern "C" fn() -> *const c_char,
+    pub(crate) is_model_loaded: unsafe extern "C" fn(u32) -> bool,
+    pub(crate) get_version: unsafe extern "C" fn() -> *const c_char,
+    pub(crate) is_gpu_mode: unsafe extern "C" fn() -> bool,
+    // The function pointers are only valid as long as the library is loaded.
+    // Buffers handed out by the library keep a reference to it as well.
+    pub(crate) lib: Arc<libloading::Library>,
+}
+
+impl VoiceVoxFns {
+    /// Resolves all symbols this crate uses from the voicevox core library.
+    pub(crate) fn load(lib: libloading::Library, path: &Path) -> Result<Self, Error> {
+        unsafe {
+            Ok(Self {
+                init: get_symbol(&lib, path, "voicevox_initialize")?,
+                finalize: get_symbol(&lib, path, "voicevox_finalize")?,
+                load_model: get_symbol(&lib, path, "voicevox_load_model")?,
+                tts: get_symbol(&lib, path, "voicevox_tts")?,
+                wav_free: get_symbol(&lib, path, "voicevox_wav_free")?,
+                audio_query: get_symbol(&lib, path, "voicevox_audio_query")?,
+                audio_query_json_free: get_symbol(&lib, path, "voicevox_audio_query_json_free")?,
+                synthesis: get_symbol(&lib, path, "voicevox_synthesis")?,
+                get_metas_json: get_symbol(&lib, path, "voicevox_get_metas_json")?,
+                is_model_loaded: get_symbol(&lib, path, "voicevox_is_model_loaded")?,
+                get_version: get_symbol(&lib, path, "voicevox_get_version")?,
+                is_gpu_mode: get_symbol(&lib, path, "voicevox_is_gpu_mode")?,
+                lib: Arc::new(lib),
+            })
+        }
+    }
+}
+
+/// Guards calls into the core, which keeps its state in globals.
+///
+/// The lock is global instead of per [`VoiceVox`](crate::VoiceVox), because every instance
+/// loading the same library shares that state.
+static CORE: RwLock<()> = RwLock::new(());
+
+/// Locks the core for calls that modify its state or run inference.
+///
+/// Core calls can not leave the lock in an inconsistent state, so poisoning is ignored.
+pub(crate) fn lock_exclusive() -> RwLockWriteGuard<'static, ()> {
+    CORE.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Locks the core for read-only queries, which may run concurrently with each other.
+pub(crate) fn lock_shared() -> RwLockReadGuard<'static, ()> {
+    CORE.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Resolves a function from the voicevox core library.
+///
+/// If the symbol is missing, the library is inspected to explain why.
+///
+/// # Safety
+/// `T` must match the signature of the symbol exported by the library.
+unsafe fn get_symbol<T: Copy>(
+    lib: &libloading::Library,
+    path: &Path,
+    name: &'static str,
+) -> Result<T, Error> {
+    lib.get::<T>(name.as_bytes())
+        .map(|symbol| *symbol)
+        .map_err(|source| {
+            LibraryLoadError::MissingSymbol {
+                path: path.to_owned(),
+                symbol: name,
+                source,
+                core_version: core_version(lib),
+                synthesizer_api: lib.get::<*const ()>(b"voicevox_synthesizer_tts").is_ok(),
+            }
+            .into()
+        })
+}
+
+/// The version reported by the library, if it exports `voicevox_get_version`,
+/// which every voicevox core release does.
+unsafe fn core_version(lib: &libloading::Library) -> Option<String> {
+    let get_version = lib
+        .get::<unsafe extern "C" fn() -> *const c_char>(b"voicevox_get_version")
+        .ok()?;
+    Some(CStr::from_ptr(get_version()).to_string_lossy().into_owned())
+}
+
+type TtsFn = unsafe extern "C" fn(
+    text: *const c_char,
+    speaker_id: u32,
+    options: TtsOptions,
+    output_wav_length: *mut usize,
+    output_wav: *mut *mut u8,
+) -> ResultCode;
+
+type AudioQueryFn = unsafe extern "C" fn(
+    text: *const c_char,
+    speaker_id: u32,
+    options: RawAudioQueryOptions,
+    output_audio_query_json: *mut *mut c_char,
+) -> ResultCode;
+
+type SynthesisFn = unsafe extern "C" fn(
+    audio_query_json: *const c_char,
+    speaker_id: u32,
+    options: RawSynthesisOptions,
+    output_wav_length: *mut usize,
+    output_wav: *mut *mut u8,
+) -> ResultCode;
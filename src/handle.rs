@@ -0,0 +1,337 @@
+use std::{
+    io::Write,
+    path::Path,
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::Duration,
+};
+
+use crate::{
+    limiter::Limiter, write_file, AccelerationMode, AudioQuery, CPointerWrap, CaptionSegment,
+    ConcurrencyStats, Error, MemoryReport, ModelLoadReport, SpeechParams, StyleId,
+    SynthesisOptions, SynthesisStats, TtsOptions, VoiceVox, VoiceVoxInfo, Wav, WavBytes,
+};
+
+/// A cloneable handle to a [`VoiceVox`] that can be shared between threads,
+/// e.g. between the request handlers of a web server.
+///
+/// Initialization takes exclusive access to the [`VoiceVox`], the other calls share it. At
+/// most [`VoiceVoxHandle::set_max_concurrent_syntheses`] synthesizing calls run at once, the
+/// others wait for them to finish instead of competing for the CPU.
+/// Created with [`VoiceVox::into_handle`].
+///
+/// ```no_run
+/// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, VoiceVox};
+///
+/// # fn main() -> Result<(), voicevox_dyn::Error> {
+/// let handle = VoiceVox::quickstart(ZUNDAMON_NORMAL)?.into_handle();
+/// let threads: Vec<_> = ["おはよう", "こんばんは"]
+///     .into_iter()
+///     .map(|text| {
+///         let handle = handle.clone();
+///         std::thread::spawn(move || handle.tts(text, ZUNDAMON_NORMAL, Default::default()))
+///     })
+///     .collect();
+/// for thread in threads {
+///     let wav = thread.join().unwrap()?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct VoiceVoxHandle {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    vv: RwLock<VoiceVox>,
+    limiter: Limiter,
+}
+
+impl VoiceVoxHandle {
+    /// Allows as many concurrent syntheses as the CPU has cores.
+    pub fn new(vv: VoiceVox) -> Self {
+        let limit = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self {
+            inner: Arc::new(Inner {
+                vv: RwLock::new(vv),
+                limiter: Limiter::new(limit),
+            }),
+        }
+    }
+
+    /// Locks the underlying [`VoiceVox`] for exclusive access.
+    ///
+    /// A panic while the lock was held does not leave `VoiceVox` in an inconsistent state,
+    /// so poisoning is ignored.
+    pub fn lock(&self) -> RwLockWriteGuard<'_, VoiceVox> {
+        self.inner
+            .vv
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the underlying [`VoiceVox`] for shared access. Calls made through the guard do
+    /// not count towards [`VoiceVoxHandle::set_max_concurrent_syntheses`].
+    pub fn read(&self) -> RwLockReadGuard<'_, VoiceVox> {
+        self.inner.vv.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Limits how many synthesizing calls run at once across all clones of the handle, at
+    /// least one. Further calls wait until one of the running calls finishes.
+    ///
+    /// Voicevox core runs one inference at a time, see [`VoiceVox`], so the calls only
+    /// overlap preparing the text and post-processing the speech.
+    pub fn set_max_concurrent_syntheses(&self, limit: usize) {
+        self.inner.limiter.set_limit(limit);
+    }
+
+    /// How many synthesizing calls run and wait right now, e.g. for monitoring a server.
+    pub fn concurrency_stats(&self) -> ConcurrencyStats {
+        self.inner.limiter.stats()
+    }
+
+    /// Runs a synthesizing call once the limit of concurrent syntheses allows it.
+    pub(crate) fn synthesize<T>(&self, f: impl FnOnce(&VoiceVox) -> T) -> T {
+        let _permit = self.inner.limiter.acquire();
+        f(&self.read())
+    }
+
+    /// See [`VoiceVox::init`].
+    pub fn init(
+        &self,
+        acceleration_mode: AccelerationMode,
+        cpu_num_threads: u16,
+        load_all_models: bool,
+    ) -> Result<(), Error> {
+        self.lock()
+            .init(acceleration_mode, cpu_num_threads, load_all_models)
+    }
+
+    /// See [`VoiceVox::set_cpu_threads`]. Waits for the calls made through other clones of the
+    /// handle to finish.
+    pub fn set_cpu_threads(&self, cpu_num_threads: u16) -> Result<(), Error> {
+        self.lock().set_cpu_threads(cpu_num_threads)
+    }
+
+    /// See [`VoiceVox::load_model`].
+    pub fn load_model(&self, speaker_id: u32) -> Result<(), Error> {
+        self.read().load_model(speaker_id)
+    }
+
+    /// See [`VoiceVox::load_models`].
+    pub fn load_models(&self, style_ids: &[StyleId]) -> Result<(), ModelLoadReport> {
+        self.read().load_models(style_ids)
+    }
+
+    /// See [`VoiceVox::tts_with_stats`].
+    pub fn tts_with_stats(
+        &self,
+        text: impl AsRef<str>,
+        style_id: StyleId,
+        opts: TtsOptions,
+    ) -> Result<(Wav, SynthesisStats), Error> {
+        self.synthesize(|vv| vv.tts_with_stats(text, style_id, opts))
+    }
+
+    /// See [`VoiceVox::warmup`].
+    pub fn warmup(&self, style_id: StyleId) -> Result<(), Error> {
+        self.synthesize(|vv| vv.warmup(style_id))
+    }
+
+    /// See [`VoiceVox::warmup_loaded`].
+    pub fn warmup_loaded(&self) -> Result<(), Error> {
+        self.synthesize(VoiceVox::warmup_loaded)
+    }
+
+    /// See [`VoiceVox::is_model_loaded`].
+    pub fn is_model_loaded(&self, style_id: StyleId) -> bool {
+        self.read().is_model_loaded(style_id)
+    }
+
+    /// See [`VoiceVox::info`].
+    pub fn info(&self) -> VoiceVoxInfo {
+        self.read().info()
+    }
+
+    /// See [`VoiceVox::evict_models`].
+    pub fn evict_models(&self) -> Result<Vec<StyleId>, Error> {
+        self.read().evict_models()
+    }
+
+    /// See [`VoiceVox::memory_report`].
+    pub fn memory_report(&self) -> MemoryReport {
+        self.read().memory_report()
+    }
+
+    /// See [`VoiceVox::tts`].
+    pub fn tts(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<WavBytes, Error> {
+        self.synthesize(|vv| vv.tts(text, speaker_id, opts))
+    }
+
+    /// See [`VoiceVox::tts_wav`].
+    pub fn tts_wav(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<Wav, Error> {
+        self.synthesize(|vv| vv.tts_wav(text, speaker_id, opts))
+    }
+
+    /// See [`VoiceVox::tts_with_options`].
+    pub fn tts_with_options(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: &SynthesisOptions,
+    ) -> Result<Wav, Error> {
+        self.synthesize(|vv| vv.tts_with_options(text, speaker_id, opts))
+    }
+
+    /// See [`VoiceVox::tts_batch`]. The whole batch counts as one call towards
+    /// [`VoiceVoxHandle::set_max_concurrent_syntheses`].
+    pub fn tts_batch(
+        &self,
+        items: &[(String, StyleId, SynthesisOptions)],
+    ) -> Vec<Result<Wav, Error>> {
+        self.synthesize(|vv| vv.tts_batch(items))
+    }
+
+    /// See [`VoiceVox::tts_into`].
+    pub fn tts_into(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        self.synthesize(|vv| vv.tts_into(text, speaker_id, opts, buf))
+    }
+
+    /// See [`VoiceVox::tts_to_writer`].
+    ///
+    /// The handle is only locked during synthesis, not while writing to `writer`.
+    pub fn tts_to_writer(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+        writer: &mut impl Write,
+    ) -> Result<(), Error> {
+        let result = if self.read().synthesis_cache().is_some() {
+            writer.write_all(self.tts(text, speaker_id, opts)?.as_slice())
+        } else {
+            writer.write_all(&self.tts_raw(text, speaker_id, opts)?)
+        };
+        result.map_err(Error::io("failed to write the synthesized wav"))
+    }
+
+    /// See [`VoiceVox::tts_to_file`].
+    pub fn tts_to_file(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+        path: impl AsRef<Path>,
+    ) -> Result<(), Error> {
+        if self.read().synthesis_cache().is_some() {
+            return write_file(path.as_ref(), self.tts(text, speaker_id, opts)?.as_slice());
+        }
+        write_file(path.as_ref(), &self.tts_raw(text, speaker_id, opts)?)
+    }
+
+    /// See [`VoiceVox::tts_to_file_with_labels`].
+    pub fn tts_to_file_with_labels(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+        path: impl AsRef<Path>,
+    ) -> Result<(), Error> {
+        self.synthesize(|vv| vv.tts_to_file_with_labels(text, speaker_id, opts, path))
+    }
+
+    /// See [`VoiceVox::tts_raw`].
+    pub fn tts_raw(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<CPointerWrap<u8>, Error> {
+        self.synthesize(|vv| vv.tts_raw(text, speaker_id, opts))
+    }
+
+    /// See [`VoiceVox::audio_query`].
+    pub fn audio_query(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<AudioQuery, Error> {
+        self.synthesize(|vv| vv.audio_query(text, speaker_id, opts))
+    }
+
+    /// See [`VoiceVox::synthesis`].
+    pub fn synthesis(
+        &self,
+        query: &AudioQuery,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<WavBytes, Error> {
+        self.synthesize(|vv| vv.synthesis(query, speaker_id, opts))
+    }
+
+    /// See [`VoiceVox::tts_with_params`].
+    pub fn tts_with_params(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+        params: &SpeechParams,
+    ) -> Result<WavBytes, Error> {
+        self.synthesize(|vv| vv.tts_with_params(text, speaker_id, opts, params))
+    }
+
+    /// See [`VoiceVox::tts_ssml`].
+    pub fn tts_ssml(
+        &self,
+        ssml: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<Wav, Error> {
+        self.synthesize(|vv| vv.tts_ssml(ssml, speaker_id, opts))
+    }
+
+    /// See [`VoiceVox::tts_with_captions`].
+    pub fn tts_with_captions(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<(Wav, Vec<CaptionSegment>), Error> {
+        self.synthesize(|vv| vv.tts_with_captions(text, speaker_id, opts))
+    }
+
+    /// See [`VoiceVox::estimate_duration`].
+    pub fn estimate_duration(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+        params: &SpeechParams,
+    ) -> Result<Duration, Error> {
+        self.synthesize(|vv| vv.estimate_duration(text, speaker_id, opts, params))
+    }
+}
+
+impl std::fmt::Debug for VoiceVoxHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Locking here could deadlock when formatting while the lock is held.
+        f.debug_struct("VoiceVoxHandle").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,22 @@
+use std::path::PathBuf;
+
+use crate::{AccelerationMode, StyleId};
+
+/// Describes a loaded [`VoiceVox`](crate::VoiceVox), see [`VoiceVox::info`](crate::VoiceVox::info).
+///
+/// Useful for logs and bug reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoiceVoxInfo {
+    /// The directory voicevox was downloaded to.
+    pub install_dir: PathBuf,
+    /// The voicevox core library that was loaded.
+    pub library_path: PathBuf,
+    /// The version reported by the voicevox core.
+    pub core_version: String,
+    /// The Open JTalk dictionary used, `None` before initialization.
+    pub dict_dir: Option<PathBuf>,
+    /// Styles whose models are loaded.
+    pub loaded_models: Vec<StyleId>,
+    /// Whether inference runs on the CPU or GPU, `None` before initialization.
+    pub acceleration: Option<AccelerationMode>,
+}
@@ -5,43 +5,190 @@
 //! and in particular making it easy to distribute a single binary that
 //! sets up voicevox itself and is also able to run it.
 //!
+//! ### Features
+//! - `tracing` (enabled by default): emit log messages with [tracing](https://docs.rs/tracing).
+//!   Without it, log messages can be received with [`log::set_logger`].
+//! - `eyre`: adds [`Error::into_report`], which attaches suggestions for fixing common errors.
+//! - `playback`: adds [`play`] and [`VoiceVox::say`] for playing speech on the default output
+//!   device with [rodio](https://docs.rs/rodio). On Linux this requires the ALSA development files.
+//! - `cpal`: adds [`SpeechStream`], which plays queued utterances while the following ones are
+//!   still being synthesized, with [cpal](https://docs.rs/cpal). Requires the same as `playback`.
+//! - `flac`: adds [`Wav::encode_flac`], a small built-in lossless encoder without further dependencies.
+//! - `mp3`: adds [`Wav::encode_mp3`], a small built-in mp3 encoder without further dependencies.
+//! - `opus`: adds [`Wav::encode_opus`] and [`Wav::encode_ogg`] for compressing speech with
+//!   [opus](https://opus-codec.org). Requires libopus or cmake for building it.
+//! - `resample`: adds [`Wav::resample`] and [`SynthesisOptions::output_rate`] for converting
+//!   speech to other sampling rates with [rubato](https://docs.rs/rubato).
+//! - `stretch`: adds [`Wav::stretch`] for changing the duration of speech without changing its pitch.
+//! - `async`: adds the async API in [`nonblocking`], which works with any runtime by making
+//!   the blocking calls on a thread per call.
+//! - `tokio`, `async-std`, `smol`: enable `async` and make the blocking calls on the blocking
+//!   thread pool of the runtime instead, see [`nonblocking::Executor`].
+//! - `cli`: builds the `voicevox-dyn` binary, e.g. `voicevox-dyn download --device cuda` for
+//!   provisioning machines and Docker images, or `voicevox-dyn say こんにちは --out hello.wav`
+//!   for checking an installation and shell scripts. Install it with
+//!   `cargo install voicevox-dyn --features cli`.
+//!
 //! ### Alternatives
 //! If you prefer to dynamically link voicevox instead, I recommend using [vvcore](https://github.com/iwase22334/voicevox-core-rs).
 
-use color_eyre::eyre::bail;
-use libloading::Symbol;
-use std::{ffi::OsStr, path::PathBuf, process::Stdio};
-use tracing::info;
+use std::{
+    ffi::{CStr, OsStr},
+    io::Write,
+    ops::{Deref, Index},
+    path::{Path, PathBuf},
+    process::Stdio,
+    slice::SliceIndex,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+#[macro_use]
+pub mod log;
+
+mod actor;
+mod async_voicevox;
+mod audio_query;
+mod background;
+pub mod bench;
+#[cfg(any(feature = "flac", feature = "mp3"))]
+mod bits;
+pub mod blocking;
+mod builder;
+mod cache;
+mod cancel;
+pub mod captions;
+mod dialogue;
+mod dictionary;
+mod error;
+mod eviction;
+mod fade;
+#[cfg(feature = "flac")]
+mod flac;
+mod fns;
+mod handle;
+mod info;
+mod limiter;
+mod loudness;
+mod memory;
+mod metas;
+#[cfg(feature = "mp3")]
+mod mp3;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+mod options;
+#[cfg(feature = "opus")]
+mod opus;
+mod pending;
+#[cfg(feature = "playback")]
+mod playback;
+mod pool;
+mod queue;
+mod scratch;
+#[cfg(feature = "cpal")]
+mod speech_stream;
+pub mod ssml;
+mod stats;
+mod stream;
+#[cfg(feature = "stretch")]
+mod stretch;
+pub mod styles;
+pub mod text;
+pub mod typestate;
+mod utterance;
+mod wav;
+mod wav_writer;
+
+pub use actor::{TtsActor, TtsClient};
+pub use async_voicevox::AsyncVoiceVox;
+pub use audio_query::{AccentPhrase, AudioQuery, Mora, PhonemeLabel, SpeechParams};
+pub use background::InitHandle;
+pub use builder::{Device, LoadStage, MemoryProfile, VoiceVoxBuilder};
+pub use cache::{AudioQueryCache, CacheStats, SynthesisCache};
+pub use cancel::CancellationToken;
+pub use captions::CaptionSegment;
+pub use dialogue::{Dialogue, DialogueAudio, Line};
+pub use dictionary::{DictionaryManager, UserWord};
+pub use error::{DownloadError, Error, LibraryLoadError, ModelLoadReport, ResultCode};
+pub use eviction::ModelEvictionPolicy;
+pub use fade::FadeCurve;
+pub use fns::VoiceVoxFns;
+pub use handle::VoiceVoxHandle;
+pub use info::VoiceVoxInfo;
+pub use limiter::ConcurrencyStats;
+pub use memory::{MemoryReport, ModelMemory};
+pub use metas::{SpeakerMeta, StyleMeta};
+#[cfg(feature = "async")]
+pub use nonblocking::AudioChunkStream;
+pub use options::{LongInput, SynthesisOptions, TtsOptions, TtsOptionsBuilder};
+pub use pending::{Pending, PendingSynthesis};
+#[cfg(feature = "playback")]
+pub use playback::{play, play_wav, Playback};
+pub use pool::VoiceVoxPool;
+pub use queue::{Priority, RequestId, SynthesisQueue};
+pub use scratch::SynthesisScratch;
+#[cfg(feature = "cpal")]
+pub use speech_stream::SpeechStream;
+pub use stats::SynthesisStats;
+pub use stream::{AudioChunk, LongText, TtsStream};
+pub use utterance::Utterance;
+pub use wav::{Peak, Wav};
+pub use wav_writer::StreamingWavWriter;
 
+/// The id of a style of a speaker. Voicevox core calls this the speaker id.
+pub type StyleId = u32;
+
+/// A loaded voicevox core.
+///
+/// # Thread safety
+/// `VoiceVox` is `Send` and `Sync`: it only holds the library handle and function pointers
+/// into it. Initialization requires `&mut self`, so it can not race with other calls.
+///
+/// Voicevox core 0.14 keeps its state in globals, so calls into it are synchronized
+/// internally, across all `VoiceVox` instances in the process:
+/// - [`VoiceVox::init`], [`VoiceVox::load_model`], [`VoiceVox::tts`],
+///   [`VoiceVox::audio_query`] and [`VoiceVox::synthesis`] run one at a time,
+/// - read-only queries like [`VoiceVox::is_model_loaded`] and [`VoiceVox::info`] may run
+///   concurrently with each other, but not with the calls above.
+///
+/// Calling `tts` from several threads at once is therefore safe, but does not synthesize
+/// in parallel. To share a `VoiceVox` between threads, use [`VoiceVox::into_handle`].
+///
+/// # Multiple instances
+/// Instances loaded from the same library share its globals: the initialization options,
+/// the Open JTalk dictionary and the loaded models. Independent instances, e.g. with different
+/// dictionaries or devices, need the handle based API of voicevox core 0.15 or newer, which
+/// is not supported yet. To avoid surprises, initializing a second instance of the same library
+/// fails if the options differ from the first, otherwise it shares the first's state.
 pub struct VoiceVox {
     fns: VoiceVoxFns,
     init: bool,
+    dir: PathBuf,
+    library_path: PathBuf,
+    dict_dir: Option<PathBuf>,
+    metas: Vec<SpeakerMeta>,
+    auto_load_models: bool,
+    cache: Option<Arc<SynthesisCache>>,
+    query_cache: Option<Arc<AudioQueryCache>>,
+    eviction_policy: ModelEvictionPolicy,
+    model_usage: Arc<eviction::ModelUsage>,
 }
 
-#[ouroboros::self_referencing]
-pub struct VoiceVoxFns {
-    lib: libloading::Library,
-    #[covariant]
-    #[borrows(lib)]
-    init: Symbol<'this, unsafe extern "C" fn(InitOptions) -> ResultCode>,
-    #[covariant]
-    #[borrows(lib)]
-    load_model: Symbol<'this, unsafe extern "C" fn(u32) -> ResultCode>,
-    #[covariant]
-    #[borrows(lib)]
-    tts: Symbol<'this, TtsFn>,
-    #[covariant]
-    #[borrows(lib)]
-    wav_free: Symbol<'this, unsafe extern "C" fn(*mut u8)>,
-}
-
-type TtsFn = unsafe extern "C" fn(
-    text: *const ::std::os::raw::c_char,
-    speaker_id: u32,
-    options: TtsOptions,
-    output_wav_length: *mut usize,
-    output_wav: *mut *mut u8,
-) -> ResultCode;
+impl std::fmt::Debug for VoiceVox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("VoiceVox").field(&self.info()).finish()
+    }
+}
+
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<VoiceVox>();
+    assert_send_sync::<VoiceVoxHandle>();
+    assert_send_sync::<CPointerWrap<u8>>();
+};
 
 impl VoiceVox {
     /// Creates a new VoiceVox instance and downloads all required files for running
@@ -53,7 +200,7 @@ impl VoiceVox {
     ///
     /// By default the CPU runtime for voicevox is downloaded. For cuda support,
     /// use [`VoiceVox::new_with_args`] with `["--device", "cuda"]` as the argument.
-    pub fn load() -> color_eyre::Result<Self> {
+    pub fn load() -> Result<Self, Error> {
         Self::load_with_args(std::iter::empty::<&str>())
     }
 
@@ -62,125 +209,879 @@ impl VoiceVox {
     /// See [here](https://github.com/VOICEVOX/voicevox_core/blob/6a662757b8d42fc5d0902364b1d549684b50b5bc/crates/download/src/main.rs#L50) for a list of possible arguments.
     pub fn load_with_args<S: AsRef<OsStr>>(
         args: impl IntoIterator<Item = S>,
-    ) -> color_eyre::Result<Self> {
-        let exe_path = download_path()?;
-        #[cfg(target_os = "windows")]
-        let dll = exe_path.join("voicevox_core.dll");
-        #[cfg(target_os = "macos")]
-        let dll = exe_path.join("libvoicevox_core.dylib");
-        #[cfg(target_os = "linux")]
-        let dll = exe_path.join("libvoicevox_core.so");
+    ) -> Result<Self, Error> {
+        Self::load_from(download_path()?, None, args)
+    }
+
+    /// Downloads voicevox if necessary, initializes it with [`AccelerationMode::Auto`]
+    /// using all available cores and loads the model of `style_id`.
+    ///
+    /// ```no_run
+    /// # fn main() -> Result<(), voicevox_dyn::Error> {
+    /// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, VoiceVox};
+    ///
+    /// let wav = VoiceVox::quickstart(ZUNDAMON_NORMAL)?.tts("こんにちは", ZUNDAMON_NORMAL, Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Use [`VoiceVox::builder`] for more control.
+    pub fn quickstart(style_id: StyleId) -> Result<Self, Error> {
+        let threads = std::thread::available_parallelism()
+            .map(|n| u16::try_from(n.get()).unwrap_or(u16::MAX))
+            .unwrap_or(0);
+        Self::builder()
+            .acceleration_mode(AccelerationMode::Auto)
+            .cpu_num_threads(threads)
+            .load_models([style_id])
+            .build()
+    }
 
+    /// Wraps `self` in a [`VoiceVoxHandle`], which can be cloned and shared between threads.
+    pub fn into_handle(self) -> VoiceVoxHandle {
+        VoiceVoxHandle::new(self)
+    }
+
+    /// Returns a [`VoiceVoxBuilder`] for configuring download, loading and initialization
+    /// in one place.
+    pub fn builder() -> VoiceVoxBuilder {
+        VoiceVoxBuilder::default()
+    }
+
+    /// Downloads voicevox into `exe_path` if necessary and loads the core library from there.
+    ///
+    /// `version` selects the release of the downloader, `None` uses the latest release.
+    pub(crate) fn load_from<S: AsRef<OsStr>>(
+        exe_path: PathBuf,
+        version: Option<&str>,
+        args: impl IntoIterator<Item = S>,
+    ) -> Result<Self, Error> {
+        let dll = library_path(&exe_path);
         if !dll.exists() {
-            // get the downloader
-            info!("Downloading voicevox downloader.");
-            let mut reader = ureq::get(&voicevox_downloader_url()?).call()?.into_reader();
-            let downloader_path = exe_path.join("voicevox_downloader");
-            let file = std::fs::File::create(&downloader_path)?;
-            std::io::copy(&mut reader, &mut std::io::BufWriter::new(file))?;
-
-            #[cfg(target_family = "unix")]
-            std::process::Command::new("chmod")
-                .arg("+x")
-                .arg(&downloader_path)
-                .output()
-                .unwrap();
-
-            // use the downloader
-            let mut child = std::process::Command::new(downloader_path)
-                .args([
-                    "-o",
-                    exe_path.to_str().ok_or(color_eyre::eyre::eyre!(
-                        "failed to convert {:?} to str",
-                        exe_path
-                    ))?,
-                ])
-                .args(args)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()?;
-
-            info!("Downloading voicevox. This may take a while, roughly 700MB of data will be downloaded.");
-            // This doesn't output the progress bars, so not very useful.
-            // let mut out = child.stdout.take().unwrap();
-            // let mut err = child.stderr.take().unwrap();
-            // std::thread::spawn(move || {
-            //     std::io::copy(&mut out, &mut std::io::stderr()).unwrap();
-            // });
-            // std::thread::spawn(move || {
-            //     std::io::copy(&mut err, &mut std::io::stdout()).unwrap();
-            // });
-
-            child.wait()?;
-        }
-
-        unsafe {
-            let lib = libloading::Library::new(dll).unwrap();
-
-            Ok(Self {
-                fns: VoiceVoxFns::new(
-                    lib,
-                    |lib| lib.get(b"voicevox_initialize").unwrap(),
-                    |lib| lib.get(b"voicevox_load_model").unwrap(),
-                    |lib| lib.get(b"voicevox_tts").unwrap(),
-                    |lib| lib.get(b"voicevox_wav_free").unwrap(),
-                ),
-                init: false,
-            })
+            download_to(&exe_path, version, args)?;
         }
+
+        let lib =
+            unsafe { libloading::Library::new(&dll) }.map_err(|source| LibraryLoadError::Open {
+                path: dll.clone(),
+                source,
+            })?;
+        let fns = VoiceVoxFns::load(lib, &dll)?;
+
+        let metas = unsafe { CStr::from_ptr((fns.get_metas_json)()) };
+        let metas = metas
+            .to_str()
+            .map_err(|e| Error::InvalidOutput(e.to_string()))
+            .and_then(|json| {
+                serde_json::from_str(json).map_err(|e| Error::InvalidOutput(e.to_string()))
+            })?;
+
+        Ok(Self {
+            fns,
+            init: false,
+            dir: exe_path,
+            library_path: dll,
+            dict_dir: None,
+            metas,
+            auto_load_models: false,
+            cache: None,
+            query_cache: None,
+            eviction_policy: ModelEvictionPolicy::Never,
+            model_usage: Arc::default(),
+        })
     }
 
     /// Initializes the voicevox runtime. This is expensive when called with
     /// `load_all_models = true`, so it is recommended to instead load only
     /// the models you need with [`VoiceVox::load_model`].
+    ///
+    /// The Open JTalk dictionary is searched for in these locations, in order:
+    /// 1. the directory in the `VOICEVOX_OPEN_JTALK_DICT_DIR` environment variable,
+    /// 2. `open_jtalk_dic_utf_8-1.11` in the directory voicevox was loaded from,
+    /// 3. `open_jtalk_dic_utf_8-1.11` in the directory of the executable,
+    /// 4. `open_jtalk_dic_utf_8-1.11` in the current directory.
+    ///
+    /// Returns [`Error::DictionaryNotFound`] listing every location if none of them exist.
+    /// Use [`VoiceVox::init_with_dict_dir`] to pass the dictionary directly.
     pub fn init(
         &mut self,
         acceleration_mode: AccelerationMode,
         cpu_num_threads: u16,
         load_all_models: bool,
-    ) -> color_eyre::Result<()> {
-        let opts = InitOptions::new(acceleration_mode, cpu_num_threads, load_all_models)?;
+    ) -> Result<(), Error> {
+        let dict_dir = find_dict_dir(Some(&self.dir))?;
+        self.init_with_dict_dir(
+            acceleration_mode,
+            cpu_num_threads,
+            load_all_models,
+            dict_dir,
+        )
+    }
 
+    /// Same as [`VoiceVox::init`] but uses the Open JTalk dictionary in `dict_dir`.
+    pub fn init_with_dict_dir(
+        &mut self,
+        acceleration_mode: AccelerationMode,
+        cpu_num_threads: u16,
+        load_all_models: bool,
+        dict_dir: impl AsRef<Path>,
+    ) -> Result<(), Error> {
+        let opts = InitOptions::with_dict_dir(
+            acceleration_mode,
+            cpu_num_threads,
+            load_all_models,
+            dict_dir,
+        )?;
+        self.init_with_options(opts)
+    }
+
+    pub(crate) fn init_with_options(&mut self, opts: InitOptions) -> Result<(), Error> {
         info!("Initializing voicevox. This can take a while.");
         if self.init {
             return Ok(());
         }
-        match unsafe { (self.fns.borrow_init())(opts) } {
+        let dict_dir = unsafe { CStr::from_ptr(opts.open_jtalk_dict_dir) }
+            .to_string_lossy()
+            .into_owned();
+        let settings = InitSettings {
+            library: std::fs::canonicalize(&self.library_path)
+                .unwrap_or_else(|_| self.library_path.clone()),
+            acceleration_mode: opts.acceleration_mode,
+            cpu_num_threads: opts.cpu_num_threads,
+            dict_dir: dict_dir.clone(),
+        };
+        let _core = fns::lock_exclusive();
+        let mut initialized = INITIALIZED.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(first) = initialized.iter().find(|s| s.library == settings.library) {
+            if *first != settings {
+                return Err(Error::InvalidInput(format!(
+                    "{} was already initialized by another VoiceVox with {first:?}, which \
+                     differs from {settings:?}. Voicevox core 0.14 keeps its state in globals, \
+                     so all instances loaded from the same library share it",
+                    settings.library.display()
+                )));
+            }
+            debug!("Voicevox is already initialized, sharing the state of the first VoiceVox.");
+            self.init = true;
+            self.clear_caches_if_dict_changed(Path::new(&dict_dir));
+            self.dict_dir = Some(dict_dir.into());
+            return Ok(());
+        }
+        match memory::measure_init(|| unsafe { opts.initialize(self.fns.init) }) {
             ResultCode::Ok => {
                 self.init = true;
+                self.clear_caches_if_dict_changed(Path::new(&dict_dir));
+                self.dict_dir = Some(dict_dir.into());
+                initialized.push(settings);
                 Ok(())
             }
             e => Err(e.into()),
         }
     }
 
+    /// Changes the number of CPU threads inference uses, e.g. when a laptop switches between
+    /// battery and AC. `0` lets voicevox decide.
+    ///
+    /// Voicevox core only reads the setting when it is initialized, so it is finalized and
+    /// initialized again with the other settings unchanged, and the models that were loaded
+    /// are loaded again. This waits for running calls and takes about as long as loading the
+    /// models. Every [`VoiceVox`] loaded from the same library shares the new setting, see
+    /// [`VoiceVox#multiple-instances`].
+    ///
+    /// Returns [`Error::NotInitialized`] before [`VoiceVox::init`]. If initializing with the
+    /// new setting fails, voicevox is initialized with the previous one again, and if that
+    /// fails too, it is left uninitialized and has to be initialized with [`VoiceVox::init`].
+    /// Models that fail to load again are returned in [`Error::ModelLoad`].
+    pub fn set_cpu_threads(&mut self, cpu_num_threads: u16) -> Result<(), Error> {
+        self.check_initialized()?;
+        let current = self
+            .init_settings(|settings| settings.cpu_num_threads)
+            .ok_or(Error::NotInitialized)?;
+        if current == cpu_num_threads {
+            return Ok(());
+        }
+        info!(
+            "Initializing voicevox again with {} CPU threads instead of {}.",
+            cpu_num_threads, current
+        );
+        self.reconfigure(None, Some(cpu_num_threads))
+    }
+
+    /// Runs [`VoiceVox::reinitialize`] keeping the loaded models, and marks this instance as
+    /// uninitialized if voicevox was left uninitialized.
+    pub(crate) fn reconfigure(
+        &mut self,
+        acceleration_mode: Option<AccelerationMode>,
+        cpu_num_threads: Option<u16>,
+    ) -> Result<(), Error> {
+        let result = self.reinitialize(acceleration_mode, cpu_num_threads, &self.loaded_styles());
+        if result.is_err() && self.init_settings(|_| ()).is_none() {
+            self.init = false;
+        }
+        result
+    }
+
+    /// Calls `f` with the settings the library of this instance was initialized with.
+    fn init_settings<T>(&self, f: impl FnOnce(&InitSettings) -> T) -> Option<T> {
+        let library =
+            std::fs::canonicalize(&self.library_path).unwrap_or_else(|_| self.library_path.clone());
+        let initialized = INITIALIZED.lock().unwrap_or_else(PoisonError::into_inner);
+        initialized.iter().find(|s| s.library == library).map(f)
+    }
+
+    /// Finalizes voicevox core and initializes it again with the settings it was initialized
+    /// with, except for `acceleration_mode` and `cpu_num_threads` if given, then loads the
+    /// models of `keep` again. Voicevox core 0.14 can neither change its settings nor unload
+    /// models otherwise.
+    ///
+    /// If initializing fails, the core is initialized with the previous settings again. If that
+    /// fails too, the core is left uninitialized and the settings are forgotten, so that it can
+    /// be initialized again.
+    pub(crate) fn reinitialize(
+        &self,
+        acceleration_mode: Option<AccelerationMode>,
+        cpu_num_threads: Option<u16>,
+        keep: &[StyleId],
+    ) -> Result<(), Error> {
+        let library =
+            std::fs::canonicalize(&self.library_path).unwrap_or_else(|_| self.library_path.clone());
+        let result = {
+            let _core = fns::lock_exclusive();
+            let mut initialized = INITIALIZED.lock().unwrap_or_else(PoisonError::into_inner);
+            let Some(index) = initialized.iter().position(|s| s.library == library) else {
+                return Err(Error::NotInitialized);
+            };
+            let settings = &mut initialized[index];
+            self.clear_caches_if_dict_changed(Path::new(&settings.dict_dir));
+            let dict_dir = dict_dir_to_c_string(Path::new(&settings.dict_dir))?;
+            let init = |acceleration_mode: i32, cpu_num_threads: u16| {
+                let opts = InitOptions {
+                    acceleration_mode,
+                    cpu_num_threads,
+                    load_all_models: false,
+                    open_jtalk_dict_dir: dict_dir.clone().into_raw(),
+                };
+                unsafe { (self.fns.finalize)() };
+                match unsafe { opts.initialize(self.fns.init) } {
+                    ResultCode::Ok => Ok(()),
+                    e => Err(Error::from(e)),
+                }
+            };
+            let previous = (settings.acceleration_mode, settings.cpu_num_threads);
+            let requested = (
+                acceleration_mode.map_or(previous.0, AccelerationMode::as_raw),
+                cpu_num_threads.unwrap_or(previous.1),
+            );
+            match init(requested.0, requested.1) {
+                Ok(()) => {
+                    (settings.acceleration_mode, settings.cpu_num_threads) = requested;
+                    Ok(())
+                }
+                Err(e) => {
+                    error!("Initializing voicevox again failed: {}", e);
+                    if let Err(restore) = init(previous.0, previous.1) {
+                        error!("Restoring the previous settings failed: {}", restore);
+                        initialized.remove(index);
+                        return Err(e);
+                    }
+                    Err(e)
+                }
+            }
+        };
+        debug!("Loading {} models again.", keep.len());
+        let loaded = self.load_models(keep).map_err(Error::ModelLoad);
+        result.and(loaded)
+    }
+
+    /// Describes this installation, e.g. for logs and bug reports.
+    pub fn info(&self) -> VoiceVoxInfo {
+        let core_version = {
+            let _core = fns::lock_shared();
+            unsafe { CStr::from_ptr((self.fns.get_version)()) }
+                .to_string_lossy()
+                .into_owned()
+        };
+        let loaded_models = self.loaded_styles();
+        let acceleration = self.init.then(|| {
+            let _core = fns::lock_shared();
+            if unsafe { (self.fns.is_gpu_mode)() } {
+                AccelerationMode::Gpu
+            } else {
+                AccelerationMode::Cpu
+            }
+        });
+
+        VoiceVoxInfo {
+            install_dir: self.dir.clone(),
+            library_path: self.library_path.clone(),
+            core_version,
+            dict_dir: self.dict_dir.clone(),
+            loaded_models,
+            acceleration,
+        }
+    }
+
+    /// The speakers and their styles available in the loaded voicevox core.
+    pub fn metas(&self) -> &[SpeakerMeta] {
+        &self.metas
+    }
+
+    /// The first style listed in [`VoiceVox::metas`], useful for examples and tests
+    /// that should work with any installation.
+    pub fn default_style(&self) -> Option<StyleId> {
+        self.metas
+            .iter()
+            .flat_map(|speaker| &speaker.styles)
+            .map(|style| style.id)
+            .next()
+    }
+
+    /// Returns [`Error::UnknownStyle`] if `style_id` is not part of [`VoiceVox::metas`].
+    fn check_style(&self, style_id: StyleId) -> Result<(), Error> {
+        let known = self
+            .metas
+            .iter()
+            .any(|speaker| speaker.styles.iter().any(|style| style.id == style_id));
+        if known {
+            Ok(())
+        } else {
+            Err(Error::UnknownStyle {
+                style_id,
+                available: metas::style_names(&self.metas),
+            })
+        }
+    }
+
+    /// Whether [`VoiceVox::init`] has been called successfully.
+    pub fn is_initialized(&self) -> bool {
+        self.init
+    }
+
+    fn check_initialized(&self) -> Result<(), Error> {
+        if self.init {
+            Ok(())
+        } else {
+            Err(Error::NotInitialized)
+        }
+    }
+
+    /// Clears the caches if voicevox was initialized with another dictionary than `dict_dir`
+    /// before, as the same text can be read differently with another dictionary.
+    fn clear_caches_if_dict_changed(&self, dict_dir: &Path) {
+        if self
+            .dict_dir
+            .as_deref()
+            .is_none_or(|previous| previous == dict_dir)
+        {
+            return;
+        }
+        debug!(
+            "The dictionary changed to {:?}, clearing the caches.",
+            dict_dir
+        );
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+        if let Some(cache) = &self.query_cache {
+            cache.clear();
+        }
+    }
+
+    /// Checks `style_id` and loads its model if [`VoiceVox::set_auto_load_models`] is enabled.
+    fn prepare_style(&self, style_id: StyleId) -> Result<(), Error> {
+        self.check_initialized()?;
+        self.check_style(style_id)?;
+        if self.auto_load_models && !self.is_model_loaded(style_id) {
+            debug!("Loading the model for style {} on demand.", style_id);
+            self.load_model(style_id)?;
+        }
+        self.model_usage.touch(style_id);
+        Ok(())
+    }
+
+    /// When enabled, [`VoiceVox::tts`], [`VoiceVox::audio_query`] and [`VoiceVox::synthesis`]
+    /// load the model of the requested style if it is not loaded yet,
+    /// instead of failing. Disabled by default.
+    pub fn set_auto_load_models(&mut self, auto_load_models: bool) {
+        self.auto_load_models = auto_load_models;
+    }
+
+    /// Caches the speech synthesized by [`VoiceVox::tts`] and everything built on it, see
+    /// [`SynthesisCache`]. `None`, which is the default, disables caching.
+    pub fn set_synthesis_cache(&mut self, cache: Option<SynthesisCache>) {
+        self.cache = cache.map(Arc::new);
+    }
+
+    /// The cache set with [`VoiceVox::set_synthesis_cache`], e.g. for its statistics.
+    pub fn synthesis_cache(&self) -> Option<&SynthesisCache> {
+        self.cache.as_deref()
+    }
+
+    /// Caches the queries created by [`VoiceVox::audio_query`] and everything built on it,
+    /// see [`AudioQueryCache`]. `None`, which is the default, disables caching.
+    pub fn set_audio_query_cache(&mut self, cache: Option<AudioQueryCache>) {
+        self.query_cache = cache.map(Arc::new);
+    }
+
+    /// The cache set with [`VoiceVox::set_audio_query_cache`], e.g. for its statistics.
+    pub fn audio_query_cache(&self) -> Option<&AudioQueryCache> {
+        self.query_cache.as_deref()
+    }
+
+    /// Whether a synthesis that exceeded [`SynthesisOptions::timeout`] is still running.
+    ///
+    /// Voicevox core can not interrupt it and can not be finalized while it runs, so until it
+    /// returns, every other call into the core waits for it, including
+    /// [`VoiceVox::set_cpu_threads`]. If it never returns, the core is stuck and the process
+    /// has to be restarted.
+    pub fn needs_reinit(&self) -> bool {
+        TIMED_OUT.load(Ordering::SeqCst) > 0
+    }
+
+    /// Runs [`VoiceVox::tts_with_options`] on a separate thread and gives up on it after
+    /// `timeout`.
+    fn tts_with_timeout(
+        &self,
+        text: String,
+        speaker_id: u32,
+        opts: SynthesisOptions,
+        timeout: Duration,
+    ) -> Result<Wav, Error> {
+        let vv = VoiceVox {
+            fns: self.fns.clone(),
+            init: self.init,
+            dir: self.dir.clone(),
+            library_path: self.library_path.clone(),
+            dict_dir: self.dict_dir.clone(),
+            metas: self.metas.clone(),
+            auto_load_models: self.auto_load_models,
+            cache: self.cache.clone(),
+            query_cache: self.query_cache.clone(),
+            eviction_policy: self.eviction_policy,
+            model_usage: self.model_usage.clone(),
+        };
+        let (sender, receiver) = mpsc::sync_channel(1);
+        std::thread::Builder::new()
+            .name("voicevox-watchdog".to_owned())
+            .spawn(move || {
+                let result = vv.tts_with_options(text, speaker_id, &opts);
+                // The receiver is only gone if the synthesis timed out.
+                if sender.send(result).is_err() {
+                    timed_out_finished();
+                }
+            })
+            .map_err(Error::io("failed to spawn the synthesis thread"))?;
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                timed_out(timeout);
+                Err(Error::Timeout(timeout))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(Error::io("the synthesis panicked")(
+                std::io::Error::other("the synthesis thread panicked"),
+            )),
+        }
+    }
+
+    /// Whether the model of `style_id` has been loaded.
+    pub fn is_model_loaded(&self, style_id: StyleId) -> bool {
+        let _core = fns::lock_shared();
+        unsafe { (self.fns.is_model_loaded)(style_id) }
+    }
+
+    /// The styles of [`VoiceVox::metas`] whose model is loaded.
+    fn loaded_styles(&self) -> Vec<StyleId> {
+        self.metas
+            .iter()
+            .flat_map(|speaker| &speaker.styles)
+            .map(|style| style.id)
+            .filter(|&id| self.is_model_loaded(id))
+            .collect()
+    }
+
+    /// Synthesizes a short text with `style_id` and discards it, so that the first synthesis
+    /// the user waits for is as fast as the ones after it, e.g. during a loading screen.
+    ///
+    /// Voicevox core sets up the inference of a model when it is first used, which makes the
+    /// first synthesis take several times as long. The model is loaded first if necessary,
+    /// like with [`VoiceVox::tts`], and caches are not used.
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, VoiceVox};
+    ///
+    /// # fn main() -> Result<(), voicevox_dyn::Error> {
+    /// let vv = VoiceVox::builder().load_models([ZUNDAMON_NORMAL]).build()?;
+    /// // Show the loading screen.
+    /// vv.warmup_loaded()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn warmup(&self, style_id: StyleId) -> Result<(), Error> {
+        let start = Instant::now();
+        self.tts_raw("あ", style_id, TtsOptions::default())?;
+        debug!("Warming up style {} took {:?}.", style_id, start.elapsed());
+        Ok(())
+    }
+
+    /// Runs [`VoiceVox::warmup`] for every style whose model is loaded, stopping at the first
+    /// error.
+    pub fn warmup_loaded(&self) -> Result<(), Error> {
+        self.loaded_styles()
+            .into_iter()
+            .try_for_each(|style_id| self.warmup(style_id))
+    }
+
     /// Loads one of the models.
-    pub fn load_model(&self, speaker_id: u32) -> Result<(), ResultCode> {
-        match unsafe { (self.fns.borrow_load_model())(speaker_id) } {
-            ResultCode::Ok => Ok(()),
-            e => Err(e),
+    ///
+    /// Unloads other models first if [`VoiceVox::set_model_eviction_policy`] requires it.
+    pub fn load_model(&self, speaker_id: u32) -> Result<(), Error> {
+        self.check_initialized()?;
+        self.check_style(speaker_id)?;
+        if !self.is_model_loaded(speaker_id) {
+            self.evict_models_for(1)?;
+        }
+        self.model_usage.touch(speaker_id);
+        let _core = fns::lock_exclusive();
+        if unsafe { (self.fns.is_model_loaded)(speaker_id) } {
+            return Ok(());
+        }
+        let styles = || {
+            self.metas
+                .iter()
+                .flat_map(|speaker| &speaker.styles)
+                .map(|style| style.id)
+                .filter(|&id| unsafe { (self.fns.is_model_loaded)(id) })
+        };
+        let before: Vec<StyleId> = styles().collect();
+        match memory::measure_model_load(speaker_id, || unsafe {
+            (self.fns.load_model)(speaker_id)
+        }) {
+            ResultCode::Ok => {
+                let model: Vec<StyleId> = styles().filter(|id| !before.contains(id)).collect();
+                self.model_usage.record_model(speaker_id, &model);
+                Ok(())
+            }
+            e => Err(e.into()),
+        }
+    }
+
+    /// Loads multiple models. Failing to load a model does not stop the remaining
+    /// models from being loaded, instead every failure is collected into the returned
+    /// [`ModelLoadReport`].
+    pub fn load_models(&self, style_ids: &[StyleId]) -> Result<(), ModelLoadReport> {
+        self.load_models_with_progress(style_ids, |_, _| {})
+    }
+
+    /// Same as [`VoiceVox::load_models`] but calls `progress` with the index and the style id
+    /// of every model before loading it.
+    pub(crate) fn load_models_with_progress(
+        &self,
+        style_ids: &[StyleId],
+        mut progress: impl FnMut(usize, StyleId),
+    ) -> Result<(), ModelLoadReport> {
+        let mut report = ModelLoadReport::default();
+        for (index, &style_id) in style_ids.iter().enumerate() {
+            progress(index, style_id);
+            match self.load_model(style_id) {
+                Ok(()) => report.loaded.push(style_id),
+                Err(e) => report.failed.push((style_id, e)),
+            }
+        }
+
+        if report.failed.is_empty() {
+            Ok(())
+        } else {
+            Err(report)
         }
     }
 
     /// Synthesizes speech from the given text.
     ///
-    /// To get a list of speaker ids, run the [`VoiceVox::new`] once
-    /// and check `model/metas.json` in the directory of the executable.
+    /// The available speaker ids are listed in [`VoiceVox::metas`].
+    ///
+    /// Returns [`Error::InvalidInput`] if `text` contains a NUL byte or is rejected by
+    /// [`TtsOptions::validate`], and [`Error::UnknownStyle`] if `speaker_id` does not exist.
+    ///
+    /// With [`VoiceVox::set_synthesis_cache`], speech that is in the cache is returned without
+    /// synthesizing it again or loading the model of the style, but still counts as a use of
+    /// the style for the [`ModelEvictionPolicy`]. The caches are cleared when voicevox is
+    /// initialized with another Open JTalk dictionary.
     pub fn tts(
         &self,
         text: impl AsRef<str>,
         speaker_id: u32,
         opts: TtsOptions,
-    ) -> Result<CPointerWrap<u8>, ResultCode> {
+    ) -> Result<WavBytes, Error> {
+        let Some(cache) = &self.cache else {
+            return self
+                .tts_raw(text, speaker_id, opts)
+                .map(CPointerWrap::into_owned);
+        };
+        let text = text.as_ref();
+        self.check_initialized()?;
+        self.check_style(speaker_id)?;
+        let key = cache::CacheKey::synthesis(text, speaker_id, opts);
+        if let Some(wav) = cache.get(&key) {
+            debug!("Found the speech for {} in the cache.", text);
+            // Keeps the model of a style that is only served from the cache from being evicted.
+            self.model_usage.touch(speaker_id);
+            return Ok(WavBytes(wav));
+        }
+        let wav = self.tts_raw(text, speaker_id, opts)?.into_owned();
+        cache.insert(key, wav.0.clone());
+        Ok(wav)
+    }
+
+    /// Same as [`VoiceVox::tts`] but parses the result into a [`Wav`].
+    pub fn tts_wav(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<Wav, Error> {
+        if self.cache.is_some() {
+            return Wav::parse_output(self.tts(text, speaker_id, opts)?.as_slice());
+        }
+        Wav::parse_output(&self.tts_raw(text, speaker_id, opts)?)
+    }
+
+    /// Synthesizes `text` and plays it on the default output device,
+    /// blocking until playback has finished.
+    #[cfg(feature = "playback")]
+    pub fn say(&self, text: impl AsRef<str>, speaker_id: u32) -> Result<(), Error> {
+        let wav = self.tts_wav(text, speaker_id, TtsOptions::default())?;
+        play_wav(&wav)?.wait();
+        Ok(())
+    }
+
+    /// Same as [`VoiceVox::tts_wav`] but prepares the text and post-processes the result as
+    /// configured in `opts`, e.g. resampling it.
+    ///
+    /// Text longer than [`SynthesisOptions::max_input_len`] is split into pieces that are
+    /// synthesized one after another, see [`SynthesisOptions::on_long_input`]. Pause tags such
+    /// as `[pause 500ms]` are turned into silence, see [`SynthesisOptions::pause_markup`].
+    pub fn tts_with_options(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: &SynthesisOptions,
+    ) -> Result<Wav, Error> {
+        if let Some((timeout, opts)) = opts.watchdog() {
+            return self.tts_with_timeout(text.as_ref().to_owned(), speaker_id, opts, timeout);
+        }
+        opts.check_cancelled()?;
+        let segments = opts.segments(text.as_ref())?;
+        let wav = match segments.as_slice() {
+            [ssml::Segment::Speech { text, prosody }] => {
+                let text = opts.prepare_text(text);
+                match opts.split(&text)?.as_slice() {
+                    [text] => self.tts_wav(text, speaker_id, opts.tts())?,
+                    pieces => {
+                        debug!("Synthesizing a long text in {} pieces.", pieces.len());
+                        let pieces = pieces
+                            .iter()
+                            .map(|piece| ssml::Segment::Speech {
+                                text: (*piece).to_owned(),
+                                prosody: *prosody,
+                            })
+                            .collect();
+                        self.synthesize_segments(pieces, speaker_id, opts.tts(), || {
+                            opts.check_cancelled()
+                        })?
+                    }
+                }
+            }
+            _ => {
+                let mut pieces = Vec::new();
+                for segment in segments {
+                    match segment {
+                        ssml::Segment::Speech { text, prosody } => {
+                            let text = opts.prepare_text(&text);
+                            pieces.extend(opts.split(&text)?.into_iter().map(|piece| {
+                                ssml::Segment::Speech {
+                                    text: piece.to_owned(),
+                                    prosody,
+                                }
+                            }));
+                        }
+                        pause => pieces.push(pause),
+                    }
+                }
+                self.synthesize_segments(pieces, speaker_id, opts.tts(), || opts.check_cancelled())?
+            }
+        };
+        opts.apply(wav)
+    }
+
+    /// Synthesizes many short texts, e.g. all the strings of a user interface, with
+    /// [`VoiceVox::tts_with_options`], returning a result for every item.
+    ///
+    /// With [`VoiceVox::set_auto_load_models`], the models of all styles in the batch are
+    /// loaded before synthesizing instead of on demand. To synthesize on several threads, use
+    /// [`VoiceVoxPool::tts_batch`].
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, SynthesisOptions, VoiceVox};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let vv = VoiceVox::quickstart(ZUNDAMON_NORMAL)?;
+    /// let items: Vec<_> = ["はい", "いいえ", "キャンセル"]
+    ///     .into_iter()
+    ///     .map(|text| (text.to_owned(), ZUNDAMON_NORMAL, SynthesisOptions::new()))
+    ///     .collect();
+    /// for (wav, (text, ..)) in vv.tts_batch(&items).into_iter().zip(&items) {
+    ///     std::fs::write(format!("{text}.wav"), wav?.to_bytes())?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tts_batch(
+        &self,
+        items: &[(String, StyleId, SynthesisOptions)],
+    ) -> Vec<Result<Wav, Error>> {
+        if self.auto_load_models && self.init {
+            let mut styles: Vec<StyleId> = items.iter().map(|(_, style_id, _)| *style_id).collect();
+            styles.sort_unstable();
+            styles.dedup();
+            styles.retain(|&style_id| {
+                self.check_style(style_id).is_ok() && !self.is_model_loaded(style_id)
+            });
+            // Models that fail to load are reported by the items using them.
+            let _ = self.load_models(&styles);
+        }
+        items
+            .iter()
+            .map(|(text, style_id, opts)| self.tts_with_options(text, *style_id, opts))
+            .collect()
+    }
+
+    /// Splits `text` into sentences and synthesizes them one at a time, see
+    /// [`text::split_sentences`].
+    ///
+    /// Every sentence is only synthesized once the iterator reaches it, so playback of long texts
+    /// can start right after the first one instead of waiting for the whole text.
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, VoiceVox};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let vv = VoiceVox::quickstart(ZUNDAMON_NORMAL)?;
+    /// let opts = Default::default();
+    /// for chunk in vv.tts_stream("おはよう。今日はいい天気ですね。", ZUNDAMON_NORMAL, &opts) {
+    ///     let chunk = chunk?;
+    ///     std::fs::write(format!("{}.wav", chunk.index), chunk.wav.to_bytes())?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tts_stream<'a>(
+        &'a self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: &'a SynthesisOptions,
+    ) -> TtsStream<'a> {
+        TtsStream::new(self, text::split_sentences(text.as_ref()), speaker_id, opts)
+    }
+
+    /// Same as [`VoiceVox::tts`] but writes the wav into `buf`, replacing its contents.
+    ///
+    /// The buffer allocated by voicevox is freed right after copying, so reusing `buf`
+    /// avoids allocating for every utterance.
+    pub fn tts_into(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        buf.clear();
+        if self.cache.is_some() {
+            buf.extend_from_slice(self.tts(text, speaker_id, opts)?.as_slice());
+            return Ok(());
+        }
+        buf.extend_from_slice(&self.tts_raw(text, speaker_id, opts)?);
+        Ok(())
+    }
+
+    /// Same as [`VoiceVox::tts`] but writes the wav to `writer`, e.g. a socket.
+    ///
+    /// Without a [`SynthesisCache`], the buffer allocated by voicevox is written directly,
+    /// without copying it first.
+    pub fn tts_to_writer(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+        writer: &mut impl Write,
+    ) -> Result<(), Error> {
+        let result = if self.cache.is_some() {
+            writer.write_all(self.tts(text, speaker_id, opts)?.as_slice())
+        } else {
+            writer.write_all(&self.tts_raw(text, speaker_id, opts)?)
+        };
+        result.map_err(Error::io("failed to write the synthesized wav"))
+    }
+
+    /// Same as [`VoiceVox::tts`] but writes the wav to the file at `path`,
+    /// replacing it if it exists.
+    pub fn tts_to_file(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+        path: impl AsRef<Path>,
+    ) -> Result<(), Error> {
+        if self.cache.is_some() {
+            return write_file(path.as_ref(), self.tts(text, speaker_id, opts)?.as_slice());
+        }
+        write_file(path.as_ref(), &self.tts_raw(text, speaker_id, opts)?)
+    }
+
+    /// Same as [`VoiceVox::tts_to_file`] but also writes the timing of every phoneme as an
+    /// HTK label file next to it, with the extension replaced by `lab`, see [`AudioQuery::to_lab`].
+    pub fn tts_to_file_with_labels(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+        path: impl AsRef<Path>,
+    ) -> Result<(), Error> {
+        let path = path.as_ref();
+        let query = self.audio_query(text, speaker_id, opts)?;
+        let wav = self.synthesis_raw(&query, speaker_id, opts)?;
+        write_file(path, &wav)?;
+        write_file(&path.with_extension("lab"), query.to_lab().as_bytes())
+    }
+
+    /// Same as [`VoiceVox::tts`] but returns the buffer allocated by voicevox
+    /// instead of copying it.
+    pub fn tts_raw(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<CPointerWrap<u8>, Error> {
         let text = text.as_ref();
         info!("Synthesizing speech from: {}", text);
 
-        let text = std::ffi::CString::new(text).unwrap();
+        self.prepare_style(speaker_id)?;
+        opts.validate(text)?;
+        self.tts_c(&c_string(text)?, speaker_id, opts)
+    }
+
+    /// Synthesizes `text`, which was checked by the caller, with voicevox core.
+    pub(crate) fn tts_c(
+        &self,
+        text: &CStr,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<CPointerWrap<u8>, Error> {
         let mut output_wav_length = 0;
         let mut output_wav = std::ptr::null_mut();
 
+        let _core = fns::lock_exclusive();
         match unsafe {
-            (self.fns.borrow_tts())(
+            (self.fns.tts)(
                 text.as_ptr(),
                 speaker_id,
                 opts,
@@ -191,47 +1092,556 @@ impl VoiceVox {
             ResultCode::Ok => Ok(CPointerWrap::new(
                 output_wav,
                 output_wav_length,
-                self.fns.borrow_wav_free(),
+                self.fns.wav_free,
+                self.fns.lib.clone(),
             )),
-            e => Err(e),
+            e => Err(e.into()),
+        }
+    }
+
+    /// Creates an [`AudioQuery`] from the given text, which can be modified
+    /// and then synthesized with [`VoiceVox::synthesis`].
+    ///
+    /// Only [`TtsOptions::kana`] is used from `opts`.
+    ///
+    /// With [`VoiceVox::set_audio_query_cache`], a query that is in the cache is returned
+    /// without analyzing the text again.
+    pub fn audio_query(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<AudioQuery, Error> {
+        let text = text.as_ref();
+        let Some(cache) = &self.query_cache else {
+            return self.create_audio_query(text, speaker_id, opts);
+        };
+        let key = cache::CacheKey::audio_query(text, speaker_id, opts);
+        if let Some(query) = cache.get(&key) {
+            return Ok(query);
         }
+        let query = self.create_audio_query(text, speaker_id, opts)?;
+        cache.insert(key, query.clone());
+        Ok(query)
+    }
+
+    /// Creates an [`AudioQuery`] with voicevox core, see [`VoiceVox::audio_query`].
+    fn create_audio_query(
+        &self,
+        text: &str,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<AudioQuery, Error> {
+        self.prepare_style(speaker_id)?;
+        opts.validate(text)?;
+        let text = c_string(text)?;
+        let mut output_json = std::ptr::null_mut();
+
+        let _core = fns::lock_exclusive();
+        match unsafe {
+            (self.fns.audio_query)(
+                text.as_ptr(),
+                speaker_id,
+                RawAudioQueryOptions { kana: opts.kana },
+                &mut output_json,
+            )
+        } {
+            ResultCode::Ok => {
+                let json = unsafe { CStr::from_ptr(output_json) };
+                let query = json
+                    .to_str()
+                    .map_err(|e| Error::InvalidOutput(e.to_string()))
+                    .and_then(|json| {
+                        serde_json::from_str(json).map_err(|e| Error::InvalidOutput(e.to_string()))
+                    });
+                unsafe { (self.fns.audio_query_json_free)(output_json) };
+                query
+            }
+            e => Err(e.into()),
+        }
+    }
+
+    /// Creates an [`AudioQuery`] from the given text for tuning its accents, pauses and pitch,
+    /// see [`Utterance`].
+    pub fn utterance(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<Utterance<'_>, Error> {
+        let query = self.audio_query(text, speaker_id, opts)?;
+        Ok(Utterance::from_query(self, query, speaker_id, opts))
+    }
+
+    /// Synthesizes speech from an [`AudioQuery`].
+    ///
+    /// Only [`TtsOptions::enable_interrogative_upspeak`] is used from `opts`.
+    pub fn synthesis(
+        &self,
+        query: &AudioQuery,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<WavBytes, Error> {
+        self.synthesis_raw(query, speaker_id, opts)
+            .map(CPointerWrap::into_owned)
+    }
+
+    /// Same as [`VoiceVox::synthesis`] but returns the buffer allocated by voicevox
+    /// instead of copying it.
+    pub fn synthesis_raw(
+        &self,
+        query: &AudioQuery,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<CPointerWrap<u8>, Error> {
+        self.prepare_style(speaker_id)?;
+        let json = serde_json::to_string(query).map_err(|e| Error::InvalidInput(e.to_string()))?;
+        let json = c_string(&json)?;
+        let mut output_wav_length = 0;
+        let mut output_wav = std::ptr::null_mut();
+
+        let _core = fns::lock_exclusive();
+        match unsafe {
+            (self.fns.synthesis)(
+                json.as_ptr(),
+                speaker_id,
+                RawSynthesisOptions {
+                    enable_interrogative_upspeak: opts.enable_interrogative_upspeak,
+                },
+                &mut output_wav_length,
+                &mut output_wav,
+            )
+        } {
+            ResultCode::Ok => Ok(CPointerWrap::new(
+                output_wav,
+                output_wav_length,
+                self.fns.wav_free,
+                self.fns.lib.clone(),
+            )),
+            e => Err(e.into()),
+        }
+    }
+
+    /// Same as [`VoiceVox::tts_wav`] but also returns when every sentence of `text` is spoken,
+    /// e.g. for subtitles, see [`captions`].
+    ///
+    /// Every sentence is synthesized separately, captions start after the silence before the
+    /// speech of their sentence and end before the silence after it.
+    /// For the timing of single words see [`AudioQuery::accent_phrase_times`].
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::{captions, styles::ZUNDAMON_NORMAL, VoiceVox};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let vv = VoiceVox::quickstart(ZUNDAMON_NORMAL)?;
+    /// let (wav, captions) =
+    ///     vv.tts_with_captions("おはよう。今日はいい天気ですね。", ZUNDAMON_NORMAL, Default::default())?;
+    /// std::fs::write("speech.wav", wav.to_bytes())?;
+    /// std::fs::write("speech.srt", captions::to_srt(&captions))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tts_with_captions(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<(Wav, Vec<CaptionSegment>), Error> {
+        let mut parts = Vec::new();
+        let mut captions = Vec::new();
+        let mut offset = Duration::ZERO;
+        for sentence in text::split_sentences(text.as_ref()) {
+            let query = self.audio_query(sentence, speaker_id, opts)?;
+            let wav = Wav::parse_output(&self.synthesis_raw(&query, speaker_id, opts)?)?;
+            let speed = query.speed_scale as f64;
+            let duration = wav.duration();
+            captions.push(CaptionSegment {
+                text: sentence.to_owned(),
+                start: offset + Duration::from_secs_f64(query.pre_phoneme_length as f64 / speed),
+                end: (offset + duration).saturating_sub(Duration::from_secs_f64(
+                    query.post_phoneme_length as f64 / speed,
+                )),
+            });
+            offset += duration;
+            parts.push(wav);
+        }
+        if parts.is_empty() {
+            return Err(Error::InvalidInput(
+                "the text contains no sentences".to_owned(),
+            ));
+        }
+        Ok((Wav::concat(&parts, Duration::ZERO)?, captions))
+    }
+
+    /// Estimates how long the speech for `text` will be with the given [`SpeechParams`],
+    /// see [`AudioQuery::duration`].
+    ///
+    /// This only creates an [`AudioQuery`], which is a lot faster than synthesizing the speech.
+    pub fn estimate_duration(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+        params: &SpeechParams,
+    ) -> Result<Duration, Error> {
+        let mut query = self.audio_query(text, speaker_id, opts)?;
+        params.apply(&mut query);
+        Ok(query.duration())
+    }
+
+    /// Synthesizes speech from an SSML document, see [`ssml`] for the supported tags.
+    ///
+    /// Every [`ssml::Segment::Speech`] is synthesized separately with its prosody applied to
+    /// the [`AudioQuery`], breaks lengthen the silence around the neighbouring speech.
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, VoiceVox};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let vv = VoiceVox::quickstart(ZUNDAMON_NORMAL)?;
+    /// let wav = vv.tts_ssml(
+    ///     r#"<speak>ただいま<break time="1s"/><prosody pitch="high">おかえり！</prosody></speak>"#,
+    ///     ZUNDAMON_NORMAL,
+    ///     Default::default(),
+    /// )?;
+    /// std::fs::write("speech.wav", wav.to_bytes())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tts_ssml(
+        &self,
+        ssml: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Result<Wav, Error> {
+        let segments = ssml::parse(ssml.as_ref())?;
+        if !segments
+            .iter()
+            .any(|segment| matches!(segment, ssml::Segment::Speech { .. }))
+        {
+            return Err(Error::InvalidInput("the SSML contains no text".to_owned()));
+        }
+        self.synthesize_segments(segments, speaker_id, opts, || Ok(()))
+    }
+
+    /// Synthesizes every [`ssml::Segment::Speech`] separately, with the breaks added to the
+    /// silence around the neighbouring speech. `check` is called before every segment, if it
+    /// cancels the synthesis the segments synthesized before are returned with the error.
+    ///
+    /// The audio queries are created on a separate thread, so the query of the next segment is
+    /// ready when the synthesis of the previous one finishes. Voicevox core runs one call at a
+    /// time, so this saves the work done around the calls, such as converting the queries from
+    /// and to JSON and decoding the wavs.
+    fn synthesize_segments(
+        &self,
+        segments: Vec<ssml::Segment>,
+        speaker_id: u32,
+        opts: TtsOptions,
+        check: impl Fn() -> Result<(), Error> + Sync,
+    ) -> Result<Wav, Error> {
+        let check = &check;
+        // Only one query waits, so a failed synthesis stops the queries soon.
+        let (sender, receiver) = mpsc::sync_channel::<Result<(String, AudioQuery), Error>>(1);
+        let chunks = std::thread::scope(|scope| {
+            std::thread::Builder::new()
+                .name("voicevox-query".to_owned())
+                .spawn_scoped(scope, move || {
+                    let result = self.query_segments(segments, speaker_id, opts, check, |query| {
+                        sender.send(Ok(query)).is_ok()
+                    });
+                    if let Err(e) = result {
+                        let _ = sender.send(Err(e));
+                    }
+                })
+                .map_err(Error::io("failed to spawn the audio query thread"))?;
+
+            let mut chunks = Vec::new();
+            for (index, query) in receiver.into_iter().enumerate() {
+                let (text, query) = match query.and_then(|query| check().map(|()| query)) {
+                    Ok(query) => query,
+                    Err(e) => return Err(e.with_completed(chunks)),
+                };
+                let wav = Wav::parse_output(&self.synthesis_raw(&query, speaker_id, opts)?)?;
+                chunks.push(AudioChunk { index, text, wav });
+            }
+            Ok(chunks)
+        })?;
+        if chunks.is_empty() {
+            return Err(Error::InvalidInput(
+                "there is no text to synthesize".to_owned(),
+            ));
+        }
+        let parts: Vec<Wav> = chunks.into_iter().map(|chunk| chunk.wav).collect();
+        Wav::concat(&parts, Duration::ZERO)
+    }
+
+    /// Creates the audio queries of [`VoiceVox::synthesize_segments`] and passes them to `send`
+    /// in order, until it returns `false`.
+    ///
+    /// A query is passed on once the break after it is known, which is when the next speech
+    /// starts.
+    fn query_segments(
+        &self,
+        segments: Vec<ssml::Segment>,
+        speaker_id: u32,
+        opts: TtsOptions,
+        check: impl Fn() -> Result<(), Error>,
+        mut send: impl FnMut((String, AudioQuery)) -> bool,
+    ) -> Result<(), Error> {
+        let mut previous: Option<(String, AudioQuery)> = None;
+        let mut pause = Duration::ZERO;
+        for segment in segments {
+            match segment {
+                ssml::Segment::Break(duration) => pause += duration,
+                ssml::Segment::Speech { text, prosody } => {
+                    // The silence is stretched along with the speech, so it is scaled by the speed.
+                    let pause = std::mem::take(&mut pause).as_secs_f32();
+                    let mut pre_pause = 0.0;
+                    match previous.take() {
+                        Some((previous_text, mut last)) => {
+                            last.post_phoneme_length += pause * last.speed_scale;
+                            if !send((previous_text, last)) {
+                                return Ok(());
+                            }
+                        }
+                        None => pre_pause = pause,
+                    }
+                    check()?;
+                    let mut query = self.audio_query(&text, speaker_id, opts)?;
+                    prosody.apply(&mut query);
+                    query.pre_phoneme_length += pre_pause * query.speed_scale;
+                    previous = Some((text, query));
+                }
+            }
+        }
+        if let Some((text, mut last)) = previous {
+            last.post_phoneme_length += pause.as_secs_f32() * last.speed_scale;
+            send((text, last));
+        }
+        Ok(())
+    }
+
+    /// Synthesizes speech from the given text with the given [`SpeechParams`].
+    ///
+    /// This creates an [`AudioQuery`], applies `params` to it and synthesizes it.
+    pub fn tts_with_params(
+        &self,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: TtsOptions,
+        params: &SpeechParams,
+    ) -> Result<WavBytes, Error> {
+        let text = text.as_ref();
+        info!("Synthesizing speech with {:?} from: {}", params, text);
+
+        let mut query = self.audio_query(text, speaker_id, opts)?;
+        params.apply(&mut query);
+        self.synthesis(&query, speaker_id, opts)
     }
 }
 
-fn download_path() -> color_eyre::Result<PathBuf> {
-    let exe_path = std::env::current_exe()?;
+/// Writes `bytes` to the file at `path`, naming the path in the error.
+pub(crate) fn write_file(path: &Path, bytes: &[u8]) -> Result<(), Error> {
+    std::fs::write(path, bytes).map_err(Error::io(format!("failed to write {}", path.display())))
+}
+
+/// Converts user provided text into a `CString`, rejecting interior NUL bytes.
+fn c_string(text: &str) -> Result<std::ffi::CString, Error> {
+    std::ffi::CString::new(text).map_err(|e| {
+        Error::InvalidInput(format!(
+            "text contains a NUL byte at position {}",
+            e.nul_position()
+        ))
+    })
+}
+
+/// The path of the voicevox core library in `dir`.
+fn library_path(dir: &Path) -> PathBuf {
+    #[cfg(target_os = "windows")]
+    let dll = dir.join("voicevox_core.dll");
+    #[cfg(target_os = "macos")]
+    let dll = dir.join("libvoicevox_core.dylib");
+    #[cfg(target_os = "linux")]
+    let dll = dir.join("libvoicevox_core.so");
+    dll
+}
+
+/// Downloads the voicevox downloader of `version` into `dir` and runs it with `args`, see
+/// [`VoiceVox::load_with_args`].
+fn download_to<S: AsRef<OsStr>>(
+    dir: &Path,
+    version: Option<&str>,
+    args: impl IntoIterator<Item = S>,
+) -> Result<(), Error> {
+    // get the downloader
+    info!("Downloading voicevox downloader.");
+    std::fs::create_dir_all(dir).map_err(DownloadError::io(format!(
+        "failed to create directory {:?}",
+        dir
+    )))?;
+    let mut reader = ureq::get(&voicevox_downloader_url(version)?)
+        .call()?
+        .into_reader();
+    let downloader_path = dir.join("voicevox_downloader");
+    let file = std::fs::File::create(&downloader_path).map_err(DownloadError::io(format!(
+        "failed to create {:?}",
+        downloader_path
+    )))?;
+    std::io::copy(&mut reader, &mut std::io::BufWriter::new(file)).map_err(DownloadError::io(
+        "failed to download the voicevox downloader",
+    ))?;
+
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&downloader_path, std::fs::Permissions::from_mode(0o755))
+            .map_err(DownloadError::io(
+                "failed to make the voicevox downloader executable",
+            ))?;
+    }
+
+    // use the downloader
+    let mut child = std::process::Command::new(downloader_path)
+        .args([
+            "-o",
+            dir.to_str()
+                .ok_or_else(|| DownloadError::InvalidPath(dir.to_owned()))?,
+        ])
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(DownloadError::io("failed to run the voicevox downloader"))?;
+
+    info!("Downloading voicevox. This may take a while, roughly 700MB of data will be downloaded.");
+    // This doesn't output the progress bars, so not very useful.
+    // let mut out = child.stdout.take().unwrap();
+    // let mut err = child.stderr.take().unwrap();
+    // std::thread::spawn(move || {
+    //     std::io::copy(&mut out, &mut std::io::stderr()).unwrap();
+    // });
+    // std::thread::spawn(move || {
+    //     std::io::copy(&mut err, &mut std::io::stdout()).unwrap();
+    // });
+
+    let status = child
+        .wait()
+        .map_err(DownloadError::io("failed to run the voicevox downloader"))?;
+    if !status.success() {
+        return Err(DownloadError::Downloader(status).into());
+    }
+    Ok(())
+}
+
+/// Returns the first existing Open JTalk dictionary, see [`VoiceVox::init`] for the search order.
+fn find_dict_dir(install_dir: Option<&Path>) -> Result<PathBuf, Error> {
+    let mut candidates: Vec<PathBuf> = std::env::var_os("VOICEVOX_OPEN_JTALK_DICT_DIR")
+        .map(PathBuf::from)
+        .into_iter()
+        .collect();
+    let dirs = [
+        install_dir.map(Path::to_owned),
+        download_path().ok(),
+        std::env::current_dir().ok(),
+    ];
+    candidates.extend(
+        dirs.into_iter()
+            .flatten()
+            .map(|dir| dir.join(OPEN_JTALK_DICT_DIR)),
+    );
+    // The install dir is usually the directory of the executable.
+    candidates.dedup();
+
+    match candidates.iter().find(|dir| dir.is_dir()) {
+        Some(dir) => Ok(dir.clone()),
+        None => Err(Error::DictionaryNotFound { tried: candidates }),
+    }
+}
+
+fn download_path() -> Result<PathBuf, Error> {
+    let exe_path =
+        std::env::current_exe().map_err(Error::io("failed to get the path of the executable"))?;
     Ok(exe_path
         .parent()
-        .ok_or(color_eyre::eyre::eyre!("exe path has no parent directory"))?
+        .ok_or_else(|| Error::Io {
+            context: "failed to get the directory of the executable".to_owned(),
+            source: std::io::Error::other("exe path has no parent directory"),
+        })?
         .to_owned())
 }
 
-fn voicevox_downloader_url() -> color_eyre::Result<String> {
+fn voicevox_downloader_url(version: Option<&str>) -> Result<String, Error> {
     let os = match std::env::consts::OS {
         os @ "windows" | os @ "linux" => os,
         "macos" => "osx",
-        _ => bail!("unsupported os"),
+        os => return Err(DownloadError::UnsupportedPlatform(os.to_owned()).into()),
     };
     let arch = match std::env::consts::ARCH {
         "x86_64" => "x64",
         "aarch64" => "arm64",
-        _ => bail!("unsupported arch"),
+        arch => return Err(DownloadError::UnsupportedPlatform(arch.to_owned()).into()),
     };
     let extension = match os {
         "windows" => ".exe",
         _ => "",
     };
-    let base = "https://github.com/VOICEVOX/voicevox_core/releases/latest/download/download-";
-    Ok(format!("{base}{os}-{arch}{extension}"))
+    let release = match version {
+        Some(version) => format!("download/{version}"),
+        None => "latest/download".to_owned(),
+    };
+    let base = "https://github.com/VOICEVOX/voicevox_core/releases";
+    Ok(format!("{base}/{release}/download-{os}-{arch}{extension}"))
 }
 
 #[repr(C)]
-#[derive(Default, Debug, Copy, Clone)]
-pub struct TtsOptions {
-    pub kana: bool,
-    pub enable_interrogative_upspeak: bool,
+#[derive(Debug, Clone, Copy)]
+struct RawAudioQueryOptions {
+    kana: bool,
 }
 
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct RawSynthesisOptions {
+    enable_interrogative_upspeak: bool,
+}
+
+/// The libraries initialized in this process and the options they were initialized with,
+/// see [`VoiceVox#multiple-instances`].
+static INITIALIZED: Mutex<Vec<InitSettings>> = Mutex::new(Vec::new());
+
+/// The number of syntheses that timed out and are still running, see
+/// [`VoiceVox::needs_reinit`].
+static TIMED_OUT: AtomicUsize = AtomicUsize::new(0);
+
+/// Records that a synthesis timed out, see [`SynthesisOptions::timeout`].
+fn timed_out(timeout: Duration) {
+    error!("A synthesis did not finish within {:?}.", timeout);
+    TIMED_OUT.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Records that a synthesis that timed out finished after all.
+fn timed_out_finished() {
+    info!("A synthesis that timed out finished after all.");
+    TIMED_OUT.fetch_sub(1, Ordering::SeqCst);
+}
+
+#[derive(Debug, PartialEq)]
+struct InitSettings {
+    library: PathBuf,
+    acceleration_mode: i32,
+    cpu_num_threads: u16,
+    dict_dir: String,
+}
+
+/// The options voicevox core is initialized with, mirroring `VoicevoxInitializeOptions` of its
+/// C API.
+///
+/// # ONNX Runtime options
+/// Voicevox core 0.14 creates the ONNX Runtime sessions itself and only exposes the options
+/// here. The graph optimization level, the memory arena and the options of the execution
+/// provider are fixed by the core and can not be configured through this crate. The
+/// synthesizer options of later core versions are not supported either, as this crate loads
+/// the 0.14 C API.
 #[repr(C)]
 #[derive(Debug, Clone)]
 pub struct InitOptions {
@@ -241,131 +1651,318 @@ pub struct InitOptions {
     open_jtalk_dict_dir: *mut ::std::os::raw::c_char,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AccelerationMode {
     Auto,
     Cpu,
     Gpu,
 }
 
+impl AccelerationMode {
+    /// The value of `VoicevoxAccelerationMode` in the C API.
+    fn as_raw(self) -> i32 {
+        match self {
+            AccelerationMode::Auto => 0,
+            AccelerationMode::Cpu => 1,
+            AccelerationMode::Gpu => 2,
+        }
+    }
+
+    fn from_raw(raw: i32) -> Option<Self> {
+        match raw {
+            0 => Some(AccelerationMode::Auto),
+            1 => Some(AccelerationMode::Cpu),
+            2 => Some(AccelerationMode::Gpu),
+            _ => None,
+        }
+    }
+}
+
+/// Name of the Open JTalk dictionary directory created by the voicevox downloader.
+const OPEN_JTALK_DICT_DIR: &str = "open_jtalk_dic_utf_8-1.11";
+
 impl InitOptions {
+    /// Searches for the Open JTalk dictionary like [`VoiceVox::init`].
     pub fn new(
         acceleration_mode: AccelerationMode,
         cpu_num_threads: u16,
         load_all_models: bool,
-    ) -> color_eyre::Result<Self> {
-        let p = download_path()?
-            .join("open_jtalk_dic_utf_8-1.11")
-            .canonicalize()?;
-        let open_jtalk_dict_dir = p
-            .to_str()
-            .ok_or(color_eyre::eyre::eyre!("failed to convert {:?} to str", p))?;
+    ) -> Result<Self, Error> {
+        Self::with_dict_dir(
+            acceleration_mode,
+            cpu_num_threads,
+            load_all_models,
+            find_dict_dir(None)?,
+        )
+    }
+
+    /// Same as [`InitOptions::new`] but uses the Open JTalk dictionary in `dict_dir`.
+    pub fn with_dict_dir(
+        acceleration_mode: AccelerationMode,
+        cpu_num_threads: u16,
+        load_all_models: bool,
+        dict_dir: impl AsRef<Path>,
+    ) -> Result<Self, Error> {
+        let dict_dir = dict_dir.as_ref();
+        let p = dict_dir.canonicalize().map_err(Error::io(format!(
+            "failed to find the open jtalk dictionary {:?}",
+            dict_dir
+        )))?;
+        let open_jtalk_dict_dir = dict_dir_to_c_string(&p)?;
 
         Ok(Self {
-            acceleration_mode: match acceleration_mode {
-                AccelerationMode::Auto => 0,
-                AccelerationMode::Cpu => 1,
-                AccelerationMode::Gpu => 2,
-            },
+            acceleration_mode: acceleration_mode.as_raw(),
             cpu_num_threads,
             load_all_models,
-            open_jtalk_dict_dir: std::ffi::CString::new(open_jtalk_dict_dir)
-                .unwrap()
-                .into_raw(),
+            open_jtalk_dict_dir: open_jtalk_dict_dir.into_raw(),
         })
     }
 }
 
-impl Drop for InitOptions {
-    fn drop(&mut self) {
-        drop(unsafe { std::ffi::CString::from_raw(self.open_jtalk_dict_dir) })
+impl InitOptions {
+    /// Initializes voicevox core with these options and frees the dictionary path afterwards,
+    /// which the core only reads during the call. Passing the options by value to `init`
+    /// moves them without dropping them.
+    pub(crate) unsafe fn initialize(
+        self,
+        init: unsafe extern "C" fn(InitOptions) -> ResultCode,
+    ) -> ResultCode {
+        let dict_dir = self.open_jtalk_dict_dir;
+        let result = init(self);
+        drop(std::ffi::CString::from_raw(dict_dir));
+        result
     }
 }
 
-#[repr(i32)]
-#[derive(Debug, PartialEq, Eq)]
-pub enum ResultCode {
-    /// Success
-    Ok = 0,
-    /// Failed to load Open JTalk dictionary file
-    NotLoadedOpenjtalkDictError = 1,
-    /// Failed to load the model
-    LoadModelError = 2,
-    /// Failed to get supported device information
-    GetSupportedDevicesError = 3,
-    /// GPU mode is not supported
-    GpuSupportError = 4,
-    /// Failed to load meta information
-    LoadMetasError = 5,
-    /// Status is uninitialized
-    UninitializedStatusError = 6,
-    /// Invalid speaker ID specified
-    InvalidSpeakerIdError = 7,
-    /// Invalid model index specified
-    InvalidModelIndexError = 8,
-    /// Inference failed
-    InferenceError = 9,
-    /// Failed to output context labels
-    ExtractFullContextLabelError = 10,
-    /// Invalid UTF-8 string input
-    InvalidUtf8InputError = 11,
-    /// Failed to parse Aquestalk-style text
-    ParseKanaError = 12,
-    /// Invalid AudioQuery
-    InvalidAudioQueryError = 13,
-}
-
-impl std::fmt::Display for ResultCode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = match self {
-            ResultCode::Ok => "Success",
-            ResultCode::NotLoadedOpenjtalkDictError => "Failed to load Open JTalk dictionary file",
-            ResultCode::LoadModelError => "Failed to load the model",
-            ResultCode::GetSupportedDevicesError => "Failed to get supported device information",
-            ResultCode::GpuSupportError => "GPU mode is not supported",
-            ResultCode::LoadMetasError => "Failed to load meta information",
-            ResultCode::UninitializedStatusError => "Status is uninitialized",
-            ResultCode::InvalidSpeakerIdError => "Invalid speaker ID specified",
-            ResultCode::InvalidModelIndexError => "Invalid model index specified",
-            ResultCode::InferenceError => "Inference failed",
-            ResultCode::ExtractFullContextLabelError => "Failed to output context labels",
-            ResultCode::InvalidUtf8InputError => "Invalid UTF-8 string input",
-            ResultCode::ParseKanaError => "Failed to parse Aquestalk-style text",
-            ResultCode::InvalidAudioQueryError => "Invalid AudioQuery",
-        };
-        write!(f, "{}", s)
+/// Converts a canonicalized dictionary path into the form the core expects.
+///
+/// On Windows `canonicalize` returns verbatim paths like `\\?\C:\dict`, which Open JTalk
+/// can not open, so the prefix is removed. Open JTalk also opens files with the ANSI
+/// code page there, so only ASCII paths are reliable.
+fn dict_dir_to_c_string(path: &Path) -> Result<std::ffi::CString, Error> {
+    let invalid = |reason: &str| {
+        Error::InvalidInput(format!(
+            "the open jtalk dictionary path {:?} {}, move the dictionary to another directory \
+             and pass it to `VoiceVox::init_with_dict_dir`",
+            path, reason
+        ))
+    };
+
+    let path = path.to_str().ok_or_else(|| invalid("is not valid UTF-8"))?;
+    let path = if cfg!(windows) {
+        match path.strip_prefix(r"\\?\") {
+            Some(unc) => match unc.strip_prefix(r"UNC\") {
+                Some(share) => format!(r"\\{share}"),
+                None => unc.to_owned(),
+            },
+            None => path.to_owned(),
+        }
+    } else {
+        path.to_owned()
+    };
+    if cfg!(windows) && !path.is_ascii() {
+        return Err(invalid(
+            "contains non-ASCII characters, which Open JTalk can not open on Windows",
+        ));
     }
+    std::ffi::CString::new(path).map_err(|_| invalid("contains a NUL byte"))
 }
 
-impl std::error::Error for ResultCode {}
+impl Drop for InitOptions {
+    fn drop(&mut self) {
+        drop(unsafe { std::ffi::CString::from_raw(self.open_jtalk_dict_dir) })
+    }
+}
 
 /// Once dropped the memory is freed.
-pub struct CPointerWrap<'a, T> {
+///
+/// The buffer keeps the voicevox core library loaded, so it can outlive the [`VoiceVox`]
+/// it was created by and be sent to other threads, e.g. for playback.
+pub struct CPointerWrap<T> {
     bytes: *mut T,
     length: usize,
-    free_fn: &'a Symbol<'a, unsafe extern "C" fn(*mut T)>,
+    free_fn: unsafe extern "C" fn(*mut T),
+    _lib: Arc<libloading::Library>,
 }
 
-impl<'a, T> CPointerWrap<'a, T> {
-    pub fn new(
+// SAFETY: The buffer is owned exclusively by the wrapper and freeing it
+// does not depend on the thread it was allocated on.
+unsafe impl<T: Send> Send for CPointerWrap<T> {}
+unsafe impl<T: Sync> Sync for CPointerWrap<T> {}
+
+impl<T> CPointerWrap<T> {
+    pub(crate) fn new(
         bytes: *mut T,
         length: usize,
-        free_fn: &'a Symbol<'a, unsafe extern "C" fn(*mut T)>,
+        free_fn: unsafe extern "C" fn(*mut T),
+        lib: Arc<libloading::Library>,
     ) -> Self {
         Self {
             bytes,
             length,
             free_fn,
+            _lib: lib,
         }
     }
 
     pub fn as_slice(&self) -> &[T] {
         unsafe { std::slice::from_raw_parts(self.bytes, self.length) }
     }
+
+    /// Copies the data into a `Vec`.
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.as_slice().to_vec()
+    }
+}
+
+impl CPointerWrap<u8> {
+    /// Copies the wav into a [`WavBytes`] and frees the memory allocated by voicevox.
+    pub fn into_owned(self) -> WavBytes {
+        WavBytes(self.to_vec())
+    }
 }
 
-impl<'a, T> Drop for CPointerWrap<'a, T> {
+impl<T> Drop for CPointerWrap<T> {
     fn drop(&mut self) {
         unsafe { (self.free_fn)(self.bytes) };
     }
 }
+
+impl<T> Deref for CPointerWrap<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T> AsRef<[T]> for CPointerWrap<T> {
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, I: SliceIndex<[T]>> Index<I> for CPointerWrap<T> {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        &self.as_slice()[index]
+    }
+}
+
+impl<'a, T> IntoIterator for &'a CPointerWrap<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
+/// A wav file synthesized by voicevox.
+///
+/// Unlike [`CPointerWrap`] it owns its data and does not keep the voicevox core library loaded.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WavBytes(pub Vec<u8>);
+
+impl WavBytes {
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+
+    /// Parses the wav, see [`Wav::parse`].
+    pub fn to_wav(&self) -> Result<Wav, Error> {
+        Wav::parse_output(&self.0)
+    }
+
+    /// The length of the speech.
+    ///
+    /// This and the other accessors read the wav header on every call without copying the samples.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use voicevox_dyn::{Wav, WavBytes};
+    ///
+    /// let wav = WavBytes(Wav::from_samples_i16(24000, 1, &[0; 12000]).to_bytes());
+    /// assert_eq!(wav.duration()?, Duration::from_millis(500));
+    /// assert_eq!(wav.num_samples()?, 12000);
+    /// # Ok::<(), voicevox_dyn::Error>(())
+    /// ```
+    pub fn duration(&self) -> Result<Duration, Error> {
+        let header = self.header()?;
+        Ok(Duration::from_secs_f64(
+            header.frames() as f64 / header.sample_rate as f64,
+        ))
+    }
+
+    pub fn sample_rate(&self) -> Result<u32, Error> {
+        Ok(self.header()?.sample_rate)
+    }
+
+    pub fn channels(&self) -> Result<u16, Error> {
+        Ok(self.header()?.channels)
+    }
+
+    /// The number of samples of every channel.
+    pub fn num_samples(&self) -> Result<usize, Error> {
+        Ok(self.header()?.frames())
+    }
+
+    fn header(&self) -> Result<wav::Header, Error> {
+        wav::parse_header(&self.0)
+            .map_err(|reason| Error::InvalidOutput(format!("invalid wav: {}", reason)))
+    }
+}
+
+impl From<WavBytes> for Vec<u8> {
+    fn from(wav: WavBytes) -> Self {
+        wav.0
+    }
+}
+
+impl Deref for WavBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for WavBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<I: SliceIndex<[u8]>> Index<I> for WavBytes {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a WavBytes {
+    type Item = &'a u8;
+    type IntoIter = std::slice::Iter<'a, u8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for WavBytes {
+    type Item = u8;
+    type IntoIter = std::vec::IntoIter<u8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+
+/// How busy a [`VoiceVoxHandle`](crate::VoiceVoxHandle) is, see
+/// [`VoiceVoxHandle::concurrency_stats`](crate::VoiceVoxHandle::concurrency_stats).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConcurrencyStats {
+    /// The number of calls that may run at once.
+    pub limit: usize,
+    /// The number of calls that are running.
+    pub running: usize,
+    /// The number of calls that wait for one of the running calls to finish.
+    pub waiting: usize,
+    /// The largest number of calls that were waiting at once.
+    pub peak_waiting: usize,
+}
+
+/// A semaphore that limits how many calls run at once.
+pub(crate) struct Limiter {
+    stats: Mutex<ConcurrencyStats>,
+    freed: Condvar,
+}
+
+/// Allows running a call until it is dropped.
+pub(crate) struct Permit<'a> {
+    limiter: &'a Limiter,
+}
+
+impl Limiter {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            stats: Mutex::new(ConcurrencyStats {
+                limit: limit.max(1),
+                running: 0,
+                waiting: 0,
+                peak_waiting: 0,
+            }),
+            freed: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ConcurrencyStats> {
+        self.stats.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Blocks until less than the limit of calls are running.
+    pub(crate) fn acquire(&self) -> Permit<'_> {
+        let mut stats = self.lock();
+        if stats.running >= stats.limit {
+            stats.waiting += 1;
+            stats.peak_waiting = stats.peak_waiting.max(stats.waiting);
+            while stats.running >= stats.limit {
+                stats = self
+                    .freed
+                    .wait(stats)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+            stats.waiting -= 1;
+        }
+        stats.running += 1;
+        Permit { limiter: self }
+    }
+
+    pub(crate) fn set_limit(&self, limit: usize) {
+        self.lock().limit = limit.max(1);
+        self.freed.notify_all();
+    }
+
+    pub(crate) fn stats(&self) -> ConcurrencyStats {
+        *self.lock()
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.limiter.lock().running -= 1;
+        self.limiter.freed.notify_one();
+    }
+}
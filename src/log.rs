@@ -0,0 +1,62 @@
+//! Receiving log messages without depending on `tracing`.
+//!
+//! With the `tracing` feature enabled, log messages are emitted as tracing events.
+//! Either way, a callback can be registered with [`set_logger`].
+
+use std::sync::OnceLock;
+
+/// The severity of a log message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+type Logger = Box<dyn Fn(Level, &str) + Send + Sync>;
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Registers a callback that receives all log messages of this crate.
+///
+/// Only one logger can be registered, returns `false` if a logger was already registered.
+///
+/// ```
+/// voicevox_dyn::log::set_logger(|level, message| eprintln!("[{:?}] {}", level, message));
+/// ```
+pub fn set_logger(logger: impl Fn(Level, &str) + Send + Sync + 'static) -> bool {
+    LOGGER.set(Box::new(logger)).is_ok()
+}
+
+#[doc(hidden)]
+pub fn log(level: Level, args: std::fmt::Arguments<'_>) {
+    if let Some(logger) = LOGGER.get() {
+        logger(level, &args.to_string());
+    }
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::info!($($arg)*);
+        $crate::log::log($crate::log::Level::Info, format_args!($($arg)*));
+    }};
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+        $crate::log::log($crate::log::Level::Debug, format_args!($($arg)*));
+    }};
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::error!($($arg)*);
+        $crate::log::log($crate::log::Level::Error, format_args!($($arg)*));
+    }};
+}
@@ -0,0 +1,198 @@
+//! Loudness measurement following ITU-R BS.1770 as used by EBU R128, volume adjustment and panning.
+
+use std::f64::consts::PI;
+
+use crate::Wav;
+
+/// Blocks quieter than this are ignored completely.
+const ABSOLUTE_GATE: f64 = -70.0;
+/// Blocks more than this below the ungated loudness are ignored.
+const RELATIVE_GATE: f64 = -10.0;
+/// Samples above this level are compressed by [`soft_clip`].
+const SOFT_CLIP_KNEE: f32 = 0.8;
+
+impl Wav {
+    /// The integrated loudness in LUFS, or `None` if the wav is silent.
+    ///
+    /// The loudness is measured over gated 400 ms blocks of K-weighted samples like EBU R128
+    /// prescribes, so pauses do not lower it. Wavs shorter than a block are measured as a whole.
+    pub fn loudness_lufs(&self) -> Option<f32> {
+        let block = (self.sample_rate as usize * 4 / 10).max(1);
+        let step = (self.sample_rate as usize / 10).max(1);
+
+        // The mean square of every block, summed over the channels.
+        let channels: Vec<Vec<f64>> = self
+            .channels_f32()
+            .iter()
+            .map(|c| k_weighted(c, self.sample_rate))
+            .collect();
+        let frames = channels[0].len();
+        if frames == 0 {
+            return None;
+        }
+        let powers: Vec<f64> = (0..=frames.saturating_sub(block))
+            .step_by(step)
+            .map(|start| {
+                let end = (start + block).min(frames);
+                channels
+                    .iter()
+                    .map(|c| {
+                        c[start..end].iter().map(|s| s * s).sum::<f64>() / (end - start) as f64
+                    })
+                    .sum()
+            })
+            .collect();
+
+        let gated_loudness = |threshold: f64| {
+            let gated: Vec<f64> = powers
+                .iter()
+                .copied()
+                .filter(|&power| loudness(power) > threshold)
+                .collect();
+            (!gated.is_empty()).then(|| loudness(gated.iter().sum::<f64>() / gated.len() as f64))
+        };
+        let ungated = gated_loudness(ABSOLUTE_GATE)?;
+        gated_loudness(ungated + RELATIVE_GATE).map(|loudness| loudness as f32)
+    }
+
+    /// Scales the wav to an integrated loudness of `target` LUFS, see [`Wav::loudness_lufs`].
+    /// The result is 16 bit.
+    ///
+    /// EBU R128 recommends -23 LUFS for broadcasting, streaming platforms use around -16 LUFS.
+    /// Samples that would exceed full scale are soft clipped like in [`Wav::gain_db`] and silent
+    /// wavs stay silent.
+    ///
+    /// ```
+    /// use voicevox_dyn::Wav;
+    ///
+    /// let samples: Vec<f32> = (0..24000).map(|i| (i as f32 / 10.0).sin() * 0.01).collect();
+    /// let wav = Wav::from_samples_f32(24000, 1, &samples).normalize_lufs(-23.0);
+    /// assert!((wav.loudness_lufs().unwrap() + 23.0).abs() < 0.1);
+    /// ```
+    pub fn normalize_lufs(&self, target: f32) -> Wav {
+        let loudness = self.loudness_lufs().unwrap_or(target);
+        self.gain_db(target - loudness)
+    }
+
+    /// Places the speech in the stereo field, from `-1.0` for left only over `0.0` for the
+    /// center to `1.0` for right only. The result is 16 bit stereo.
+    ///
+    /// The channels are mixed down first. Panning follows the balance law, so the speech keeps
+    /// its level in the channel it is panned towards and centered speech sounds like the stereo
+    /// output of [`SpeechParams::stereo`](crate::SpeechParams::stereo).
+    ///
+    /// ```
+    /// use voicevox_dyn::Wav;
+    ///
+    /// let wav = Wav::from_samples_i16(24000, 1, &[1000, -2000]);
+    /// assert_eq!(wav.pan(0.0).samples_i16(), [1000, 1000, -2000, -2000]);
+    /// assert_eq!(wav.pan(-0.5).samples_i16(), [1000, 500, -2000, -1000]);
+    /// ```
+    pub fn pan(&self, pan: f32) -> Wav {
+        let pan = pan.clamp(-1.0, 1.0);
+        let (left, right) = ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0));
+        let channels = self.channels as usize;
+        let samples: Vec<f32> = self
+            .samples_f32()
+            .chunks_exact(channels)
+            .flat_map(|frame| {
+                let mono = frame.iter().sum::<f32>() / channels as f32;
+                [mono * left, mono * right]
+            })
+            .collect();
+        Wav::from_samples_f32(self.sample_rate, 2, &samples)
+    }
+
+    /// Changes the volume by `delta` decibels, e.g. `-6.0` halves the amplitude.
+    /// The result is 16 bit.
+    ///
+    /// Instead of overflowing or clipping hard, loud samples are compressed smoothly
+    /// so they never exceed full scale.
+    ///
+    /// ```
+    /// use voicevox_dyn::Wav;
+    ///
+    /// let wav = Wav::from_samples_i16(24000, 1, &[1000, -20000, i16::MAX]);
+    /// let louder = wav.gain_db(6.0).samples_i16();
+    /// assert_eq!(louder[0], 1995);
+    /// assert!(louder[1] < -26000 && louder[2] > louder[1].abs() as i16);
+    /// ```
+    pub fn gain_db(&self, delta: f32) -> Wav {
+        let gain = 10f32.powf(delta / 20.0);
+        let samples: Vec<f32> = self
+            .samples_f32()
+            .iter()
+            .map(|s| soft_clip(s * gain))
+            .collect();
+        Wav::from_samples_f32(self.sample_rate, self.channels, &samples)
+    }
+}
+
+/// Leaves samples up to [`SOFT_CLIP_KNEE`] untouched and bends louder ones towards full scale.
+fn soft_clip(sample: f32) -> f32 {
+    let magnitude = sample.abs();
+    if magnitude <= SOFT_CLIP_KNEE {
+        return sample;
+    }
+    let headroom = 1.0 - SOFT_CLIP_KNEE;
+    let compressed = SOFT_CLIP_KNEE + headroom * ((magnitude - SOFT_CLIP_KNEE) / headroom).tanh();
+    compressed.copysign(sample)
+}
+
+fn loudness(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// Applies the K-weighting filter, a high shelf modelling the head followed by a high pass.
+///
+/// The coefficients are derived for any sampling rate the same way libebur128 does.
+fn k_weighted(samples: &[f32], sample_rate: u32) -> Vec<f64> {
+    let fs = sample_rate as f64;
+
+    let k = (PI * 1681.974450955533 / fs).tan();
+    let q = 0.7071752369554196;
+    let vh = 10f64.powf(3.999843853973347 / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    let k = (PI * 38.13547087602444 / fs).tan();
+    let q = 0.5003270373238773;
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    let samples: Vec<f64> = samples.iter().map(|&s| s as f64).collect();
+    high_pass.filter(&shelf.filter(&samples))
+}
+
+/// A second order IIR filter with `a[0]` normalized to 1.
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Biquad {
+    fn filter(&self, samples: &[f64]) -> Vec<f64> {
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        samples
+            .iter()
+            .map(|&x| {
+                let y = self.b[0] * x + self.b[1] * x1 + self.b[2] * x2
+                    - self.a[0] * y1
+                    - self.a[1] * y2;
+                (x2, x1, y2, y1) = (x1, x, y1, y);
+                y
+            })
+            .collect()
+    }
+}
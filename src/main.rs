@@ -0,0 +1,216 @@
+//! The `voicevox-dyn` command line tool, built with the `cli` feature.
+
+use std::{
+    error::Error,
+    ffi::OsString,
+    io::{Read, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use voicevox_dyn::{
+    styles::ZUNDAMON_NORMAL, AccelerationMode, Device, SpeechParams, StyleId, TtsOptions, VoiceVox,
+    VoiceVoxBuilder,
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Downloads and runs voicevox")]
+struct Cli {
+    /// Print debug messages.
+    #[arg(short, long, global = true)]
+    verbose: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Download voicevox without running it, e.g. to provision a machine or Docker image.
+    Download(DownloadArgs),
+    /// Synthesize speech in one go, downloading voicevox if necessary, e.g. to check an
+    /// installation or in shell scripts.
+    Say(SayArgs),
+}
+
+#[derive(Debug, Args)]
+struct SayArgs {
+    /// The text to speak. Read from stdin if missing.
+    text: Option<String>,
+    /// The style to speak with, see the styles module of voicevox-dyn.
+    #[arg(short, long, default_value_t = ZUNDAMON_NORMAL)]
+    style: StyleId,
+    /// The wav file to write. Written to stdout if missing.
+    #[arg(short, long)]
+    out: Option<PathBuf>,
+    /// The speed of the speech, 1.0 is normal speed.
+    #[arg(long, default_value_t = 1.0)]
+    speed: f32,
+    /// The pitch shift of the speech, 0.0 is no shift.
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pitch: f32,
+    /// The strength of the intonation, 1.0 is normal intonation and 0.0 is monotone.
+    #[arg(long, default_value_t = 1.0)]
+    intonation: f32,
+    /// The volume of the speech, 1.0 is normal volume.
+    #[arg(long, default_value_t = 1.0)]
+    volume: f32,
+    /// The text is in the kana notation of AquesTalk.
+    #[arg(long)]
+    kana: bool,
+    /// Whether inference runs on the CPU or GPU.
+    #[arg(long, value_enum, default_value_t = AccelerationArg::Auto)]
+    acceleration: AccelerationArg,
+    /// The number of threads used for inference on the CPU, 0 lets voicevox decide.
+    #[arg(long, default_value_t = 0)]
+    cpu_threads: u16,
+    #[command(flatten, next_help_heading = "Download")]
+    download: DownloadArgs,
+}
+
+/// Where and what to download.
+#[derive(Debug, Args)]
+struct DownloadArgs {
+    /// The directory voicevox is downloaded to. Defaults to the directory of this executable.
+    #[arg(long)]
+    dir: Option<PathBuf>,
+    /// The voicevox core release, e.g. 0.14.4. Defaults to the latest release.
+    #[arg(long)]
+    version: Option<String>,
+    /// The runtime to download.
+    #[arg(long, value_enum, default_value_t = DeviceArg::Cpu)]
+    device: DeviceArg,
+    /// Download only the core library and models, without the Open JTalk dictionary and the
+    /// additional libraries of the device.
+    #[arg(long)]
+    min: bool,
+    /// The release of the additional libraries of the device. Defaults to the latest release.
+    #[arg(long, value_name = "VERSION")]
+    additional_libraries_version: Option<String>,
+    /// Further arguments passed to the voicevox downloader as they are.
+    #[arg(last = true, value_name = "DOWNLOADER_ARGS")]
+    downloader_args: Vec<OsString>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum DeviceArg {
+    Cpu,
+    Cuda,
+    Directml,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum AccelerationArg {
+    Auto,
+    Cpu,
+    Gpu,
+}
+
+impl From<AccelerationArg> for AccelerationMode {
+    fn from(acceleration: AccelerationArg) -> Self {
+        match acceleration {
+            AccelerationArg::Auto => AccelerationMode::Auto,
+            AccelerationArg::Cpu => AccelerationMode::Cpu,
+            AccelerationArg::Gpu => AccelerationMode::Gpu,
+        }
+    }
+}
+
+impl From<DeviceArg> for Device {
+    fn from(device: DeviceArg) -> Self {
+        match device {
+            DeviceArg::Cpu => Device::Cpu,
+            DeviceArg::Cuda => Device::Cuda,
+            DeviceArg::Directml => Device::DirectMl,
+        }
+    }
+}
+
+impl DownloadArgs {
+    fn builder(&self) -> VoiceVoxBuilder {
+        let mut builder = VoiceVox::builder().device(self.device.into());
+        if let Some(dir) = &self.dir {
+            builder = builder.dir(dir);
+        }
+        if let Some(version) = &self.version {
+            builder = builder.version(version);
+        }
+        let mut args = Vec::new();
+        if self.min {
+            args.push(OsString::from("--min"));
+        }
+        if let Some(version) = &self.additional_libraries_version {
+            args.push(OsString::from("--additional-libraries-version"));
+            args.push(OsString::from(version));
+        }
+        args.extend(self.downloader_args.iter().cloned());
+        builder.downloader_args(args)
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    tracing_subscriber::fmt()
+        .with_max_level(if cli.verbose {
+            tracing::Level::DEBUG
+        } else {
+            tracing::Level::INFO
+        })
+        .with_writer(std::io::stderr)
+        .init();
+
+    let result = match cli.command {
+        Command::Download(args) => download(args),
+        Command::Say(args) => say(args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn download(args: DownloadArgs) -> Result<(), Box<dyn Error>> {
+    let dir = args.builder().download()?;
+    println!("{}", dir.display());
+    Ok(())
+}
+
+fn say(args: SayArgs) -> Result<(), Box<dyn Error>> {
+    let text = match args.text {
+        Some(text) => text,
+        None => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text)?;
+            text
+        }
+    };
+    let vv = args
+        .download
+        .builder()
+        .acceleration_mode(args.acceleration.into())
+        .cpu_num_threads(args.cpu_threads)
+        .load_models([args.style])
+        .build()?;
+    let opts = TtsOptions::builder().kana(args.kana).build();
+    let params = SpeechParams {
+        speed: args.speed,
+        pitch: args.pitch,
+        intonation: args.intonation,
+        volume: args.volume,
+        ..SpeechParams::default()
+    };
+    let wav = vv.tts_with_params(text.trim(), args.style, opts, &params)?;
+    match args.out {
+        Some(out) => std::fs::write(&out, &wav)
+            .map_err(|e| format!("failed to write {}: {}", out.display(), e))?,
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(&wav)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
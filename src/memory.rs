@@ -0,0 +1,199 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{AccelerationMode, StyleId, VoiceVox};
+
+/// How much memory voicevox uses, see [`VoiceVox::memory_report`].
+///
+/// The operating system does not tell which library allocated memory, so the memory of the
+/// models is estimated from how much the resident memory of the process grew while they were
+/// loaded. Allocations made by other threads meanwhile are counted as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// The resident memory of the whole process in bytes. Only measured on Linux.
+    pub process_rss: Option<u64>,
+    /// How much the resident memory grew while voicevox was initialized, which includes the
+    /// models loaded with `load_all_models`. `None` if it was not measured.
+    pub init_rss_delta: Option<i64>,
+    /// The styles whose models are loaded.
+    pub models: Vec<ModelMemory>,
+    /// The files in the model directory of voicevox core and their sizes in bytes.
+    pub model_files: Vec<(PathBuf, u64)>,
+    /// The GPU memory used by the process in bytes. Only queried with
+    /// [`AccelerationMode::Gpu`] on NVIDIA GPUs, with `nvidia-smi`.
+    pub gpu_memory: Option<u64>,
+}
+
+/// The memory of a loaded model, see [`MemoryReport::models`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelMemory {
+    pub style_id: StyleId,
+    /// How much the resident memory grew while the model was loaded with
+    /// [`VoiceVox::load_model`]. `None` if it was not measured, e.g. because it was loaded
+    /// during initialization. Styles sharing a model only grow it when the first one is
+    /// loaded.
+    pub rss_delta: Option<i64>,
+}
+
+impl MemoryReport {
+    /// The size of all [`MemoryReport::model_files`].
+    pub fn model_files_size(&self) -> u64 {
+        self.model_files.iter().map(|(_, size)| size).sum()
+    }
+}
+
+/// The growth of the resident memory measured while loading, shared by every [`VoiceVox`] as
+/// the core keeps its models in globals.
+static MEASURED: Mutex<Measured> = Mutex::new(Measured {
+    init: None,
+    models: BTreeMap::new(),
+});
+
+struct Measured {
+    init: Option<i64>,
+    models: BTreeMap<StyleId, i64>,
+}
+
+/// Runs `load` and records how much the resident memory grew meanwhile. Has to be called with
+/// the core locked, so other core calls do not distort the measurement.
+pub(crate) fn measure_init<T>(load: impl FnOnce() -> T) -> T {
+    let (result, delta) = measure(load);
+    let mut measured = MEASURED.lock().unwrap_or_else(PoisonError::into_inner);
+    measured.init = delta;
+    measured.models.clear();
+    result
+}
+
+/// Same as [`measure_init`] for loading the model of `style_id`.
+pub(crate) fn measure_model_load<T>(style_id: StyleId, load: impl FnOnce() -> T) -> T {
+    let (result, delta) = measure(load);
+    if let Some(delta) = delta {
+        MEASURED
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .models
+            .insert(style_id, delta);
+    }
+    result
+}
+
+fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<i64>) {
+    let before = process_rss();
+    let result = f();
+    let delta = before
+        .zip(process_rss())
+        .map(|(before, after)| after as i64 - before as i64);
+    (result, delta)
+}
+
+/// The resident memory of the process in bytes, from `/proc/self/status`.
+fn process_rss() -> Option<u64> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kib = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+/// The GPU memory used by this process in bytes, from `nvidia-smi`.
+fn gpu_memory() -> Option<u64> {
+    let output = Command::new("nvidia-smi")
+        .args([
+            "--query-compute-apps=pid,used_memory",
+            "--format=csv,noheader,nounits",
+        ])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let pid = std::process::id();
+    let mib: u64 = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once(','))
+        .filter(|(line_pid, _)| line_pid.trim().parse() == Ok(pid))
+        .filter_map(|(_, used)| used.trim().parse::<u64>().ok())
+        .sum();
+    Some(mib * 1024 * 1024)
+}
+
+/// The files in `dir` and its subdirectories with their sizes.
+fn files_with_sizes(dir: &Path, files: &mut Vec<(PathBuf, u64)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            files_with_sizes(&entry.path(), files);
+        } else {
+            files.push((entry.path(), metadata.len()));
+        }
+    }
+}
+
+impl VoiceVox {
+    /// Reports how much memory voicevox and its loaded models use, e.g. for monitoring, see
+    /// [`MemoryReport`].
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, VoiceVox};
+    ///
+    /// # fn main() -> Result<(), voicevox_dyn::Error> {
+    /// let vv = VoiceVox::builder().load_models([ZUNDAMON_NORMAL]).build()?;
+    /// let report = vv.memory_report();
+    /// for model in &report.models {
+    ///     println!("style {}: {:?} bytes", model.style_id, model.rss_delta);
+    /// }
+    /// println!("model files: {} bytes", report.model_files_size());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn memory_report(&self) -> MemoryReport {
+        let info = self.info();
+        let (init_rss_delta, models) = {
+            let measured = MEASURED.lock().unwrap_or_else(PoisonError::into_inner);
+            let models = info
+                .loaded_models
+                .iter()
+                .map(|&style_id| ModelMemory {
+                    style_id,
+                    rss_delta: measured.models.get(&style_id).copied(),
+                })
+                .collect();
+            (measured.init, models)
+        };
+        let mut model_files = Vec::new();
+        if let Some(library_dir) = self.library_path.parent() {
+            files_with_sizes(&library_dir.join("model"), &mut model_files);
+        }
+        model_files.sort();
+        let gpu_memory = match info.acceleration {
+            Some(AccelerationMode::Gpu) => gpu_memory(),
+            _ => None,
+        };
+        MemoryReport {
+            process_rss: process_rss(),
+            init_rss_delta,
+            models,
+            model_files,
+            gpu_memory,
+        }
+    }
+}
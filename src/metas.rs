@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+use crate::StyleId;
+
+/// A speaker with its styles, as listed in the metas of the voicevox core.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpeakerMeta {
+    pub name: String,
+    pub styles: Vec<StyleMeta>,
+    pub speaker_uuid: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StyleMeta {
+    pub name: String,
+    pub id: StyleId,
+}
+
+/// Returns `(style id, "speaker (style)")` for every style, sorted by style id.
+pub(crate) fn style_names(metas: &[SpeakerMeta]) -> Vec<(StyleId, String)> {
+    let mut styles: Vec<_> = metas
+        .iter()
+        .flat_map(|speaker| {
+            speaker
+                .styles
+                .iter()
+                .map(move |style| (style.id, format!("{} ({})", speaker.name, style.name)))
+        })
+        .collect();
+    styles.sort_by_key(|(id, _)| *id);
+    styles
+}
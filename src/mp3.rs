@@ -0,0 +1,469 @@
+//! A minimal MPEG-1/2 Layer III encoder.
+//!
+//! It only implements what is needed for speech at a constant bitrate: long blocks,
+//! no psychoacoustic model and no bit reservoir. Every granule is quantized with the
+//! finest global gain that fits into its share of the frame.
+
+use std::f32::consts::PI;
+
+use crate::{bits::BitWriter, Error, Wav};
+
+mod tables;
+
+use tables::{HUFFMAN_CODES_16, HUFFMAN_CODES_24, HUFFMAN_LENGTHS_16, HUFFMAN_LENGTHS_24, WINDOW};
+
+/// Bitrates in kbit/s, indexed by the bitrate index of the frame header.
+const MPEG1_BITRATES: [u32; 15] = [
+    0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+];
+const MPEG2_BITRATES: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+/// Sampling rates indexed by the sampling frequency index of the frame header.
+const MPEG1_SAMPLE_RATES: [u32; 3] = [44100, 48000, 32000];
+const MPEG2_SAMPLE_RATES: [u32; 3] = [22050, 24000, 16000];
+
+/// Samples per channel in a granule.
+const GRANULE_LEN: usize = 576;
+/// Linbits of Huffman tables 16 to 23 and 24 to 31.
+const LINBITS_16: [u32; 8] = [1, 2, 3, 4, 6, 8, 10, 13];
+const LINBITS_24: [u32; 8] = [4, 5, 6, 7, 8, 9, 11, 13];
+/// The largest value Huffman tables can represent, 15 plus 13 linbits.
+const MAX_QUANTIZED: u32 = 15 + (1 << 13) - 1;
+/// `part2_3_length` of the side information has 12 bits.
+const MAX_GRANULE_BITS: usize = (1 << 12) - 1;
+
+impl Wav {
+    /// Encodes the wav into an mp3 file with a constant bitrate.
+    ///
+    /// `bitrate` is in bits per second. 44.1, 48 and 32 kHz support 32 to 320 kbit/s,
+    /// 22.05, 24 and 16 kHz, including the 24 kHz voicevox synthesizes by default,
+    /// support 8 to 160 kbit/s. 48 kbit/s is plenty for speech at 24 kHz.
+    /// There may be at most 2 channels.
+    ///
+    /// ```
+    /// use voicevox_dyn::Wav;
+    ///
+    /// let wav = Wav::from_samples_i16(24000, 1, &[0; 24000]);
+    /// let mp3 = wav.encode_mp3(48000)?;
+    /// // One second at 48 kbit/s, plus the frames needed to flush the encoder.
+    /// assert!(mp3.len() >= 6000 && mp3.len() < 7000);
+    /// # Ok::<(), voicevox_dyn::Error>(())
+    /// ```
+    pub fn encode_mp3(&self, bitrate: u32) -> Result<Vec<u8>, Error> {
+        let format = Format::new(self.sample_rate, self.channels, bitrate)?;
+        let channels = self.channels_f32();
+        let frame_len = format.granules * GRANULE_LEN;
+        // The filterbank delays the signal, one extra frame flushes it out.
+        let frames = channels[0].len().div_ceil(frame_len) + 1;
+
+        let mut analysis: Vec<_> = channels.iter().map(|_| Analysis::new()).collect();
+        let mut out = Vec::new();
+        let mut padding_remainder = 0;
+        for frame in 0..frames {
+            // Frames are padded by a byte when needed to keep the average bitrate exact.
+            padding_remainder += format.slot_remainder;
+            let padding = padding_remainder >= self.sample_rate;
+            if padding {
+                padding_remainder -= self.sample_rate;
+            }
+            let frame_bytes = format.frame_bytes + padding as usize;
+            let main_bits = (frame_bytes - 4 - format.side_info_len) * 8;
+            let budget = (main_bits / (format.granules * channels.len())).min(MAX_GRANULE_BITS);
+
+            let mut granules = Vec::with_capacity(format.granules * channels.len());
+            for granule in 0..format.granules {
+                for (channel, analysis) in channels.iter().zip(&mut analysis) {
+                    let start = (frame * format.granules + granule) * GRANULE_LEN;
+                    let mut samples = [0.0; GRANULE_LEN];
+                    if start < channel.len() {
+                        let end = (start + GRANULE_LEN).min(channel.len());
+                        samples[..end - start].copy_from_slice(&channel[start..end]);
+                    }
+                    granules.push(Granule::quantize(&analysis.process(&samples), budget));
+                }
+            }
+
+            let mut writer = BitWriter::with_capacity(frame_bytes);
+            format.write_header(&mut writer, padding);
+            format.write_side_info(&mut writer, &granules);
+            for granule in &granules {
+                granule.write(&mut writer);
+            }
+            out.extend(writer.finish(frame_bytes));
+        }
+        Ok(out)
+    }
+}
+
+/// Parameters shared by every frame.
+struct Format {
+    mpeg1: bool,
+    mono: bool,
+    bitrate_index: u32,
+    sample_rate_index: u32,
+    /// Granules per frame, 2 for MPEG-1 and 1 for MPEG-2.
+    granules: usize,
+    side_info_len: usize,
+    /// The size of a frame without padding.
+    frame_bytes: usize,
+    /// `frame_bytes` is rounded down, this accumulates to the padding.
+    slot_remainder: u32,
+}
+
+impl Format {
+    fn new(sample_rate: u32, channels: u16, bitrate: u32) -> Result<Self, Error> {
+        let (mpeg1, sample_rate_index) = match (
+            MPEG1_SAMPLE_RATES.iter().position(|&r| r == sample_rate),
+            MPEG2_SAMPLE_RATES.iter().position(|&r| r == sample_rate),
+        ) {
+            (Some(i), _) => (true, i),
+            (_, Some(i)) => (false, i),
+            _ => {
+                return Err(Error::InvalidInput(format!(
+                    "mp3 does not support a sampling rate of {sample_rate} Hz, \
+                     resample to 16, 22.05, 24, 32, 44.1 or 48 kHz first"
+                )))
+            }
+        };
+        let bitrates = if mpeg1 {
+            &MPEG1_BITRATES
+        } else {
+            &MPEG2_BITRATES
+        };
+        let bitrate_index = bitrates[1..]
+            .iter()
+            .position(|&b| b * 1000 == bitrate)
+            .ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "mp3 does not support a bitrate of {bitrate} bit/s at {sample_rate} Hz, \
+                     the supported bitrates in kbit/s are {:?}",
+                    &bitrates[1..]
+                ))
+            })?
+            + 1;
+        let mono = match channels {
+            1 => true,
+            2 => false,
+            channels => {
+                return Err(Error::InvalidInput(format!(
+                    "mp3 supports at most 2 channels, the wav has {channels}"
+                )))
+            }
+        };
+
+        let granules = if mpeg1 { 2 } else { 1 };
+        let slot_bytes = granules as u32 * GRANULE_LEN as u32 / 8 * bitrate;
+        Ok(Self {
+            mpeg1,
+            mono,
+            bitrate_index: bitrate_index as u32,
+            sample_rate_index: sample_rate_index as u32,
+            granules,
+            side_info_len: match (mpeg1, mono) {
+                (true, true) => 17,
+                (true, false) => 32,
+                (false, true) => 9,
+                (false, false) => 17,
+            },
+            frame_bytes: (slot_bytes / sample_rate) as usize,
+            slot_remainder: slot_bytes % sample_rate,
+        })
+    }
+
+    fn write_header(&self, writer: &mut BitWriter, padding: bool) {
+        writer.write(0x7ff, 11);
+        writer.write(if self.mpeg1 { 0b11 } else { 0b10 }, 2);
+        // Layer III, without CRC.
+        writer.write(0b01, 2);
+        writer.write(1, 1);
+        writer.write(self.bitrate_index, 4);
+        writer.write(self.sample_rate_index, 2);
+        writer.write(padding as u32, 1);
+        writer.write(0, 1);
+        // Stereo or single channel, without joint stereo.
+        writer.write(if self.mono { 0b11 } else { 0b00 }, 2);
+        writer.write(0, 2);
+        // Not copyrighted, original, no emphasis.
+        writer.write(0, 1);
+        writer.write(1, 1);
+        writer.write(0, 2);
+    }
+
+    /// `granules` are ordered by granule, then channel.
+    fn write_side_info(&self, writer: &mut BitWriter, granules: &[Granule]) {
+        // No bit reservoir, so the main data always begins in the current frame.
+        let channels = if self.mono { 1 } else { 2 };
+        if self.mpeg1 {
+            writer.write(0, 9);
+            writer.write(0, if self.mono { 5 } else { 3 });
+            // scfsi, scalefactors are not used.
+            writer.write(0, 4 * channels);
+        } else {
+            writer.write(0, 8);
+            writer.write(0, channels);
+        }
+
+        for granule in granules {
+            writer.write(granule.bits as u32, 12);
+            writer.write(granule.big_values as u32, 9);
+            writer.write(granule.global_gain, 8);
+            // scalefac_compress of 0 means no scalefactor bits.
+            writer.write(0, if self.mpeg1 { 4 } else { 9 });
+            // Long blocks only.
+            writer.write(0, 1);
+            // The same table for all three regions, which makes the region boundaries irrelevant.
+            for _ in 0..3 {
+                writer.write(granule.table.index, 5);
+            }
+            writer.write(7, 4);
+            writer.write(7, 3);
+            if self.mpeg1 {
+                // preflag
+                writer.write(0, 1);
+            }
+            // scalefac_scale and count1table_select, the count1 region is never used.
+            writer.write(0, 1);
+            writer.write(0, 1);
+        }
+    }
+}
+
+/// The polyphase filterbank and MDCT for one channel.
+struct Analysis {
+    /// The last 512 input samples, newest first.
+    fifo: [f32; 512],
+    /// The subband samples of the previous granule, which the MDCT overlaps with.
+    previous: [[f32; 18]; 32],
+    /// `cos((2i + 1)(k - 16)π / 64)`
+    matrix: Box<[[f32; 64]; 32]>,
+    /// `window[n] * cos(π / 72 * (2n + 1 + 18)(2k + 1))`
+    mdct: Box<[[f32; 36]; 18]>,
+}
+
+impl Analysis {
+    fn new() -> Self {
+        let mut matrix = Box::new([[0.0; 64]; 32]);
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (k, m) in row.iter_mut().enumerate() {
+                *m = ((2 * i + 1) as f32 * (k as f32 - 16.0) * PI / 64.0).cos();
+            }
+        }
+        let mut mdct = Box::new([[0.0; 36]; 18]);
+        for (k, row) in mdct.iter_mut().enumerate() {
+            for (n, m) in row.iter_mut().enumerate() {
+                let window = (PI / 36.0 * (n as f32 + 0.5)).sin();
+                *m = window * (PI / 72.0 * (2 * n + 1 + 18) as f32 * (2 * k + 1) as f32).cos();
+            }
+        }
+        Self {
+            fifo: [0.0; 512],
+            previous: [[0.0; 18]; 32],
+            matrix,
+            mdct,
+        }
+    }
+
+    /// Transforms a granule of samples into 576 frequency lines, ordered by subband.
+    fn process(&mut self, samples: &[f32; GRANULE_LEN]) -> [f32; GRANULE_LEN] {
+        let mut subbands = [[0.0; 18]; 32];
+        for (t, block) in samples.chunks_exact(32).enumerate() {
+            self.fifo.copy_within(..480, 32);
+            for (i, &sample) in block.iter().enumerate() {
+                self.fifo[31 - i] = sample;
+            }
+
+            let mut y = [0.0; 64];
+            for (i, y) in y.iter_mut().enumerate() {
+                *y = (0..8)
+                    .map(|j| WINDOW[i + 64 * j] / 32.0 * self.fifo[i + 64 * j])
+                    .sum();
+            }
+            for (subband, row) in subbands.iter_mut().zip(self.matrix.iter()) {
+                subband[t] = row.iter().zip(&y).map(|(m, y)| m * y).sum();
+            }
+        }
+
+        // The decoder inverts every other sample of odd subbands, which is undone here.
+        for subband in subbands.iter_mut().skip(1).step_by(2) {
+            for sample in subband.iter_mut().skip(1).step_by(2) {
+                *sample = -*sample;
+            }
+        }
+
+        let mut lines = [0.0; GRANULE_LEN];
+        for (sb, (current, previous)) in subbands.iter().zip(&mut self.previous).enumerate() {
+            let input: Vec<f32> = previous.iter().chain(current).copied().collect();
+            for (k, row) in self.mdct.iter().enumerate() {
+                lines[sb * 18 + k] = row.iter().zip(&input).map(|(m, x)| m * x).sum::<f32>() / 9.0;
+            }
+            *previous = *current;
+        }
+
+        // Undoes the alias reduction butterflies of the decoder.
+        const C: [f32; 8] = [
+            -0.6, -0.535, -0.33, -0.185, -0.095, -0.041, -0.0142, -0.0037,
+        ];
+        for sb in 1..32 {
+            for (i, c) in C.iter().enumerate() {
+                let norm = (1.0 + c * c).sqrt();
+                let (cs, ca) = (1.0 / norm, c / norm);
+                let upper = lines[sb * 18 - 1 - i];
+                let lower = lines[sb * 18 + i];
+                lines[sb * 18 - 1 - i] = upper * cs + lower * ca;
+                lines[sb * 18 + i] = lower * cs - upper * ca;
+            }
+        }
+        lines
+    }
+}
+
+/// A Huffman table used for the big values region.
+#[derive(Clone, Copy)]
+struct Table {
+    /// The table number written into the side information.
+    index: u32,
+    linbits: u32,
+    codes: &'static [u16; 256],
+    lengths: &'static [u8; 256],
+}
+
+impl Table {
+    /// The cheapest table able to represent `max`, chosen from tables 16 to 31.
+    fn select(quantized: &[i32], max: u32) -> (Table, usize) {
+        let candidates = [
+            (16, &LINBITS_16, &HUFFMAN_CODES_16, &HUFFMAN_LENGTHS_16),
+            (24, &LINBITS_24, &HUFFMAN_CODES_24, &HUFFMAN_LENGTHS_24),
+        ];
+        candidates
+            .into_iter()
+            .filter_map(|(first, linbits, codes, lengths)| {
+                let i = linbits.iter().position(|&l| 15 + (1 << l) > max)?;
+                let table = Table {
+                    index: first + i as u32,
+                    linbits: linbits[i],
+                    codes,
+                    lengths,
+                };
+                Some((table, table.count(quantized)))
+            })
+            .min_by_key(|(_, bits)| *bits)
+            .expect("values are limited to what table 31 can represent")
+    }
+
+    fn count(&self, quantized: &[i32]) -> usize {
+        quantized
+            .chunks_exact(2)
+            .map(|pair| {
+                let (x, y) = (pair[0].unsigned_abs(), pair[1].unsigned_abs());
+                let mut bits = self.lengths[(x.min(15) * 16 + y.min(15)) as usize] as usize;
+                for v in [x, y] {
+                    bits += (v != 0) as usize + if v >= 15 { self.linbits as usize } else { 0 };
+                }
+                bits
+            })
+            .sum()
+    }
+
+    fn write(&self, writer: &mut BitWriter, quantized: &[i32]) {
+        for pair in quantized.chunks_exact(2) {
+            let (x, y) = (pair[0].unsigned_abs(), pair[1].unsigned_abs());
+            let i = (x.min(15) * 16 + y.min(15)) as usize;
+            writer.write(self.codes[i] as u32, self.lengths[i] as u32);
+            for (v, signed) in [(x, pair[0]), (y, pair[1])] {
+                if v >= 15 {
+                    writer.write(v - 15, self.linbits);
+                }
+                if v != 0 {
+                    writer.write((signed < 0) as u32, 1);
+                }
+            }
+        }
+    }
+}
+
+/// A quantized granule of one channel.
+struct Granule {
+    global_gain: u32,
+    /// The quantized values of the big values region, the rest are zero.
+    quantized: Vec<i32>,
+    /// The number of pairs in `quantized`.
+    big_values: usize,
+    table: Table,
+    bits: usize,
+}
+
+impl Granule {
+    /// Quantizes the lines as finely as `budget` bits allow.
+    fn quantize(lines: &[f32; GRANULE_LEN], budget: usize) -> Self {
+        // Coarser quantization never needs more bits, so the finest gain that fits is searched for.
+        let (mut low, mut high) = (0, 255);
+        let mut best = Self::with_gain(lines, high);
+        while low < high {
+            let gain = (low + high) / 2;
+            match Self::with_gain(lines, gain) {
+                Some(granule) if granule.bits <= budget => {
+                    high = gain;
+                    best = Some(granule);
+                }
+                _ => low = gain + 1,
+            }
+        }
+        best.unwrap_or_else(Self::silence)
+    }
+
+    fn with_gain(lines: &[f32; GRANULE_LEN], global_gain: u32) -> Option<Self> {
+        let step = 2f32.powf((global_gain as f32 - 210.0) / 4.0);
+        let mut quantized: Vec<i32> = lines
+            .iter()
+            .map(|&line| {
+                let q = ((line.abs() / step).powf(0.75) + 0.4054).floor() as i32;
+                if line < 0.0 {
+                    -q
+                } else {
+                    q
+                }
+            })
+            .collect();
+        let max = quantized
+            .iter()
+            .map(|q| q.unsigned_abs())
+            .max()
+            .unwrap_or(0);
+        if max > MAX_QUANTIZED {
+            return None;
+        }
+
+        let len = quantized.iter().rposition(|&q| q != 0).map_or(0, |i| i + 1);
+        quantized.truncate(len.next_multiple_of(2));
+        if quantized.is_empty() {
+            return Some(Self::silence());
+        }
+        let (table, bits) = Table::select(&quantized, max);
+        Some(Self {
+            global_gain,
+            big_values: quantized.len() / 2,
+            quantized,
+            table,
+            bits,
+        })
+    }
+
+    fn silence() -> Self {
+        Self {
+            global_gain: 0,
+            quantized: Vec::new(),
+            big_values: 0,
+            table: Table {
+                index: 16,
+                linbits: LINBITS_16[0],
+                codes: &HUFFMAN_CODES_16,
+                lengths: &HUFFMAN_LENGTHS_16,
+            },
+            bits: 0,
+        }
+    }
+
+    fn write(&self, writer: &mut BitWriter) {
+        self.table.write(writer, &self.quantized);
+    }
+}
@@ -0,0 +1,286 @@
+//! Tables of ISO/IEC 11172-3.
+
+/// The synthesis window `D[i]` of Table B.3. The analysis window `C[i]` of Table C.1 is
+/// the same window divided by 32.
+#[rustfmt::skip]
+#[allow(clippy::excessive_precision, clippy::unreadable_literal)]
+pub(super) const WINDOW: [f32; 512] = [
+     0.000000000, -0.000015259, -0.000015259, -0.000015259,
+    -0.000015259, -0.000015259, -0.000015259, -0.000030518,
+    -0.000030518, -0.000030518, -0.000030518, -0.000045776,
+    -0.000045776, -0.000061035, -0.000061035, -0.000076294,
+    -0.000076294, -0.000091553, -0.000106812, -0.000106812,
+    -0.000122070, -0.000137329, -0.000152588, -0.000167847,
+    -0.000198364, -0.000213623, -0.000244141, -0.000259399,
+    -0.000289917, -0.000320435, -0.000366211, -0.000396729,
+    -0.000442505, -0.000473022, -0.000534058, -0.000579834,
+    -0.000625610, -0.000686646, -0.000747681, -0.000808716,
+    -0.000885010, -0.000961304, -0.001037598, -0.001113892,
+    -0.001205444, -0.001296997, -0.001388550, -0.001480103,
+    -0.001586914, -0.001693726, -0.001785278, -0.001907349,
+    -0.002014160, -0.002120972, -0.002243042, -0.002349854,
+    -0.002456665, -0.002578735, -0.002685547, -0.002792358,
+    -0.002899170, -0.002990723, -0.003082275, -0.003173828,
+     0.003250122,  0.003326416,  0.003387451,  0.003433228,
+     0.003463745,  0.003479004,  0.003479004,  0.003463745,
+     0.003417969,  0.003372192,  0.003280640,  0.003173828,
+     0.003051758,  0.002883911,  0.002700806,  0.002487183,
+     0.002227783,  0.001937866,  0.001617432,  0.001266479,
+     0.000869751,  0.000442505, -0.000030518, -0.000549316,
+    -0.001098633, -0.001693726, -0.002334595, -0.003005981,
+    -0.003723145, -0.004486084, -0.005294800, -0.006118774,
+    -0.007003784, -0.007919312, -0.008865356, -0.009841919,
+    -0.010848999, -0.011886597, -0.012939453, -0.014022827,
+    -0.015121460, -0.016235352, -0.017349243, -0.018463135,
+    -0.019577026, -0.020690918, -0.021789551, -0.022857666,
+    -0.023910522, -0.024932861, -0.025909424, -0.026840210,
+    -0.027725220, -0.028533936, -0.029281616, -0.029937744,
+    -0.030532837, -0.031005859, -0.031387329, -0.031661987,
+    -0.031814575, -0.031845093, -0.031738281, -0.031478882,
+     0.031082153,  0.030517578,  0.029785156,  0.028884888,
+     0.027801514,  0.026535034,  0.025085449,  0.023422241,
+     0.021575928,  0.019531250,  0.017257690,  0.014801025,
+     0.012115479,  0.009231567,  0.006134033,  0.002822876,
+    -0.000686646, -0.004394531, -0.008316040, -0.012420654,
+    -0.016708374, -0.021179199, -0.025817871, -0.030609131,
+    -0.035552979, -0.040634155, -0.045837402, -0.051132202,
+    -0.056533813, -0.061996460, -0.067520142, -0.073059082,
+    -0.078628540, -0.084182739, -0.089706421, -0.095169067,
+    -0.100540161, -0.105819702, -0.110946655, -0.115921021,
+    -0.120697021, -0.125259399, -0.129562378, -0.133590698,
+    -0.137298584, -0.140670776, -0.143676758, -0.146255493,
+    -0.148422241, -0.150115967, -0.151306152, -0.151962280,
+    -0.152069092, -0.151596069, -0.150497437, -0.148773193,
+    -0.146362305, -0.143264771, -0.139450073, -0.134887695,
+    -0.129577637, -0.123474121, -0.116577148, -0.108856201,
+     0.100311279,  0.090927124,  0.080688477,  0.069595337,
+     0.057617187,  0.044784546,  0.031082153,  0.016510010,
+     0.001068115, -0.015228271, -0.032379150, -0.050354004,
+    -0.069168091, -0.088775635, -0.109161377, -0.130310059,
+    -0.152206421, -0.174789429, -0.198059082, -0.221984863,
+    -0.246505737, -0.271591187, -0.297210693, -0.323318481,
+    -0.349868774, -0.376800537, -0.404083252, -0.431655884,
+    -0.459472656, -0.487472534, -0.515609741, -0.543823242,
+    -0.572036743, -0.600219727, -0.628295898, -0.656219482,
+    -0.683914185, -0.711318970, -0.738372803, -0.765029907,
+    -0.791213989, -0.816864014, -0.841949463, -0.866363525,
+    -0.890090942, -0.913055420, -0.935195923, -0.956481934,
+    -0.976852417, -0.996246338, -1.014617920, -1.031936646,
+    -1.048156738, -1.063217163, -1.077117920, -1.089782715,
+    -1.101211548, -1.111373901, -1.120223999, -1.127746582,
+    -1.133926392, -1.138763428, -1.142211914, -1.144287109,
+     1.144989014,  1.144287109,  1.142211914,  1.138763428,
+     1.133926392,  1.127746582,  1.120223999,  1.111373901,
+     1.101211548,  1.089782715,  1.077117920,  1.063217163,
+     1.048156738,  1.031936646,  1.014617920,  0.996246338,
+     0.976852417,  0.956481934,  0.935195923,  0.913055420,
+     0.890090942,  0.866363525,  0.841949463,  0.816864014,
+     0.791213989,  0.765029907,  0.738372803,  0.711318970,
+     0.683914185,  0.656219482,  0.628295898,  0.600219727,
+     0.572036743,  0.543823242,  0.515609741,  0.487472534,
+     0.459472656,  0.431655884,  0.404083252,  0.376800537,
+     0.349868774,  0.323318481,  0.297210693,  0.271591187,
+     0.246505737,  0.221984863,  0.198059082,  0.174789429,
+     0.152206421,  0.130310059,  0.109161377,  0.088775635,
+     0.069168091,  0.050354004,  0.032379150,  0.015228271,
+    -0.001068115, -0.016510010, -0.031082153, -0.044784546,
+    -0.057617187, -0.069595337, -0.080688477, -0.090927124,
+     0.100311279,  0.108856201,  0.116577148,  0.123474121,
+     0.129577637,  0.134887695,  0.139450073,  0.143264771,
+     0.146362305,  0.148773193,  0.150497437,  0.151596069,
+     0.152069092,  0.151962280,  0.151306152,  0.150115967,
+     0.148422241,  0.146255493,  0.143676758,  0.140670776,
+     0.137298584,  0.133590698,  0.129562378,  0.125259399,
+     0.120697021,  0.115921021,  0.110946655,  0.105819702,
+     0.100540161,  0.095169067,  0.089706421,  0.084182739,
+     0.078628540,  0.073059082,  0.067520142,  0.061996460,
+     0.056533813,  0.051132202,  0.045837402,  0.040634155,
+     0.035552979,  0.030609131,  0.025817871,  0.021179199,
+     0.016708374,  0.012420654,  0.008316040,  0.004394531,
+     0.000686646, -0.002822876, -0.006134033, -0.009231567,
+    -0.012115479, -0.014801025, -0.017257690, -0.019531250,
+    -0.021575928, -0.023422241, -0.025085449, -0.026535034,
+    -0.027801514, -0.028884888, -0.029785156, -0.030517578,
+     0.031082153,  0.031478882,  0.031738281,  0.031845093,
+     0.031814575,  0.031661987,  0.031387329,  0.031005859,
+     0.030532837,  0.029937744,  0.029281616,  0.028533936,
+     0.027725220,  0.026840210,  0.025909424,  0.024932861,
+     0.023910522,  0.022857666,  0.021789551,  0.020690918,
+     0.019577026,  0.018463135,  0.017349243,  0.016235352,
+     0.015121460,  0.014022827,  0.012939453,  0.011886597,
+     0.010848999,  0.009841919,  0.008865356,  0.007919312,
+     0.007003784,  0.006118774,  0.005294800,  0.004486084,
+     0.003723145,  0.003005981,  0.002334595,  0.001693726,
+     0.001098633,  0.000549316,  0.000030518, -0.000442505,
+    -0.000869751, -0.001266479, -0.001617432, -0.001937866,
+    -0.002227783, -0.002487183, -0.002700806, -0.002883911,
+    -0.003051758, -0.003173828, -0.003280640, -0.003372192,
+    -0.003417969, -0.003463745, -0.003479004, -0.003479004,
+    -0.003463745, -0.003433228, -0.003387451, -0.003326416,
+     0.003250122,  0.003173828,  0.003082275,  0.002990723,
+     0.002899170,  0.002792358,  0.002685547,  0.002578735,
+     0.002456665,  0.002349854,  0.002243042,  0.002120972,
+     0.002014160,  0.001907349,  0.001785278,  0.001693726,
+     0.001586914,  0.001480103,  0.001388550,  0.001296997,
+     0.001205444,  0.001113892,  0.001037598,  0.000961304,
+     0.000885010,  0.000808716,  0.000747681,  0.000686646,
+     0.000625610,  0.000579834,  0.000534058,  0.000473022,
+     0.000442505,  0.000396729,  0.000366211,  0.000320435,
+     0.000289917,  0.000259399,  0.000244141,  0.000213623,
+     0.000198364,  0.000167847,  0.000152588,  0.000137329,
+     0.000122070,  0.000106812,  0.000106812,  0.000091553,
+     0.000076294,  0.000076294,  0.000061035,  0.000061035,
+     0.000045776,  0.000045776,  0.000030518,  0.000030518,
+     0.000030518,  0.000030518,  0.000015259,  0.000015259,
+     0.000015259,  0.000015259,  0.000015259,  0.000015259,
+];
+
+/// Codes of Huffman table 16 of Table B.7, indexed by `x * 16 + y`.
+/// Tables 16 to 23 share these codes and only differ in their number of linbits.
+#[rustfmt::skip]
+pub(super) const HUFFMAN_CODES_16: [u16; 256] = [
+    0x0001, 0x0005, 0x000e, 0x002c, 0x004a, 0x003f, 0x006e, 0x005d,
+    0x00ac, 0x0095, 0x008a, 0x00f2, 0x00e1, 0x00c3, 0x0178, 0x0011,
+    0x0003, 0x0004, 0x000c, 0x0014, 0x0023, 0x003e, 0x0035, 0x002f,
+    0x0053, 0x004b, 0x0044, 0x0077, 0x00c9, 0x006b, 0x00cf, 0x0009,
+    0x000f, 0x000d, 0x0017, 0x0026, 0x0043, 0x003a, 0x0067, 0x005a,
+    0x00a1, 0x0048, 0x007f, 0x0075, 0x006e, 0x00d1, 0x00ce, 0x0010,
+    0x002d, 0x0015, 0x0027, 0x0045, 0x0040, 0x0072, 0x0063, 0x0057,
+    0x009e, 0x008c, 0x00fc, 0x00d4, 0x00c7, 0x0183, 0x016d, 0x001a,
+    0x004b, 0x0024, 0x0044, 0x0041, 0x0073, 0x0065, 0x00b3, 0x00a4,
+    0x009b, 0x0108, 0x00f6, 0x00e2, 0x018b, 0x017e, 0x016a, 0x0009,
+    0x0042, 0x001e, 0x003b, 0x0038, 0x0066, 0x00b9, 0x00ad, 0x0109,
+    0x008e, 0x00fd, 0x00e8, 0x0190, 0x0184, 0x017a, 0x01bd, 0x0010,
+    0x006f, 0x0036, 0x0034, 0x0064, 0x00b8, 0x00b2, 0x00a0, 0x0085,
+    0x0101, 0x00f4, 0x00e4, 0x00d9, 0x0181, 0x016e, 0x02cb, 0x000a,
+    0x0062, 0x0030, 0x005b, 0x0058, 0x00a5, 0x009d, 0x0094, 0x0105,
+    0x00f8, 0x0197, 0x018d, 0x0174, 0x017c, 0x0379, 0x0374, 0x0008,
+    0x0055, 0x0054, 0x0051, 0x009f, 0x009c, 0x008f, 0x0104, 0x00f9,
+    0x01ab, 0x0191, 0x0188, 0x017f, 0x02d7, 0x02c9, 0x02c4, 0x0007,
+    0x009a, 0x004c, 0x0049, 0x008d, 0x0083, 0x0100, 0x00f5, 0x01aa,
+    0x0196, 0x018a, 0x0180, 0x02df, 0x0167, 0x02c6, 0x0160, 0x000b,
+    0x008b, 0x0081, 0x0043, 0x007d, 0x00f7, 0x00e9, 0x00e5, 0x00db,
+    0x0189, 0x02e7, 0x02e1, 0x02d0, 0x0375, 0x0372, 0x01b7, 0x0004,
+    0x00f3, 0x0078, 0x0076, 0x0073, 0x00e3, 0x00df, 0x018c, 0x02ea,
+    0x02e6, 0x02e0, 0x02d1, 0x02c8, 0x02c2, 0x00df, 0x01b4, 0x0006,
+    0x00ca, 0x00e0, 0x00de, 0x00da, 0x00d8, 0x0185, 0x0182, 0x017d,
+    0x016c, 0x0378, 0x01bb, 0x02c3, 0x01b8, 0x01b5, 0x06c0, 0x0004,
+    0x02eb, 0x00d3, 0x00d2, 0x00d0, 0x0172, 0x017b, 0x02de, 0x02d3,
+    0x02ca, 0x06c7, 0x0373, 0x036d, 0x036c, 0x0d83, 0x0361, 0x0002,
+    0x0179, 0x0171, 0x0066, 0x00bb, 0x02d6, 0x02d2, 0x0166, 0x02c7,
+    0x02c5, 0x0362, 0x06c6, 0x0367, 0x0d82, 0x0366, 0x01b2, 0x0000,
+    0x000c, 0x000a, 0x0007, 0x000b, 0x000a, 0x0011, 0x000b, 0x0009,
+    0x000d, 0x000c, 0x000a, 0x0007, 0x0005, 0x0003, 0x0001, 0x0003,
+];
+
+/// Code lengths of Huffman table 16.
+#[rustfmt::skip]
+pub(super) const HUFFMAN_LENGTHS_16: [u8; 256] = [
+     1,  4,  6,  8,  9,  9, 10, 10,
+    11, 11, 11, 12, 12, 12, 13,  9,
+     3,  4,  6,  7,  8,  9,  9,  9,
+    10, 10, 10, 11, 12, 11, 12,  8,
+     6,  6,  7,  8,  9,  9, 10, 10,
+    11, 10, 11, 11, 11, 12, 12,  9,
+     8,  7,  8,  9,  9, 10, 10, 10,
+    11, 11, 12, 12, 12, 13, 13, 10,
+     9,  8,  9,  9, 10, 10, 11, 11,
+    11, 12, 12, 12, 13, 13, 13,  9,
+     9,  8,  9,  9, 10, 11, 11, 12,
+    11, 12, 12, 13, 13, 13, 14, 10,
+    10,  9,  9, 10, 11, 11, 11, 11,
+    12, 12, 12, 12, 13, 13, 14, 10,
+    10,  9, 10, 10, 11, 11, 11, 12,
+    12, 13, 13, 13, 13, 15, 15, 10,
+    10, 10, 10, 11, 11, 11, 12, 12,
+    13, 13, 13, 13, 14, 14, 14, 10,
+    11, 10, 10, 11, 11, 12, 12, 13,
+    13, 13, 13, 14, 13, 14, 13, 11,
+    11, 11, 10, 11, 12, 12, 12, 12,
+    13, 14, 14, 14, 15, 15, 14, 10,
+    12, 11, 11, 11, 12, 12, 13, 14,
+    14, 14, 14, 14, 14, 13, 14, 11,
+    12, 12, 12, 12, 12, 13, 13, 13,
+    13, 15, 14, 14, 14, 14, 16, 11,
+    14, 12, 12, 12, 13, 13, 14, 14,
+    14, 16, 15, 15, 15, 17, 15, 11,
+    13, 13, 11, 12, 14, 14, 13, 14,
+    14, 15, 16, 15, 17, 15, 14, 11,
+     9,  8,  8,  9,  9, 10, 10, 10,
+    11, 11, 11, 11, 11, 11, 11,  8,
+];
+
+/// Codes of Huffman table 24 of Table B.7, indexed by `x * 16 + y`.
+/// Tables 24 to 31 share these codes and only differ in their number of linbits.
+#[rustfmt::skip]
+pub(super) const HUFFMAN_CODES_24: [u16; 256] = [
+    0x000f, 0x000d, 0x002e, 0x0050, 0x0092, 0x0106, 0x00f8, 0x01b2,
+    0x01aa, 0x029d, 0x028d, 0x0289, 0x026d, 0x0205, 0x0408, 0x0058,
+    0x000e, 0x000c, 0x0015, 0x0026, 0x0047, 0x0082, 0x007a, 0x00d8,
+    0x00d1, 0x00c6, 0x0147, 0x0159, 0x013f, 0x0129, 0x0117, 0x002a,
+    0x002f, 0x0016, 0x0029, 0x004a, 0x0044, 0x0080, 0x0078, 0x00dd,
+    0x00cf, 0x00c2, 0x00b6, 0x0154, 0x013b, 0x0127, 0x021d, 0x0012,
+    0x0051, 0x0027, 0x004b, 0x0046, 0x0086, 0x007d, 0x0074, 0x00dc,
+    0x00cc, 0x00be, 0x00b2, 0x0145, 0x0137, 0x0125, 0x010f, 0x0010,
+    0x0093, 0x0048, 0x0045, 0x0087, 0x007f, 0x0076, 0x0070, 0x00d2,
+    0x00c8, 0x00bc, 0x0160, 0x0143, 0x0132, 0x011d, 0x021c, 0x000e,
+    0x0107, 0x0042, 0x0081, 0x007e, 0x0077, 0x0072, 0x00d6, 0x00ca,
+    0x00c0, 0x00b4, 0x0155, 0x013d, 0x012d, 0x0119, 0x0106, 0x000c,
+    0x00f9, 0x007b, 0x0079, 0x0075, 0x0071, 0x00d7, 0x00ce, 0x00c3,
+    0x00b9, 0x015b, 0x014a, 0x0134, 0x0123, 0x0110, 0x0208, 0x000a,
+    0x01b3, 0x0073, 0x006f, 0x006d, 0x00d3, 0x00cb, 0x00c4, 0x00bb,
+    0x0161, 0x014c, 0x0139, 0x012a, 0x011b, 0x0213, 0x017d, 0x0011,
+    0x01ab, 0x00d4, 0x00d0, 0x00cd, 0x00c9, 0x00c1, 0x00ba, 0x00b1,
+    0x00a9, 0x0140, 0x012f, 0x011e, 0x010c, 0x0202, 0x0179, 0x0010,
+    0x014f, 0x00c7, 0x00c5, 0x00bf, 0x00bd, 0x00b5, 0x00ae, 0x014d,
+    0x0141, 0x0131, 0x0121, 0x0113, 0x0209, 0x017b, 0x0173, 0x000b,
+    0x029c, 0x00b8, 0x00b7, 0x00b3, 0x00af, 0x0158, 0x014b, 0x013a,
+    0x0130, 0x0122, 0x0115, 0x0212, 0x017f, 0x0175, 0x016e, 0x000a,
+    0x028c, 0x015a, 0x00ab, 0x00a8, 0x00a4, 0x013e, 0x0135, 0x012b,
+    0x011f, 0x0114, 0x0107, 0x0201, 0x0177, 0x0170, 0x016a, 0x0006,
+    0x0288, 0x0142, 0x013c, 0x0138, 0x0133, 0x012e, 0x0124, 0x011c,
+    0x010d, 0x0105, 0x0200, 0x0178, 0x0172, 0x016c, 0x0167, 0x0004,
+    0x026c, 0x012c, 0x0128, 0x0126, 0x0120, 0x011a, 0x0111, 0x010a,
+    0x0203, 0x017c, 0x0176, 0x0171, 0x016d, 0x0169, 0x0165, 0x0002,
+    0x0409, 0x0118, 0x0116, 0x0112, 0x010b, 0x0108, 0x0103, 0x017e,
+    0x017a, 0x0174, 0x016f, 0x016b, 0x0168, 0x0166, 0x0164, 0x0000,
+    0x002b, 0x0014, 0x0013, 0x0011, 0x000f, 0x000d, 0x000b, 0x0009,
+    0x0007, 0x0006, 0x0004, 0x0007, 0x0005, 0x0003, 0x0001, 0x0003,
+];
+
+/// Code lengths of Huffman table 24.
+#[rustfmt::skip]
+pub(super) const HUFFMAN_LENGTHS_24: [u8; 256] = [
+     4,  4,  6,  7,  8,  9,  9, 10,
+    10, 11, 11, 11, 11, 11, 12,  9,
+     4,  4,  5,  6,  7,  8,  8,  9,
+     9,  9, 10, 10, 10, 10, 10,  8,
+     6,  5,  6,  7,  7,  8,  8,  9,
+     9,  9,  9, 10, 10, 10, 11,  7,
+     7,  6,  7,  7,  8,  8,  8,  9,
+     9,  9,  9, 10, 10, 10, 10,  7,
+     8,  7,  7,  8,  8,  8,  8,  9,
+     9,  9, 10, 10, 10, 10, 11,  7,
+     9,  7,  8,  8,  8,  8,  9,  9,
+     9,  9, 10, 10, 10, 10, 10,  7,
+     9,  8,  8,  8,  8,  9,  9,  9,
+     9, 10, 10, 10, 10, 10, 11,  7,
+    10,  8,  8,  8,  9,  9,  9,  9,
+    10, 10, 10, 10, 10, 11, 11,  8,
+    10,  9,  9,  9,  9,  9,  9,  9,
+     9, 10, 10, 10, 10, 11, 11,  8,
+    10,  9,  9,  9,  9,  9,  9, 10,
+    10, 10, 10, 10, 11, 11, 11,  8,
+    11,  9,  9,  9,  9, 10, 10, 10,
+    10, 10, 10, 11, 11, 11, 11,  8,
+    11, 10,  9,  9,  9, 10, 10, 10,
+    10, 10, 10, 11, 11, 11, 11,  8,
+    11, 10, 10, 10, 10, 10, 10, 10,
+    10, 10, 11, 11, 11, 11, 11,  8,
+    11, 10, 10, 10, 10, 10, 10, 10,
+    11, 11, 11, 11, 11, 11, 11,  8,
+    12, 10, 10, 10, 10, 10, 10, 11,
+    11, 11, 11, 11, 11, 11, 11,  8,
+     8,  7,  7,  7,  7,  7,  7,  7,
+     7,  7,  7,  8,  8,  8,  8,  4,
+];
@@ -0,0 +1,395 @@
+//! The async API, with the `async` feature or one of `tokio`, `async-std` and `smol`.
+//!
+//! Synthesizing takes hundreds of milliseconds, which would stall an async runtime if done on
+//! one of its worker threads. [`VoiceVox`] runs every call with an [`Executor`] instead and
+//! returns a future that completes with its result. By default that is the blocking thread
+//! pool of the enabled runtime, or a thread per call with only the `async` feature. The
+//! blocking API is in [`blocking`](crate::blocking). To make every call on one dedicated
+//! thread instead, use [`AsyncVoiceVox`](crate::AsyncVoiceVox).
+//!
+//! ```no_run
+//! use voicevox_dyn::{nonblocking::VoiceVox, styles::ZUNDAMON_NORMAL};
+//!
+//! async fn greet() -> Result<Vec<u8>, voicevox_dyn::Error> {
+//!     let vv = VoiceVox::load(voicevox_dyn::VoiceVox::builder().load_models([ZUNDAMON_NORMAL])).await?;
+//!     let wav = vv.tts("こんにちは", ZUNDAMON_NORMAL, Default::default()).await?;
+//!     Ok(wav.into_vec())
+//! }
+//! ```
+
+mod executor;
+
+use std::{
+    future::{poll_fn, Future},
+    pin::{pin, Pin},
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+
+#[cfg(feature = "async-std")]
+pub use executor::AsyncStdExecutor;
+#[cfg(feature = "smol")]
+pub use executor::SmolExecutor;
+#[cfg(feature = "tokio")]
+pub use executor::TokioExecutor;
+pub use executor::{Executor, ThreadExecutor};
+
+use crate::{
+    text, AudioChunk, AudioQuery, CaptionSegment, Error, LoadStage, StyleId, SynthesisOptions,
+    TtsOptions, VoiceVoxBuilder, VoiceVoxHandle, VoiceVoxInfo, Wav, WavBytes,
+};
+
+/// A cloneable, async handle to a [`crate::VoiceVox`].
+///
+/// The calls share the underlying [`VoiceVoxHandle`], including its
+/// [limit of concurrent syntheses](VoiceVoxHandle::set_max_concurrent_syntheses).
+#[derive(Clone)]
+pub struct VoiceVox {
+    handle: VoiceVoxHandle,
+    executor: Arc<dyn Executor>,
+}
+
+impl From<crate::VoiceVox> for VoiceVox {
+    fn from(vv: crate::VoiceVox) -> Self {
+        vv.into_handle().into()
+    }
+}
+
+impl From<VoiceVoxHandle> for VoiceVox {
+    fn from(handle: VoiceVoxHandle) -> Self {
+        Self {
+            handle,
+            executor: executor::default_executor(),
+        }
+    }
+}
+
+impl std::fmt::Debug for VoiceVox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VoiceVox")
+            .field("handle", &self.handle)
+            .finish_non_exhaustive()
+    }
+}
+
+impl VoiceVox {
+    /// Same as [`VoiceVoxBuilder::build`] but downloads, loads and initializes voicevox with
+    /// the default [`Executor`].
+    pub async fn load(builder: VoiceVoxBuilder) -> Result<Self, Error> {
+        Self::load_with_progress(builder, |_| {}).await
+    }
+
+    /// Same as [`VoiceVoxBuilder::build_with_progress`] but downloads, loads and initializes
+    /// voicevox with the default [`Executor`], so the runtime stays responsive meanwhile,
+    /// e.g. to animate a loading screen.
+    ///
+    /// `progress` is called from the thread doing the work.
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::{nonblocking::VoiceVox, styles::ZUNDAMON_NORMAL};
+    ///
+    /// async fn start() -> Result<VoiceVox, voicevox_dyn::Error> {
+    ///     let (sender, receiver) = std::sync::mpsc::channel();
+    ///     let builder = voicevox_dyn::VoiceVox::builder().load_models([ZUNDAMON_NORMAL]);
+    ///     let vv = VoiceVox::load_with_progress(builder, move |stage| {
+    ///         let _ = sender.send(stage);
+    ///     });
+    ///     // Show the latest stage from `receiver.try_iter()` on every frame of the loading
+    ///     // screen while awaiting `vv`.
+    ///     vv.await
+    /// }
+    /// ```
+    pub async fn load_with_progress(
+        builder: VoiceVoxBuilder,
+        progress: impl FnMut(LoadStage) + Send + 'static,
+    ) -> Result<Self, Error> {
+        let executor = executor::default_executor();
+        let vv = spawn_blocking(&*executor, move || builder.build_with_progress(progress)).await?;
+        Ok(vv.into())
+    }
+
+    /// Makes the blocking calls with `executor` instead of the default, e.g. for using the
+    /// blocking thread pool of another runtime.
+    pub fn with_executor(mut self, executor: impl Executor) -> Self {
+        self.executor = Arc::new(executor);
+        self
+    }
+
+    /// The blocking handle the calls are made with.
+    pub fn blocking(&self) -> &VoiceVoxHandle {
+        &self.handle
+    }
+
+    /// See [`crate::VoiceVox::load_model`].
+    pub async fn load_model(&self, style_id: StyleId) -> Result<(), Error> {
+        self.run(move |handle| handle.load_model(style_id)).await
+    }
+
+    /// See [`crate::VoiceVox::load_models`].
+    ///
+    /// The models that failed to load are reported with [`Error::ModelLoad`].
+    pub async fn load_models(&self, style_ids: &[StyleId]) -> Result<(), Error> {
+        let style_ids = style_ids.to_vec();
+        self.run(move |handle| handle.load_models(&style_ids).map_err(Error::ModelLoad))
+            .await
+    }
+
+    /// See [`crate::VoiceVox::is_model_loaded`]. Does not block.
+    pub fn is_model_loaded(&self, style_id: StyleId) -> bool {
+        self.handle.is_model_loaded(style_id)
+    }
+
+    /// See [`crate::VoiceVox::info`]. Does not block.
+    pub fn info(&self) -> VoiceVoxInfo {
+        self.handle.info()
+    }
+
+    /// See [`crate::VoiceVox::tts`].
+    pub async fn tts(
+        &self,
+        text: impl Into<String>,
+        style_id: StyleId,
+        opts: TtsOptions,
+    ) -> Result<WavBytes, Error> {
+        let text = text.into();
+        self.run(move |handle| handle.tts(text, style_id, opts))
+            .await
+    }
+
+    /// See [`crate::VoiceVox::tts_wav`].
+    pub async fn tts_wav(
+        &self,
+        text: impl Into<String>,
+        style_id: StyleId,
+        opts: TtsOptions,
+    ) -> Result<Wav, Error> {
+        let text = text.into();
+        self.run(move |handle| handle.tts_wav(text, style_id, opts))
+            .await
+    }
+
+    /// See [`crate::VoiceVox::tts_with_options`].
+    ///
+    /// [`SynthesisOptions::timeout`] is enforced with [`Executor::sleep`] instead of a
+    /// separate thread.
+    pub async fn tts_with_options(
+        &self,
+        text: impl Into<String>,
+        style_id: StyleId,
+        opts: &SynthesisOptions,
+    ) -> Result<Wav, Error> {
+        let text = text.into();
+        let Some((timeout, opts)) = opts.watchdog() else {
+            let opts = opts.clone();
+            return self
+                .run(move |handle| handle.tts_with_options(text, style_id, &opts))
+                .await;
+        };
+
+        // Whoever claims the synthesis first, the synthesis by finishing or the timeout,
+        // decides whether it counts as timed out.
+        let claimed = Arc::new(Mutex::new(false));
+        let mut synthesis = pin!(self.run({
+            let claimed = claimed.clone();
+            move |handle| {
+                let result = handle.tts_with_options(text, style_id, &opts);
+                let mut claimed = claimed.lock().unwrap_or_else(PoisonError::into_inner);
+                if *claimed {
+                    crate::timed_out_finished();
+                }
+                *claimed = true;
+                result
+            }
+        }));
+        let mut sleep = self.executor.sleep(timeout);
+        let timed_out = poll_fn(|cx| {
+            if let Poll::Ready(result) = synthesis.as_mut().poll(cx) {
+                return Poll::Ready(Some(result));
+            }
+            sleep.as_mut().poll(cx).map(|()| None)
+        })
+        .await;
+        match timed_out {
+            Some(result) => result,
+            None => {
+                let finished = {
+                    let mut claimed = claimed.lock().unwrap_or_else(PoisonError::into_inner);
+                    let finished = std::mem::replace(&mut *claimed, true);
+                    if !finished {
+                        crate::timed_out(timeout);
+                    }
+                    finished
+                };
+                if finished {
+                    // The synthesis finished right before the timeout.
+                    synthesis.await
+                } else {
+                    Err(Error::Timeout(timeout))
+                }
+            }
+        }
+    }
+
+    /// See [`crate::VoiceVox::tts_ssml`].
+    pub async fn tts_ssml(
+        &self,
+        ssml: impl Into<String>,
+        style_id: StyleId,
+        opts: TtsOptions,
+    ) -> Result<Wav, Error> {
+        let ssml = ssml.into();
+        self.run(move |handle| handle.tts_ssml(ssml, style_id, opts))
+            .await
+    }
+
+    /// See [`crate::VoiceVox::tts_with_captions`].
+    pub async fn tts_with_captions(
+        &self,
+        text: impl Into<String>,
+        style_id: StyleId,
+        opts: TtsOptions,
+    ) -> Result<(Wav, Vec<CaptionSegment>), Error> {
+        let text = text.into();
+        self.run(move |handle| handle.tts_with_captions(text, style_id, opts))
+            .await
+    }
+
+    /// Same as [`crate::VoiceVox::tts_stream`] but returns a [`Stream`], e.g. for sending every
+    /// sentence to the client of a server as soon as it is synthesized.
+    ///
+    /// The sentences are synthesized with the [`Executor`], at most one ahead of the stream.
+    /// Dropping the stream stops the synthesis after the current sentence.
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::{nonblocking::VoiceVox, styles::ZUNDAMON_NORMAL};
+    ///
+    /// async fn narrate(vv: &VoiceVox) -> Result<(), voicevox_dyn::Error> {
+    ///     let opts = Default::default();
+    ///     let mut stream = vv.tts_stream("おはよう。いい天気ですね。", ZUNDAMON_NORMAL, &opts);
+    ///     while let Some(chunk) = stream.next().await {
+    ///         let chunk = chunk?;
+    ///         println!("{}: {:?}", chunk.text, chunk.wav.duration());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn tts_stream(
+        &self,
+        text: impl Into<String>,
+        style_id: StyleId,
+        opts: &SynthesisOptions,
+    ) -> AudioChunkStream {
+        let (sender, receiver) = async_channel::bounded(1);
+        let handle = self.handle.clone();
+        let text = text.into();
+        let opts = opts.clone();
+        let errors = sender.clone();
+        let spawned = self.executor.spawn_blocking(Box::new(move || {
+            for (index, sentence) in text::split_sentences(&text).into_iter().enumerate() {
+                let result = handle
+                    .synthesize(|vv| vv.tts_with_options(sentence, style_id, &opts))
+                    .map(|wav| AudioChunk {
+                        index,
+                        text: sentence.to_owned(),
+                        wav,
+                    });
+                let cancelled = matches!(result, Err(Error::Cancelled { .. }));
+                if sender.send_blocking(result).is_err() || cancelled {
+                    break;
+                }
+            }
+        }));
+        if let Err(e) = spawned {
+            let _ = errors.try_send(Err(Error::io("failed to run a blocking voicevox call")(e)));
+        }
+        AudioChunkStream {
+            receiver: Box::pin(receiver),
+        }
+    }
+
+    /// See [`crate::VoiceVox::audio_query`].
+    pub async fn audio_query(
+        &self,
+        text: impl Into<String>,
+        style_id: StyleId,
+        opts: TtsOptions,
+    ) -> Result<AudioQuery, Error> {
+        let text = text.into();
+        self.run(move |handle| handle.audio_query(text, style_id, opts))
+            .await
+    }
+
+    /// See [`crate::VoiceVox::synthesis`].
+    pub async fn synthesis(
+        &self,
+        query: &AudioQuery,
+        style_id: StyleId,
+        opts: TtsOptions,
+    ) -> Result<WavBytes, Error> {
+        let query = query.clone();
+        self.run(move |handle| handle.synthesis(&query, style_id, opts))
+            .await
+    }
+
+    /// Runs `f` with the handle with the [`Executor`].
+    fn run<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&VoiceVoxHandle) -> Result<T, Error> + Send + 'static,
+    ) -> impl Future<Output = Result<T, Error>> {
+        let handle = self.handle.clone();
+        spawn_blocking(&*self.executor, move || f(&handle))
+    }
+}
+
+/// The sentences synthesized by [`VoiceVox::tts_stream`].
+#[derive(Debug)]
+pub struct AudioChunkStream {
+    receiver: Pin<Box<async_channel::Receiver<Result<AudioChunk, Error>>>>,
+}
+
+impl AudioChunkStream {
+    /// Waits for the next sentence, returning `None` once all of them were synthesized.
+    pub async fn next(&mut self) -> Option<Result<AudioChunk, Error>> {
+        self.receiver.recv().await.ok()
+    }
+}
+
+impl Stream for AudioChunkStream {
+    type Item = Result<AudioChunk, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.as_mut().poll_next(cx)
+    }
+}
+
+impl VoiceVoxHandle {
+    /// An async handle making its calls with this handle.
+    pub fn nonblocking(&self) -> VoiceVox {
+        self.clone().into()
+    }
+}
+
+/// Runs `f` with `executor` and returns a future that completes with its result.
+fn spawn_blocking<T: Send + 'static>(
+    executor: &dyn Executor,
+    f: impl FnOnce() -> Result<T, Error> + Send + 'static,
+) -> impl Future<Output = Result<T, Error>> {
+    let (sender, receiver) = async_channel::bounded(1);
+    let spawned = executor
+        .spawn_blocking(Box::new(move || {
+            let _ = sender.send_blocking(f());
+        }))
+        .map_err(Error::io("failed to run a blocking voicevox call"));
+    async move {
+        spawned?;
+        // The sender is only dropped without sending if `f` panicked or the executor shut
+        // down before running it.
+        receiver.recv().await.unwrap_or_else(|_| {
+            Err(Error::io("the blocking task did not finish")(
+                std::io::Error::other("the blocking task panicked or was cancelled"),
+            ))
+        })
+    }
+}
@@ -0,0 +1,124 @@
+use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+
+/// Runs the blocking calls of a [`nonblocking::VoiceVox`](super::VoiceVox) for an async
+/// runtime.
+///
+/// Implemented for tokio, async-std and smol with their features, and for plain threads,
+/// which work with any runtime. Implement it to use the blocking thread pool of another
+/// runtime.
+pub trait Executor: Send + Sync + 'static {
+    /// Runs `f` where blocking is fine, without waiting for it to finish.
+    ///
+    /// Returns an error if `f` can not be run, e.g. because no thread could be spawned.
+    fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>) -> std::io::Result<()>;
+
+    /// Completes after `duration` without blocking.
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+/// Spawns a thread for every call, independent of the async runtime.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadExecutor;
+
+impl Executor for ThreadExecutor {
+    fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>) -> std::io::Result<()> {
+        std::thread::Builder::new()
+            .name("voicevox-blocking".to_owned())
+            .spawn(f)
+            .map(drop)
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let (sender, receiver) = async_channel::bounded(1);
+        let spawned = self.spawn_blocking(Box::new(move || {
+            std::thread::sleep(duration);
+            let _ = sender.send_blocking(());
+        }));
+        if let Err(e) = spawned {
+            // Never completing lets the call run without a timeout instead of timing it out
+            // right away.
+            error!("Failed to spawn a thread for a voicevox timeout: {}", e);
+            return Box::pin(std::future::pending());
+        }
+        Box::pin(async move {
+            let _ = receiver.recv().await;
+        })
+    }
+}
+
+/// Uses tokio's blocking thread pool. Calls made outside of a tokio runtime return an
+/// [`Error::Io`](crate::Error::Io).
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioExecutor;
+
+#[cfg(feature = "tokio")]
+impl Executor for TokioExecutor {
+    fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>) -> std::io::Result<()> {
+        let runtime = tokio::runtime::Handle::try_current().map_err(std::io::Error::other)?;
+        runtime.spawn_blocking(f);
+        Ok(())
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        // Creating the timer outside of a runtime panics. Never completing lets the call run
+        // without a timeout like in `ThreadExecutor::sleep`.
+        if let Err(e) = tokio::runtime::Handle::try_current() {
+            error!("Failed to start a voicevox timeout: {}", e);
+            return Box::pin(std::future::pending());
+        }
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Uses async-std's blocking thread pool.
+#[cfg(feature = "async-std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsyncStdExecutor;
+
+#[cfg(feature = "async-std")]
+impl Executor for AsyncStdExecutor {
+    fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>) -> std::io::Result<()> {
+        // Dropping the join handle detaches the task.
+        async_std::task::spawn_blocking(f);
+        Ok(())
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(async_std::task::sleep(duration))
+    }
+}
+
+/// Uses smol's blocking thread pool.
+#[cfg(feature = "smol")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SmolExecutor;
+
+#[cfg(feature = "smol")]
+impl Executor for SmolExecutor {
+    fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>) -> std::io::Result<()> {
+        smol::unblock(f).detach();
+        Ok(())
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let timer = smol::Timer::after(duration);
+        Box::pin(async move {
+            timer.await;
+        })
+    }
+}
+
+/// The executor of the first enabled runtime feature out of `tokio`, `async-std` and `smol`,
+/// otherwise [`ThreadExecutor`].
+pub(crate) fn default_executor() -> Arc<dyn Executor> {
+    #[cfg(feature = "tokio")]
+    let executor = TokioExecutor;
+    #[cfg(all(feature = "async-std", not(feature = "tokio")))]
+    let executor = AsyncStdExecutor;
+    #[cfg(all(feature = "smol", not(any(feature = "tokio", feature = "async-std"))))]
+    let executor = SmolExecutor;
+    #[cfg(not(any(feature = "tokio", feature = "async-std", feature = "smol")))]
+    let executor = ThreadExecutor;
+    Arc::new(executor)
+}
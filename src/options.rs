@@ -0,0 +1,457 @@
+use std::{borrow::Cow, time::Duration};
+
+use crate::{
+    ssml::{Prosody, Segment},
+    text::{self, FilterChain, Normalizer, TextFilter, Transliterator},
+    CancellationToken, Error, FadeCurve, Wav,
+};
+
+/// Options for [`VoiceVox::tts`](crate::VoiceVox::tts) and related methods.
+///
+/// Can be created with [`TtsOptions::builder`] or `TtsOptions::default()`,
+/// which disables every option.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone)]
+pub struct TtsOptions {
+    /// Treats the text as AquesTalk style kana instead of regular Japanese text,
+    /// e.g. `"コンニチワ'"`, see [`TtsOptionsBuilder::kana`].
+    pub kana: bool,
+    /// Raises the pitch at the end of questions, see [`TtsOptionsBuilder::enable_interrogative_upspeak`].
+    pub enable_interrogative_upspeak: bool,
+}
+
+impl TtsOptions {
+    /// ```
+    /// use voicevox_dyn::TtsOptions;
+    ///
+    /// let opts = TtsOptions::builder().enable_interrogative_upspeak(true).build();
+    /// assert!(opts.enable_interrogative_upspeak);
+    /// ```
+    pub fn builder() -> TtsOptionsBuilder {
+        TtsOptionsBuilder::default()
+    }
+
+    /// Checks whether `text` can be synthesized with these options.
+    ///
+    /// This is done automatically before synthesizing, so that mistakes are reported
+    /// with a helpful [`Error::InvalidInput`] instead of an opaque error from voicevox.
+    /// Currently only text passed with [`TtsOptions::kana`] enabled is checked, with
+    /// [`text::lint_kana`], and the first problem is reported.
+    pub fn validate(&self, text: &str) -> Result<(), Error> {
+        if self.kana {
+            if let Some(issue) = text::lint_kana(text).into_iter().next() {
+                return Err(Error::InvalidInput(issue.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Applies `f` to `text`, keeping it borrowed if neither changes it.
+fn then<'t>(text: Cow<'t, str>, f: impl Fn(&str) -> Cow<'_, str>) -> Cow<'t, str> {
+    match text {
+        Cow::Borrowed(text) => f(text),
+        Cow::Owned(text) => Cow::Owned(f(&text).into_owned()),
+    }
+}
+
+/// Builds [`TtsOptions`], created with [`TtsOptions::builder`].
+#[derive(Default, Debug, Clone)]
+pub struct TtsOptionsBuilder {
+    opts: TtsOptions,
+}
+
+impl TtsOptionsBuilder {
+    /// Treats the text as AquesTalk style kana instead of regular Japanese text.
+    /// Defaults to `false`.
+    ///
+    /// Kana skips Open JTalk's reading and accent estimation, which gives full control over
+    /// the pronunciation. The text may only contain katakana and these symbols:
+    /// - `'` marks the accent of the preceding mora,
+    /// - `/` separates accent phrases,
+    /// - `、` separates accent phrases with a pause,
+    /// - `_` makes the following mora unvoiced,
+    /// - `？` at the end of an accent phrase makes it a question.
+    pub fn kana(mut self, kana: bool) -> Self {
+        self.opts.kana = kana;
+        self
+    }
+
+    /// Raises the pitch at the end of sentences ending with `?` or `？`,
+    /// which makes questions sound like questions. Defaults to `false`.
+    pub fn enable_interrogative_upspeak(mut self, enable_interrogative_upspeak: bool) -> Self {
+        self.opts.enable_interrogative_upspeak = enable_interrogative_upspeak;
+        self
+    }
+
+    pub fn build(self) -> TtsOptions {
+        self.opts
+    }
+}
+
+/// Options for [`VoiceVox::tts_with_options`](crate::VoiceVox::tts_with_options),
+/// which prepare the text and post-process the synthesized [`Wav`].
+///
+/// ```
+/// use voicevox_dyn::{SynthesisOptions, TtsOptions};
+///
+/// let opts = SynthesisOptions::new().tts_options(TtsOptions::builder().kana(true).build());
+/// ```
+#[derive(Debug, Clone)]
+pub struct SynthesisOptions {
+    tts: TtsOptions,
+    filters: FilterChain,
+    pause_markup: bool,
+    auto_upspeak: bool,
+    inline_readings: bool,
+    normalizer: Option<Normalizer>,
+    ruby: bool,
+    normalize_numbers: bool,
+    transliterator: Option<Transliterator>,
+    trim_silence: bool,
+    loudness: Option<f32>,
+    pan: Option<f32>,
+    fade_in: Option<Duration>,
+    fade_out: Option<Duration>,
+    fade_curve: FadeCurve,
+    #[cfg(feature = "resample")]
+    output_rate: Option<u32>,
+    max_input_len: usize,
+    long_input: LongInput,
+    cancellation: Option<CancellationToken>,
+    timeout: Option<Duration>,
+}
+
+/// What [`VoiceVox::tts_with_options`](crate::VoiceVox::tts_with_options) does with text
+/// longer than [`SynthesisOptions::max_input_len`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LongInput {
+    /// Splits the text at punctuation with [`text::split_chunks`], synthesizes the pieces
+    /// and joins them.
+    #[default]
+    Split,
+    /// Returns [`Error::InvalidInput`].
+    Error,
+}
+
+impl Default for SynthesisOptions {
+    fn default() -> Self {
+        Self {
+            tts: TtsOptions::default(),
+            filters: FilterChain::new(),
+            pause_markup: true,
+            auto_upspeak: false,
+            inline_readings: true,
+            normalizer: Some(Normalizer::new()),
+            ruby: false,
+            normalize_numbers: true,
+            transliterator: None,
+            trim_silence: false,
+            loudness: None,
+            pan: None,
+            fade_in: None,
+            fade_out: None,
+            fade_curve: FadeCurve::default(),
+            #[cfg(feature = "resample")]
+            output_rate: None,
+            max_input_len: 200,
+            long_input: LongInput::Split,
+            cancellation: None,
+            timeout: None,
+        }
+    }
+}
+
+/// The level below which [`SynthesisOptions::trim_silence`] considers samples silent.
+const TRIM_THRESHOLD_DB: f32 = -50.0;
+
+impl SynthesisOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options passed to the voicevox core.
+    pub fn tts_options(mut self, tts: TtsOptions) -> Self {
+        self.tts = tts;
+        self
+    }
+
+    /// Rewrites the text with `filter` before synthesizing, e.g. with a
+    /// [`SymbolFilter`](text::SymbolFilter) to handle URLs and emoji.
+    ///
+    /// Filters run in the order they were added, after [`SynthesisOptions::inline_readings`] and
+    /// [`SynthesisOptions::normalizer`] and before the other text options.
+    pub fn filter(mut self, filter: impl TextFilter + 'static) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Turns tags such as `[pause 500ms]` into pauses, see [`text::parse_pauses`].
+    /// Defaults to `true`.
+    ///
+    /// The text around the tags is prepared and synthesized separately and the pauses are
+    /// added to the silence after or before it.
+    pub fn pause_markup(mut self, pause_markup: bool) -> Self {
+        self.pause_markup = pause_markup;
+        self
+    }
+
+    /// Raises the pitch at the end of the sentences that are questions, including those without
+    /// a question mark such as `行きますか。`, see [`text::is_question`]. Defaults to `false`.
+    ///
+    /// [`TtsOptions::enable_interrogative_upspeak`] only raises the pitch of sentences ending
+    /// with `？`, this adds the question mark with [`text::mark_questions`] and enables it.
+    pub fn auto_upspeak(mut self, auto_upspeak: bool) -> Self {
+        self.auto_upspeak = auto_upspeak;
+        self
+    }
+
+    /// Normalizes the text to NFKC with the given exceptions before the other steps, so that
+    /// text written with full-width or half-width characters is read the same. `None` disables
+    /// it. Defaults to [`Normalizer::new`].
+    pub fn normalizer(mut self, normalizer: Option<Normalizer>) -> Self {
+        self.normalizer = normalizer;
+        self
+    }
+
+    /// Reads inline readings such as `{重複|ちょうふく}` as written, see
+    /// [`text::apply_inline_readings`]. Defaults to `true`.
+    pub fn inline_readings(mut self, inline_readings: bool) -> Self {
+        self.inline_readings = inline_readings;
+        self
+    }
+
+    /// Reads words annotated with ruby such as `漢字（かんじ）` as annotated, see
+    /// [`text::apply_ruby`]. Defaults to `false`.
+    pub fn ruby(mut self, ruby: bool) -> Self {
+        self.ruby = ruby;
+        self
+    }
+
+    /// Writes numbers, dates, times and units as they are read before synthesizing, see
+    /// [`text::normalize_numbers`]. Defaults to `true`, disable it if the text is already
+    /// normalized.
+    pub fn normalize_numbers(mut self, normalize_numbers: bool) -> Self {
+        self.normalize_numbers = normalize_numbers;
+        self
+    }
+
+    /// Rewrites latin words in katakana with `transliterator` before synthesizing, see
+    /// [`Transliterator`]. Disabled by default.
+    pub fn transliterate(mut self, transliterator: Transliterator) -> Self {
+        self.transliterator = Some(transliterator);
+        self
+    }
+
+    /// Removes the silence voicevox pads the start and end of speech with, see
+    /// [`Wav::trim_silence`]. Defaults to `false`.
+    pub fn trim_silence(mut self, trim_silence: bool) -> Self {
+        self.trim_silence = trim_silence;
+        self
+    }
+
+    /// Normalizes the loudness of the output to `target` LUFS, see [`Wav::normalize_lufs`].
+    ///
+    /// Voices differ noticeably in loudness, normalizing every utterance to the same target
+    /// evens them out, e.g. in dialogues between several speakers.
+    pub fn normalize_lufs(mut self, target: f32) -> Self {
+        self.loudness = Some(target);
+        self
+    }
+
+    /// Turns the output into stereo with the speech placed at `pan`, see [`Wav::pan`].
+    ///
+    /// Giving every character of a dialogue its own position makes it easier to tell them apart,
+    /// `0.0` gives plain stereo output.
+    pub fn pan(mut self, pan: f32) -> Self {
+        self.pan = Some(pan);
+        self
+    }
+
+    /// Fades the output in over `duration`, see [`Wav::fade_in`].
+    pub fn fade_in(mut self, duration: Duration) -> Self {
+        self.fade_in = Some(duration);
+        self
+    }
+
+    /// Fades the output out over `duration`, see [`Wav::fade_out`].
+    pub fn fade_out(mut self, duration: Duration) -> Self {
+        self.fade_out = Some(duration);
+        self
+    }
+
+    /// The curve of [`SynthesisOptions::fade_in`] and [`SynthesisOptions::fade_out`].
+    /// Defaults to [`FadeCurve::Linear`].
+    pub fn fade_curve(mut self, curve: FadeCurve) -> Self {
+        self.fade_curve = curve;
+        self
+    }
+
+    /// Resamples the output to `sample_rate`, see [`Wav::resample`].
+    /// Defaults to the sampling rate of the core, 24 kHz.
+    #[cfg(feature = "resample")]
+    pub fn output_rate(mut self, sample_rate: u32) -> Self {
+        self.output_rate = Some(sample_rate);
+        self
+    }
+
+    /// The number of characters above which text is considered too long to synthesize at once,
+    /// 200 by default. Very long sentences sound worse and need a lot of memory.
+    pub fn max_input_len(mut self, chars: usize) -> Self {
+        self.max_input_len = chars;
+        self
+    }
+
+    /// What happens to text longer than [`SynthesisOptions::max_input_len`], it is split by
+    /// default. Kana is never split, as that could break its accent phrases.
+    pub fn on_long_input(mut self, long_input: LongInput) -> Self {
+        self.long_input = long_input;
+        self
+    }
+
+    /// Stops the synthesis with [`Error::Cancelled`] once `token` is cancelled.
+    ///
+    /// Voicevox core 0.14 can not interrupt an inference, so the synthesis stops before the
+    /// next piece of a long text, the next sentence of a [`TtsStream`](crate::TtsStream) or
+    /// the next chunk of a [`LongText`](crate::LongText). The pieces and chunks synthesized
+    /// before are returned in [`Error::Cancelled`], e.g. to play the audio up to where the
+    /// user skipped.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Returns [`Error::Timeout`] if the synthesis takes longer than `timeout`, e.g. because
+    /// of a pathological input or a hanging GPU driver.
+    ///
+    /// The synthesis runs on a separate thread that is abandoned when it times out. Voicevox
+    /// core can not interrupt it, so it keeps the core busy until it returns, see
+    /// [`VoiceVox::needs_reinit`](crate::VoiceVox::needs_reinit).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// The timeout and the options without it for the thread enforcing it.
+    pub(crate) fn watchdog(&self) -> Option<(Duration, Self)> {
+        let timeout = self.timeout?;
+        let opts = Self {
+            timeout: None,
+            ..self.clone()
+        };
+        Some((timeout, opts))
+    }
+
+    /// Returns [`Error::Cancelled`] if [`SynthesisOptions::cancellation`] was cancelled.
+    pub(crate) fn check_cancelled(&self) -> Result<(), Error> {
+        self.cancellation
+            .as_ref()
+            .map_or(Ok(()), CancellationToken::check)
+    }
+
+    /// Splits the prepared `text` into the pieces that are synthesized separately.
+    pub(crate) fn split<'t>(&self, text: &'t str) -> Result<Vec<&'t str>, Error> {
+        let len = text.chars().count();
+        if len <= self.max_input_len || self.tts.kana {
+            return Ok(vec![text]);
+        }
+        match self.long_input {
+            LongInput::Split => Ok(text::split_chunks(text, self.max_input_len)),
+            LongInput::Error => Err(Error::InvalidInput(format!(
+                "the text is {len} characters long, at most {} are allowed",
+                self.max_input_len
+            ))),
+        }
+    }
+
+    /// Splits `text` at its pause tags if [`SynthesisOptions::pause_markup`] is enabled.
+    pub(crate) fn segments(&self, text: &str) -> Result<Vec<Segment>, Error> {
+        if self.pause_markup {
+            text::parse_pauses(text)
+        } else {
+            Ok(vec![Segment::Speech {
+                text: text.to_owned(),
+                prosody: Prosody::default(),
+            }])
+        }
+    }
+
+    pub(crate) fn tts(&self) -> TtsOptions {
+        TtsOptions {
+            enable_interrogative_upspeak: self.tts.enable_interrogative_upspeak
+                || self.auto_upspeak,
+            ..self.tts
+        }
+    }
+
+    /// Prepares `text` for synthesis like [`VoiceVox::tts_with_options`](crate::VoiceVox::tts_with_options)
+    /// does, e.g. to check what the filters make of a text.
+    ///
+    /// Kana input is passed to voicevox as written apart from the filters, as normalizing it
+    /// would e.g. turn the full-width `？` of a question into `?`, which is not valid kana.
+    ///
+    /// ```
+    /// use voicevox_dyn::{SynthesisOptions, TtsOptions};
+    ///
+    /// let opts = SynthesisOptions::new().tts_options(TtsOptions::builder().kana(true).build());
+    /// assert_eq!(opts.prepare_text("コンニチワ'？"), "コンニチワ'？");
+    /// ```
+    pub fn prepare_text<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let mut text = Cow::Borrowed(text);
+        // First, so that no other step rewrites the markup.
+        if self.inline_readings {
+            text = then(text, text::apply_inline_readings);
+        }
+        // Before the filters, so that they only have to handle one form of every character.
+        if let Some(normalizer) = self.normalizer.as_ref().filter(|_| !self.tts.kana) {
+            text = then(text, |text| normalizer.filter(text));
+        }
+        text = then(text, |text| self.filters.filter(text));
+        if self.ruby {
+            text = then(text, text::apply_ruby);
+        }
+        if self.auto_upspeak && !self.tts.kana {
+            text = then(text, text::mark_questions);
+        }
+        // Kana can not contain numbers anyway.
+        if self.normalize_numbers && !self.tts.kana {
+            text = then(text, text::normalize_numbers);
+        }
+        // After the numbers, which keep units such as `GB` from being spelled out.
+        if let Some(transliterator) = self.transliterator.as_ref().filter(|_| !self.tts.kana) {
+            text = then(text, |text| transliterator.transliterate(text));
+        }
+        text
+    }
+
+    /// Applies the post-processing steps to `wav`.
+    pub fn apply(&self, wav: Wav) -> Result<Wav, Error> {
+        let wav = if self.trim_silence {
+            wav.trim_silence(TRIM_THRESHOLD_DB)
+        } else {
+            wav
+        };
+        let wav = match self.loudness {
+            Some(target) => wav.normalize_lufs(target),
+            None => wav,
+        };
+        let wav = match self.pan {
+            Some(pan) => wav.pan(pan),
+            None => wav,
+        };
+        let wav = match self.fade_in {
+            Some(duration) => wav.fade_in(duration, self.fade_curve),
+            None => wav,
+        };
+        let wav = match self.fade_out {
+            Some(duration) => wav.fade_out(duration, self.fade_curve),
+            None => wav,
+        };
+        #[cfg(feature = "resample")]
+        let wav = match self.output_rate {
+            Some(sample_rate) => wav.resample(sample_rate)?,
+            None => wav,
+        };
+        Ok(wav)
+    }
+}
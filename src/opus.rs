@@ -0,0 +1,118 @@
+use audiopus::{coder::Encoder, Application, Bitrate, Channels, SampleRate};
+use ogg::{PacketWriteEndInfo, PacketWriter};
+
+use crate::{Error, Wav};
+
+/// The duration of an opus packet, 20 ms is what most VoIP applications expect.
+const FRAMES_PER_SECOND: u32 = 50;
+/// Ogg opus counts samples at 48 kHz, regardless of the sampling rate of the input.
+const GRANULE_RATE: u64 = 48000;
+/// The largest packet opus recommends allocating for.
+const MAX_PACKET_SIZE: usize = 4000;
+
+impl Wav {
+    /// Encodes the wav into opus packets of 20 ms each, e.g. for sending them to Discord.
+    ///
+    /// `bitrate` is in bits per second, 32000 is plenty for speech.
+    /// The sampling rate has to be 8, 12, 16, 24 or 48 kHz, which includes the 24 kHz voicevox
+    /// synthesizes by default, and there may be at most 2 channels.
+    pub fn encode_opus(&self, bitrate: u32) -> Result<Vec<Vec<u8>>, Error> {
+        let encoder = self.opus_encoder(bitrate)?;
+        let frame_len = (self.sample_rate / FRAMES_PER_SECOND) as usize * self.channels as usize;
+
+        let mut samples = self.samples_i16();
+        // The last frame is padded with silence, opus only encodes complete frames.
+        samples.resize(samples.len().max(1).next_multiple_of(frame_len), 0);
+        let mut buf = [0; MAX_PACKET_SIZE];
+        samples
+            .chunks_exact(frame_len)
+            .map(|frame| {
+                let len = encoder
+                    .encode(frame, &mut buf)
+                    .map_err(|e| Error::Encode(Box::new(e)))?;
+                Ok(buf[..len].to_vec())
+            })
+            .collect()
+    }
+
+    /// Encodes the wav into an ogg opus file, which browsers can play directly.
+    ///
+    /// See [`Wav::encode_opus`] for the supported inputs.
+    pub fn encode_ogg(&self, bitrate: u32) -> Result<Vec<u8>, Error> {
+        // The decoder discards the samples the encoder delays its output by.
+        let pre_skip = self
+            .opus_encoder(bitrate)?
+            .lookahead()
+            .map_err(|e| Error::Encode(Box::new(e)))? as u64
+            * GRANULE_RATE
+            / self.sample_rate as u64;
+        let packets = self.encode_opus(bitrate)?;
+        let total_granules = self.data.len() as u64 / self.block_align() as u64 * GRANULE_RATE
+            / self.sample_rate as u64;
+        let packet_granules = GRANULE_RATE / FRAMES_PER_SECOND as u64;
+
+        let mut head = Vec::with_capacity(19);
+        head.extend_from_slice(b"OpusHead");
+        head.push(1);
+        head.push(self.channels as u8);
+        head.extend_from_slice(&(pre_skip as u16).to_le_bytes());
+        head.extend_from_slice(&self.sample_rate.to_le_bytes());
+        // Output gain and channel mapping family 0, i.e. mono or stereo.
+        head.extend_from_slice(&[0, 0, 0]);
+
+        let vendor = concat!("voicevox-dyn ", env!("CARGO_PKG_VERSION"));
+        let mut tags = Vec::new();
+        tags.extend_from_slice(b"OpusTags");
+        tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        tags.extend_from_slice(vendor.as_bytes());
+        tags.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut writer = PacketWriter::new(Vec::new());
+        let serial = 0x766f_7876;
+        writer
+            .write_packet(head.into(), serial, PacketWriteEndInfo::EndPage, 0)
+            .and_then(|()| writer.write_packet(tags.into(), serial, PacketWriteEndInfo::EndPage, 0))
+            .map_err(Error::io("failed to write the ogg stream"))?;
+
+        let last = packets.len().saturating_sub(1);
+        for (i, packet) in packets.into_iter().enumerate() {
+            let (end, granule) = if i == last {
+                // The final granule position tells the decoder to drop the padding.
+                (PacketWriteEndInfo::EndStream, pre_skip + total_granules)
+            } else {
+                (
+                    PacketWriteEndInfo::NormalPacket,
+                    pre_skip + (i as u64 + 1) * packet_granules,
+                )
+            };
+            writer
+                .write_packet(packet.into(), serial, end, granule)
+                .map_err(Error::io("failed to write the ogg stream"))?;
+        }
+        Ok(writer.into_inner())
+    }
+
+    fn opus_encoder(&self, bitrate: u32) -> Result<Encoder, Error> {
+        let sample_rate = SampleRate::try_from(self.sample_rate as i32).map_err(|_| {
+            Error::InvalidInput(format!(
+                "opus does not support a sampling rate of {} Hz, resample to 8, 12, 16, 24 or 48 kHz first",
+                self.sample_rate
+            ))
+        })?;
+        let channels = match self.channels {
+            1 => Channels::Mono,
+            2 => Channels::Stereo,
+            channels => {
+                return Err(Error::InvalidInput(format!(
+                    "opus supports at most 2 channels, the wav has {channels}"
+                )))
+            }
+        };
+        let mut encoder = Encoder::new(sample_rate, channels, Application::Voip)
+            .map_err(|e| Error::Encode(Box::new(e)))?;
+        encoder
+            .set_bitrate(Bitrate::BitsPerSecond(bitrate.min(i32::MAX as u32) as i32))
+            .map_err(|e| Error::Encode(Box::new(e)))?;
+        Ok(encoder)
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll, Waker},
+};
+
+use crate::{Error, Wav};
+
+/// The result of a call that is made in the background, e.g. by
+/// [`VoiceVoxPool::submit`](crate::VoiceVoxPool::submit).
+///
+/// Either block on it with [`Pending::wait`] or `.await` it, which works with any async
+/// runtime.
+#[derive(Debug)]
+pub struct Pending<T> {
+    slot: Arc<Slot<T>>,
+}
+
+/// Speech that is synthesized in the background.
+pub type PendingSynthesis = Pending<Wav>;
+
+/// Completes a [`Pending`]. Dropping it without completing it reports that the call did not
+/// finish.
+pub(crate) struct Completion<T> {
+    slot: Option<Arc<Slot<T>>>,
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    state: Mutex<State<T>>,
+    ready: Condvar,
+}
+
+#[derive(Debug)]
+struct State<T> {
+    result: Option<Result<T, Error>>,
+    waker: Option<Waker>,
+}
+
+/// Creates a [`Pending`] and the [`Completion`] that completes it.
+pub(crate) fn pending<T>() -> (Completion<T>, Pending<T>) {
+    let slot = Arc::new(Slot {
+        state: Mutex::new(State {
+            result: None,
+            waker: None,
+        }),
+        ready: Condvar::new(),
+    });
+    (
+        Completion {
+            slot: Some(slot.clone()),
+        },
+        Pending { slot },
+    )
+}
+
+impl<T> Slot<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn fill(&self, result: Result<T, Error>) {
+        let mut state = self.lock();
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        self.ready.notify_all();
+    }
+}
+
+impl<T> Completion<T> {
+    pub(crate) fn complete(mut self, result: Result<T, Error>) {
+        if let Some(slot) = self.slot.take() {
+            slot.fill(result);
+        }
+    }
+}
+
+impl<T> Drop for Completion<T> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            slot.fill(Err(Error::io("the call did not finish")(
+                std::io::Error::other("the thread making the call panicked or shut down"),
+            )));
+        }
+    }
+}
+
+impl<T> Pending<T> {
+    /// Blocks until the call finishes.
+    pub fn wait(self) -> Result<T, Error> {
+        let mut state = self.slot.lock();
+        loop {
+            if let Some(result) = state.result.take() {
+                return result;
+            }
+            state = self
+                .slot
+                .ready
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// The result if the call finished already, otherwise `self` to try again later.
+    pub fn try_wait(self) -> Result<Result<T, Error>, Self> {
+        let result = self.slot.lock().result.take();
+        result.ok_or(self)
+    }
+
+    /// Whether [`Pending::wait`] would return right away.
+    pub fn is_ready(&self) -> bool {
+        self.slot.lock().result.is_some()
+    }
+}
+
+impl<T> Future for Pending<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.slot.lock();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
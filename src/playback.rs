@@ -0,0 +1,79 @@
+use rodio::{buffer::SamplesBuffer, OutputStream, Sink};
+
+use crate::{Error, Wav};
+
+/// Plays a wav on the default output device, e.g. the result of [`VoiceVox::tts`](crate::VoiceVox::tts).
+///
+/// Playback stops when the returned [`Playback`] is dropped,
+/// call [`Playback::wait`] to block until it has finished.
+///
+/// ```no_run
+/// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, VoiceVox};
+///
+/// # fn main() -> Result<(), voicevox_dyn::Error> {
+/// let vv = VoiceVox::quickstart(ZUNDAMON_NORMAL)?;
+/// let wav = vv.tts("こんにちは", ZUNDAMON_NORMAL, Default::default())?;
+/// voicevox_dyn::play(&wav)?.wait();
+/// # Ok(())
+/// # }
+/// ```
+pub fn play(wav: &[u8]) -> Result<Playback, Error> {
+    play_wav(&Wav::parse(wav)?)
+}
+
+/// Same as [`play`] but takes an already parsed [`Wav`].
+pub fn play_wav(wav: &Wav) -> Result<Playback, Error> {
+    let (stream, handle) = OutputStream::try_default().map_err(|e| Error::Playback(Box::new(e)))?;
+    let sink = Sink::try_new(&handle).map_err(|e| Error::Playback(Box::new(e)))?;
+    sink.append(SamplesBuffer::new(
+        wav.channels,
+        wav.sample_rate,
+        wav.samples_i16(),
+    ));
+    Ok(Playback {
+        sink,
+        _stream: stream,
+    })
+}
+
+/// A wav that is being played, created with [`play`].
+///
+/// Dropping it stops playback.
+pub struct Playback {
+    sink: Sink,
+    // Playback stops once the stream is dropped.
+    _stream: OutputStream,
+}
+
+impl Playback {
+    /// Blocks until the wav has been played completely.
+    pub fn wait(self) {
+        self.sink.sleep_until_end();
+    }
+
+    /// Whether the wav has been played completely.
+    pub fn is_finished(&self) -> bool {
+        self.sink.empty()
+    }
+
+    pub fn pause(&self) {
+        self.sink.pause();
+    }
+
+    pub fn resume(&self) {
+        self.sink.play();
+    }
+
+    /// Stops playback, same as dropping the `Playback`.
+    pub fn stop(self) {
+        self.sink.stop();
+    }
+}
+
+impl std::fmt::Debug for Playback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Playback")
+            .field("is_finished", &self.is_finished())
+            .finish_non_exhaustive()
+    }
+}
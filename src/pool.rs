@@ -0,0 +1,142 @@
+use std::{
+    panic::AssertUnwindSafe,
+    sync::{mpsc, Arc, Mutex, PoisonError},
+    thread::JoinHandle,
+};
+
+use crate::{pending::pending, Error, PendingSynthesis, StyleId, SynthesisOptions, VoiceVox, Wav};
+
+type Job = Box<dyn FnOnce(&VoiceVox) + Send>;
+
+/// Worker threads that synthesize submitted texts in parallel, e.g. for a server that has
+/// to handle many requests at once.
+///
+/// Every request is prepared, synthesized and post-processed with
+/// [`VoiceVox::tts_with_options`] on one of the workers. Voicevox core 0.14 keeps its state in
+/// globals and runs one inference at a time, see [`VoiceVox`], so the workers only overlap
+/// the work before and after it, such as preparing the text, resampling and
+/// [`SynthesisOptions::normalize_lufs`]. Creating more workers than CPU cores does not help.
+///
+/// Dropping the pool waits for the submitted requests to finish.
+///
+/// ```no_run
+/// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, VoiceVox, VoiceVoxPool};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let pool = VoiceVoxPool::new(VoiceVox::quickstart(ZUNDAMON_NORMAL)?, 4)?;
+/// let opts = Default::default();
+/// let pending: Vec<_> = ["おはよう", "こんにちは", "こんばんは"]
+///     .into_iter()
+///     .map(|text| pool.submit(text, ZUNDAMON_NORMAL, &opts))
+///     .collect();
+/// for (i, pending) in pending.into_iter().enumerate() {
+///     std::fs::write(format!("{i}.wav"), pending.wait()?.to_bytes())?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct VoiceVoxPool {
+    vv: Arc<VoiceVox>,
+    sender: Option<mpsc::Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl VoiceVoxPool {
+    /// Starts `workers` threads, at least one, that synthesize with `vv`.
+    pub fn new(vv: VoiceVox, workers: usize) -> Result<Self, Error> {
+        let vv = Arc::new(vv);
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..workers.max(1))
+            .map(|i| {
+                let vv = vv.clone();
+                let receiver = receiver.clone();
+                std::thread::Builder::new()
+                    .name(format!("voicevox-pool-{i}"))
+                    .spawn(move || loop {
+                        let job = receiver
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .recv();
+                        let Ok(job) = job else {
+                            break;
+                        };
+                        // A panicking request drops its completion, which is reported to its
+                        // `PendingSynthesis`, and must not take the worker down with it.
+                        if std::panic::catch_unwind(AssertUnwindSafe(|| job(&vv))).is_err() {
+                            error!("A request panicked on voicevox pool worker {}.", i);
+                        }
+                    })
+                    .map_err(Error::io("failed to spawn a voicevox pool worker"))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            vv,
+            sender: Some(sender),
+            workers,
+        })
+    }
+
+    /// The [`VoiceVox`] the workers synthesize with, e.g. for loading models.
+    pub fn voicevox(&self) -> &VoiceVox {
+        &self.vv
+    }
+
+    /// The number of worker threads.
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Queues `text` for synthesis with [`VoiceVox::tts_with_options`] on the next free worker.
+    ///
+    /// The returned [`PendingSynthesis`] can also be awaited in async code.
+    pub fn submit(
+        &self,
+        text: impl Into<String>,
+        style_id: StyleId,
+        opts: &SynthesisOptions,
+    ) -> PendingSynthesis {
+        let (completion, pending) = pending();
+        let text = text.into();
+        let opts = opts.clone();
+        self.execute(move |vv| completion.complete(vv.tts_with_options(text, style_id, &opts)));
+        pending
+    }
+
+    /// Same as [`VoiceVox::tts_batch`] but synthesizes the items on all workers, returning the
+    /// results in the order of `items`.
+    pub fn tts_batch(
+        &self,
+        items: &[(String, StyleId, SynthesisOptions)],
+    ) -> Vec<Result<Wav, Error>> {
+        let pending: Vec<_> = items
+            .iter()
+            .map(|(text, style_id, opts)| self.submit(text.clone(), *style_id, opts))
+            .collect();
+        pending.into_iter().map(PendingSynthesis::wait).collect()
+    }
+
+    fn execute(&self, job: impl FnOnce(&VoiceVox) + Send + 'static) {
+        if let Some(sender) = &self.sender {
+            // The workers only stop once the sender is dropped.
+            let _ = sender.send(Box::new(job));
+        }
+    }
+}
+
+impl Drop for VoiceVoxPool {
+    fn drop(&mut self) {
+        self.sender = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl std::fmt::Debug for VoiceVoxPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VoiceVoxPool")
+            .field("workers", &self.workers.len())
+            .finish_non_exhaustive()
+    }
+}
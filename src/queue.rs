@@ -0,0 +1,274 @@
+use std::{
+    cmp::Reverse,
+    panic::AssertUnwindSafe,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    thread::JoinHandle,
+};
+
+use crate::{
+    pending::{pending, Completion},
+    Error, PendingSynthesis, StyleId, SynthesisOptions, VoiceVoxHandle, Wav,
+};
+
+/// How urgent a request queued with [`SynthesisQueue::submit`] is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
+
+/// Identifies a request queued with [`SynthesisQueue::submit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RequestId(u64);
+
+/// A worker thread that synthesizes queued requests one after another, the most urgent first.
+///
+/// Requests with a higher [`Priority`] are synthesized first, requests with the same priority
+/// in the order they were submitted. While a request is queued, its priority can be changed
+/// with [`SynthesisQueue::set_priority`] and it can be cancelled with
+/// [`SynthesisQueue::cancel`]. A request that is already being synthesized is not
+/// interrupted, pass a [`CancellationToken`](crate::CancellationToken) with
+/// [`SynthesisOptions::cancellation`] to be able to stop it.
+///
+/// Dropping the queue cancels the queued requests and waits for the current one to finish.
+///
+/// ```no_run
+/// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, Priority, SynthesisQueue, VoiceVox};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let queue = SynthesisQueue::new(VoiceVox::quickstart(ZUNDAMON_NORMAL)?.into_handle())?;
+/// let opts = Default::default();
+/// let (_, chapter) = queue.submit("むかしむかし、あるところに…", ZUNDAMON_NORMAL, &opts, Priority::Low);
+/// // Synthesized before the chapter unless the chapter is being synthesized already.
+/// let (_, alert) = queue.submit("バッテリーが残りわずかです", ZUNDAMON_NORMAL, &opts, Priority::Urgent);
+/// std::fs::write("alert.wav", alert.wait()?.to_bytes())?;
+/// std::fs::write("chapter.wav", chapter.wait()?.to_bytes())?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct SynthesisQueue {
+    handle: VoiceVoxHandle,
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    queued: Vec<Request>,
+    next_id: u64,
+    closed: bool,
+}
+
+struct Request {
+    id: RequestId,
+    priority: Priority,
+    text: String,
+    style_id: StyleId,
+    opts: SynthesisOptions,
+    completion: Completion<Wav>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl State {
+    /// Removes the most urgent request, the one submitted first among equally urgent ones.
+    fn pop(&mut self) -> Option<Request> {
+        let (i, _) = self
+            .queued
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, request)| (request.priority, Reverse(request.id.0)))?;
+        Some(self.queued.swap_remove(i))
+    }
+
+    fn position(&self, id: RequestId) -> Option<usize> {
+        self.queued.iter().position(|request| request.id == id)
+    }
+}
+
+impl SynthesisQueue {
+    /// Starts the worker thread that synthesizes with `handle`.
+    pub fn new(handle: VoiceVoxHandle) -> Result<Self, Error> {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::default()),
+            changed: Condvar::new(),
+        });
+        let worker = {
+            let handle = handle.clone();
+            let shared = shared.clone();
+            std::thread::Builder::new()
+                .name("voicevox-queue".to_owned())
+                .spawn(move || work(&handle, &shared))
+                .map_err(Error::io("failed to spawn the voicevox queue worker"))?
+        };
+        Ok(Self {
+            handle,
+            shared,
+            worker: Some(worker),
+        })
+    }
+
+    /// The [`VoiceVoxHandle`] the worker synthesizes with, e.g. for loading models.
+    pub fn handle(&self) -> &VoiceVoxHandle {
+        &self.handle
+    }
+
+    /// Queues `text` for synthesis with [`VoiceVox::tts_with_options`](crate::VoiceVox::tts_with_options).
+    ///
+    /// Returns the id for changing or cancelling the request and its result, which can also
+    /// be awaited in async code.
+    pub fn submit(
+        &self,
+        text: impl Into<String>,
+        style_id: StyleId,
+        opts: &SynthesisOptions,
+        priority: Priority,
+    ) -> (RequestId, PendingSynthesis) {
+        let (completion, pending) = pending();
+        let mut state = self.shared.lock();
+        let id = RequestId(state.next_id);
+        state.next_id += 1;
+        state.queued.push(Request {
+            id,
+            priority,
+            text: text.into(),
+            style_id,
+            opts: opts.clone(),
+            completion,
+        });
+        self.shared.changed.notify_one();
+        (id, pending)
+    }
+
+    /// Changes the priority of a queued request. Returns `false` if the request is not queued
+    /// anymore, because it is being synthesized, finished or was cancelled.
+    ///
+    /// The request is synthesized after the requests that were submitted before it with the
+    /// same priority.
+    pub fn set_priority(&self, id: RequestId, priority: Priority) -> bool {
+        let mut state = self.shared.lock();
+        let Some(i) = state.position(id) else {
+            return false;
+        };
+        state.queued[i].priority = priority;
+        true
+    }
+
+    /// Removes a queued request, its [`PendingSynthesis`] returns [`Error::Cancelled`].
+    /// Returns `false` if the request is not queued anymore, because it is being synthesized,
+    /// finished or was cancelled already.
+    pub fn cancel(&self, id: RequestId) -> bool {
+        let request = {
+            let mut state = self.shared.lock();
+            let Some(i) = state.position(id) else {
+                return false;
+            };
+            state.queued.swap_remove(i)
+        };
+        request.completion.complete(Err(Error::Cancelled {
+            completed: Vec::new(),
+        }));
+        true
+    }
+
+    /// Cancels all queued requests with a priority below `priority`, e.g. the remaining
+    /// narration when the user starts a new conversation. Returns how many were cancelled.
+    pub fn cancel_below(&self, priority: Priority) -> usize {
+        self.cancel_where(|request| request.priority < priority)
+    }
+
+    /// Cancels all queued requests. Returns how many were cancelled.
+    pub fn clear(&self) -> usize {
+        self.cancel_where(|_| true)
+    }
+
+    /// The number of queued requests, not counting the one being synthesized.
+    pub fn len(&self) -> usize {
+        self.shared.lock().queued.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn cancel_where(&self, mut f: impl FnMut(&Request) -> bool) -> usize {
+        let cancelled: Vec<Request> = {
+            let mut state = self.shared.lock();
+            let (cancelled, kept) = std::mem::take(&mut state.queued)
+                .into_iter()
+                .partition(|request| f(request));
+            state.queued = kept;
+            cancelled
+        };
+        let count = cancelled.len();
+        for request in cancelled {
+            request.completion.complete(Err(Error::Cancelled {
+                completed: Vec::new(),
+            }));
+        }
+        count
+    }
+}
+
+/// Synthesizes the queued requests until the queue is closed.
+fn work(handle: &VoiceVoxHandle, shared: &Shared) {
+    loop {
+        let request = {
+            let mut state = shared.lock();
+            loop {
+                if let Some(request) = state.pop() {
+                    break request;
+                }
+                if state.closed {
+                    return;
+                }
+                state = shared
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+        };
+        // A panicking request drops its completion, which is reported to its
+        // `PendingSynthesis`, and must not stop the queue.
+        let result = std::panic::catch_unwind(AssertUnwindSafe(move || {
+            let result = handle.synthesize(|vv| {
+                vv.tts_with_options(request.text, request.style_id, &request.opts)
+            });
+            request.completion.complete(result);
+        }));
+        if result.is_err() {
+            error!("A request panicked on the voicevox queue worker.");
+        }
+    }
+}
+
+impl Drop for SynthesisQueue {
+    fn drop(&mut self) {
+        self.clear();
+        self.shared.lock().closed = true;
+        self.shared.changed.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl std::fmt::Debug for SynthesisQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SynthesisQueue")
+            .field("queued", &self.len())
+            .finish_non_exhaustive()
+    }
+}
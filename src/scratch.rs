@@ -0,0 +1,84 @@
+use std::ffi::CStr;
+
+use crate::{Error, StyleId, TtsOptions, VoiceVox};
+
+/// Buffers that [`VoiceVox::tts_with_scratch`] reuses across calls, so that callers
+/// synthesizing many texts do not allocate for every one of them.
+///
+/// The buffers grow to the largest text and wav synthesized with them and keep their size.
+#[derive(Debug, Clone, Default)]
+pub struct SynthesisScratch {
+    /// The text with a terminating NUL byte for voicevox core.
+    text: Vec<u8>,
+    wav: Vec<u8>,
+}
+
+impl SynthesisScratch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Buffers that fit a text of `text_bytes` and a wav of `wav_bytes` without growing.
+    pub fn with_capacity(text_bytes: usize, wav_bytes: usize) -> Self {
+        Self {
+            text: Vec::with_capacity(text_bytes + 1),
+            wav: Vec::with_capacity(wav_bytes),
+        }
+    }
+
+    /// The wav synthesized last, empty before the first synthesis.
+    pub fn wav(&self) -> &[u8] {
+        &self.wav
+    }
+
+    /// Copies `text` into the text buffer as a C string.
+    fn c_text(&mut self, text: &str) -> Result<&CStr, Error> {
+        if let Some(position) = text.bytes().position(|b| b == 0) {
+            return Err(Error::InvalidInput(format!(
+                "text contains a NUL byte at position {}",
+                position
+            )));
+        }
+        self.text.clear();
+        self.text.extend_from_slice(text.as_bytes());
+        self.text.push(0);
+        Ok(CStr::from_bytes_with_nul(&self.text).expect("the text was checked for NUL bytes"))
+    }
+}
+
+impl VoiceVox {
+    /// Same as [`VoiceVox::tts_into`] but also reuses the buffer of the C string passed to
+    /// voicevox core, returning the wav in `scratch`.
+    ///
+    /// Voicevox core still allocates the wav it returns, which is copied into `scratch` and
+    /// freed right away. The [`SynthesisCache`](crate::SynthesisCache) is not used.
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, SynthesisScratch, VoiceVox};
+    ///
+    /// # fn main() -> Result<(), voicevox_dyn::Error> {
+    /// let vv = VoiceVox::quickstart(ZUNDAMON_NORMAL)?;
+    /// let mut scratch = SynthesisScratch::new();
+    /// for line in ["はい", "いいえ", "もう一度"] {
+    ///     let wav = vv.tts_with_scratch(line, ZUNDAMON_NORMAL, Default::default(), &mut scratch)?;
+    ///     println!("{}: {} bytes", line, wav.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tts_with_scratch<'s>(
+        &self,
+        text: &str,
+        style_id: StyleId,
+        opts: TtsOptions,
+        scratch: &'s mut SynthesisScratch,
+    ) -> Result<&'s [u8], Error> {
+        info!("Synthesizing speech from: {}", text);
+        self.prepare_style(style_id)?;
+        opts.validate(text)?;
+        let wav = self.tts_c(scratch.c_text(text)?, style_id, opts)?;
+        scratch.wav.clear();
+        scratch.wav.extend_from_slice(&wav);
+        Ok(&scratch.wav)
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    FromSample, SampleFormat, SizedSample, Stream, StreamConfig,
+};
+
+use crate::{Error, SynthesisOptions, VoiceVox, Wav};
+
+/// How much audio [`SpeechStream::new`] queues before [`SpeechStream::enqueue`] blocks.
+const DEFAULT_CAPACITY: Duration = Duration::from_secs(10);
+
+/// Plays utterances on the default output device as they are queued, with
+/// [cpal](https://docs.rs/cpal).
+///
+/// Utterances are played back to back in the order they were queued. Once the queue holds
+/// more audio than its capacity, [`SpeechStream::enqueue`] blocks until enough has been played,
+/// so synthesis never runs too far ahead of playback.
+///
+/// ```no_run
+/// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, SpeechStream, VoiceVox};
+///
+/// # fn main() -> Result<(), voicevox_dyn::Error> {
+/// let vv = VoiceVox::quickstart(ZUNDAMON_NORMAL)?;
+/// let stream = SpeechStream::new()?;
+/// // Playback starts as soon as the first sentence is synthesized.
+/// stream.say(&vv, "おはよう。今日はいい天気ですね。", ZUNDAMON_NORMAL, &Default::default())?;
+/// stream.wait();
+/// # Ok(())
+/// # }
+/// ```
+pub struct SpeechStream {
+    shared: Arc<Shared>,
+    sample_rate: u32,
+    channels: u16,
+    capacity: usize,
+    // Playback stops once the stream is dropped.
+    _stream: Stream,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    /// Notified whenever samples were played or removed from the queue.
+    played: Condvar,
+}
+
+#[derive(Default)]
+struct Queue {
+    /// Interleaved samples in the format of the output device.
+    utterances: VecDeque<Vec<f32>>,
+    /// The position in the first utterance.
+    position: usize,
+    /// The number of samples left to play.
+    len: usize,
+    paused: bool,
+}
+
+impl Queue {
+    fn skip(&mut self) {
+        if let Some(utterance) = self.utterances.pop_front() {
+            self.len -= utterance.len() - self.position;
+            self.position = 0;
+        }
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Fills `output` with the queued samples, or silence if there are none.
+    fn fill<T: SizedSample + FromSample<f32>>(&self, output: &mut [T]) {
+        let mut queue = self.lock();
+        let mut written = 0;
+        while !queue.paused && written < output.len() {
+            let position = queue.position;
+            let Some(utterance) = queue.utterances.front() else {
+                break;
+            };
+            let n = (utterance.len() - position).min(output.len() - written);
+            for (output, &sample) in output[written..written + n]
+                .iter_mut()
+                .zip(&utterance[position..position + n])
+            {
+                *output = T::from_sample(sample);
+            }
+            let finished = position + n == utterance.len();
+            written += n;
+            queue.position += n;
+            queue.len -= n;
+            if finished {
+                queue.utterances.pop_front();
+                queue.position = 0;
+            }
+        }
+        for output in &mut output[written..] {
+            *output = T::EQUILIBRIUM;
+        }
+        drop(queue);
+        self.played.notify_all();
+    }
+}
+
+impl SpeechStream {
+    /// Opens the default output device, queueing up to 10 seconds of audio.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Same as [`SpeechStream::new`] but queues up to `capacity` of audio.
+    pub fn with_capacity(capacity: Duration) -> Result<Self, Error> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| Error::Playback("no output device available".into()))?;
+        let supported = device
+            .default_output_config()
+            .map_err(|e| Error::Playback(Box::new(e)))?;
+        let format = supported.sample_format();
+        let config: StreamConfig = supported.into();
+
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue::default()),
+            played: Condvar::new(),
+        });
+        let stream = match format {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, &shared),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, &shared),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, &shared),
+            SampleFormat::I32 => build_stream::<i32>(&device, &config, &shared),
+            format => {
+                return Err(Error::Playback(
+                    format!("unsupported sample format {format}").into(),
+                ))
+            }
+        }?;
+        stream.play().map_err(|e| Error::Playback(Box::new(e)))?;
+
+        let capacity = (capacity.as_secs_f64() * config.sample_rate.0 as f64) as usize
+            * config.channels as usize;
+        Ok(Self {
+            shared,
+            sample_rate: config.sample_rate.0,
+            channels: config.channels,
+            capacity,
+            _stream: stream,
+        })
+    }
+
+    /// Queues `wav` for playback after the utterances queued before it.
+    ///
+    /// Blocks while the queue is full, unless it is empty, in which case `wav` is queued even
+    /// if it is longer than the capacity. While playback is paused, the queue never empties.
+    pub fn enqueue(&self, wav: &Wav) -> Result<(), Error> {
+        let samples = self.convert(wav);
+        let mut queue = self.shared.lock();
+        while queue.len > 0 && queue.len + samples.len() > self.capacity {
+            queue = self
+                .shared
+                .played
+                .wait(queue)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        queue.len += samples.len();
+        queue.utterances.push_back(samples);
+        Ok(())
+    }
+
+    /// Synthesizes `text` sentence by sentence with [`VoiceVox::tts_stream`] and queues every
+    /// sentence as soon as it is ready.
+    ///
+    /// Returns once everything has been queued, call [`SpeechStream::wait`] to wait for playback.
+    pub fn say(
+        &self,
+        vv: &VoiceVox,
+        text: impl AsRef<str>,
+        speaker_id: u32,
+        opts: &SynthesisOptions,
+    ) -> Result<(), Error> {
+        for chunk in vv.tts_stream(text, speaker_id, opts) {
+            self.enqueue(&chunk?.wav)?;
+        }
+        Ok(())
+    }
+
+    /// Blocks until every queued utterance has been played.
+    pub fn wait(&self) {
+        let mut queue = self.shared.lock();
+        while queue.len > 0 {
+            queue = self
+                .shared
+                .played
+                .wait(queue)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// The length of the audio that is still queued.
+    pub fn queued(&self) -> Duration {
+        let frames = self.shared.lock().len / self.channels as usize;
+        Duration::from_secs_f64(frames as f64 / self.sample_rate as f64)
+    }
+
+    /// Whether everything that was queued has been played.
+    pub fn is_idle(&self) -> bool {
+        self.shared.lock().len == 0
+    }
+
+    pub fn pause(&self) {
+        self.shared.lock().paused = true;
+    }
+
+    pub fn resume(&self) {
+        self.shared.lock().paused = false;
+    }
+
+    /// Stops the utterance that is playing and continues with the next one.
+    pub fn skip(&self) {
+        self.shared.lock().skip();
+        self.shared.played.notify_all();
+    }
+
+    /// Stops playback and removes every queued utterance.
+    pub fn stop(&self) {
+        let mut queue = self.shared.lock();
+        queue.utterances.clear();
+        queue.position = 0;
+        queue.len = 0;
+        drop(queue);
+        self.shared.played.notify_all();
+    }
+
+    /// Converts `wav` to the sampling rate and channels of the output device.
+    ///
+    /// The sampling rate is converted by linear interpolation, which is fine for speech that
+    /// is usually played at a higher sampling rate than it was synthesized at.
+    fn convert(&self, wav: &Wav) -> Vec<f32> {
+        let input = wav.channels_f32();
+        let len = input[0].len();
+        let ratio = wav.sample_rate as f64 / self.sample_rate as f64;
+        let frames = (len as f64 / ratio).round() as usize;
+        let output: Vec<Vec<f32>> = (0..self.channels as usize)
+            .map(|channel| {
+                let source: Vec<f32> = match (input.len(), self.channels) {
+                    (1, _) => input[0].clone(),
+                    // Everything is mixed down for mono output devices.
+                    (n, 1) => (0..len)
+                        .map(|i| input.iter().map(|c| c[i]).sum::<f32>() / n as f32)
+                        .collect(),
+                    _ => input
+                        .get(channel)
+                        .cloned()
+                        .unwrap_or_else(|| vec![0.0; len]),
+                };
+                (0..frames)
+                    .map(|i| {
+                        let position = i as f64 * ratio;
+                        let index = position as usize;
+                        let t = (position - index as f64) as f32;
+                        let a = source.get(index).copied().unwrap_or(0.0);
+                        let b = source.get(index + 1).copied().unwrap_or(a);
+                        a + (b - a) * t
+                    })
+                    .collect()
+            })
+            .collect();
+        Wav::interleave(&output)
+    }
+}
+
+fn build_stream<T: SizedSample + FromSample<f32>>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    shared: &Arc<Shared>,
+) -> Result<Stream, Error> {
+    let shared = shared.clone();
+    device
+        .build_output_stream(
+            config,
+            move |output: &mut [T], _| shared.fill(output),
+            |e| error!("Audio output failed: {}", e),
+            None,
+        )
+        .map_err(|e| Error::Playback(Box::new(e)))
+}
+
+impl std::fmt::Debug for SpeechStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpeechStream")
+            .field("sample_rate", &self.sample_rate)
+            .field("channels", &self.channels)
+            .field("queued", &self.queued())
+            .finish_non_exhaustive()
+    }
+}
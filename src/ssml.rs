@@ -0,0 +1,416 @@
+//! Parsing a practical subset of [SSML](https://www.w3.org/TR/speech-synthesis11/).
+//!
+//! Supported are `<break>`, `<prosody>` with `rate`, `pitch` and `volume`, `<sub>`, `<p>` and
+//! `<s>`. Other tags are skipped but their content is still spoken, and attribute values that
+//! can not be understood are ignored.
+//! [`VoiceVox::tts_ssml`](crate::VoiceVox::tts_ssml) synthesizes the parsed segments.
+//!
+//! ```
+//! use std::time::Duration;
+//! use voicevox_dyn::ssml::{self, Prosody, Segment};
+//!
+//! let segments = ssml::parse(
+//!     r#"<speak>こんにちは<break time="500ms"/><prosody rate="slow">さようなら</prosody></speak>"#,
+//! )?;
+//! assert_eq!(
+//!     segments,
+//!     [
+//!         Segment::Speech { text: "こんにちは".to_owned(), prosody: Prosody::default() },
+//!         Segment::Break(Duration::from_millis(500)),
+//!         Segment::Speech {
+//!             text: "さようなら".to_owned(),
+//!             prosody: Prosody { rate: 0.75, ..Default::default() },
+//!         },
+//!     ]
+//! );
+//! # Ok::<(), voicevox_dyn::Error>(())
+//! ```
+
+use std::time::Duration;
+
+use crate::{AudioQuery, Error};
+
+/// A part of an SSML document, see [`parse`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    /// Text that is spoken with the given prosody.
+    Speech { text: String, prosody: Prosody },
+    /// A pause, from `<break>`.
+    Break(Duration),
+}
+
+/// The prosody of a [`Segment::Speech`], from `<prosody>`.
+///
+/// Nested `<prosody>` tags multiply the rate and volume and add up the pitch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prosody {
+    /// Speed of the speech relative to the query, `1.0` is unchanged.
+    pub rate: f32,
+    /// Pitch shift added to the query, in the unit of [`AudioQuery::pitch_scale`].
+    pub pitch: f32,
+    /// Volume relative to the query, `1.0` is unchanged.
+    pub volume: f32,
+}
+
+impl Default for Prosody {
+    fn default() -> Self {
+        Self {
+            rate: 1.0,
+            pitch: 0.0,
+            volume: 1.0,
+        }
+    }
+}
+
+impl Prosody {
+    /// Applies the prosody on top of the settings of `query`.
+    pub fn apply(&self, query: &mut AudioQuery) {
+        query.speed_scale *= self.rate;
+        query.pitch_scale += self.pitch;
+        query.volume_scale *= self.volume;
+    }
+
+    fn nest(self, inner: Prosody) -> Prosody {
+        Prosody {
+            rate: self.rate * inner.rate,
+            pitch: self.pitch + inner.pitch,
+            volume: self.volume * inner.volume,
+        }
+    }
+}
+
+/// An element that has been opened but not closed yet.
+struct Element {
+    name: String,
+    /// The prosody of the content of the element.
+    prosody: Prosody,
+    /// Whether the content is replaced by an alias and not spoken.
+    replaced: bool,
+}
+
+/// Parses an SSML document into segments.
+///
+/// The `<speak>` root element is optional. Consecutive text with the same prosody is joined
+/// into one segment, unless it is in different `<p>` or `<s>` elements. Whitespace is collapsed.
+///
+/// Returns [`Error::InvalidInput`] if the document is not well-formed, e.g. if a tag is not
+/// closed.
+pub fn parse(ssml: &str) -> Result<Vec<Segment>, Error> {
+    let mut segments = Vec::new();
+    let mut stack: Vec<Element> = Vec::new();
+    // Whether the next text starts a new segment even if the prosody stays the same.
+    let mut boundary = true;
+    let mut rest = ssml;
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment
+                .find("-->")
+                .ok_or_else(|| invalid("a comment is not closed"))?;
+            rest = &comment[end + 3..];
+        } else if let Some(tag) = rest.strip_prefix('<') {
+            let end = tag_end(tag).ok_or_else(|| invalid("a tag is not closed"))?;
+            rest = &tag[end + 1..];
+            let tag = &tag[..end];
+            // Declarations such as `<?xml ...?>` and `<!DOCTYPE ...>`.
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                let name = name.trim();
+                match stack.pop() {
+                    Some(element) if element.name == name => {}
+                    Some(element) => {
+                        return Err(invalid(format!(
+                            "expected </{}>, found </{name}>",
+                            element.name
+                        )))
+                    }
+                    None => return Err(invalid(format!("</{name}> was never opened"))),
+                }
+                if matches!(name, "p" | "s") {
+                    boundary = true;
+                }
+                continue;
+            }
+            let (tag, self_closing) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let tag = tag.trim();
+            let (name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+            let attributes = parse_attributes(attributes)?;
+            let attribute = |key: &str| {
+                attributes
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.as_str())
+            };
+            let outer = stack.last().map_or_else(Prosody::default, |e| e.prosody);
+            let mut element = Element {
+                name: name.to_owned(),
+                prosody: outer,
+                replaced: stack.last().is_some_and(|e| e.replaced),
+            };
+            match name {
+                "speak" => {}
+                "p" | "s" => boundary = true,
+                "break" => segments.push(Segment::Break(break_duration(
+                    attribute("time"),
+                    attribute("strength"),
+                ))),
+                "prosody" => {
+                    let mut inner = Prosody::default();
+                    if let Some(rate) = attribute("rate") {
+                        inner.rate = parse_rate(rate).unwrap_or_else(|| ignored("rate", rate, 1.0));
+                    }
+                    if let Some(pitch) = attribute("pitch") {
+                        inner.pitch =
+                            parse_pitch(pitch).unwrap_or_else(|| ignored("pitch", pitch, 0.0));
+                    }
+                    if let Some(volume) = attribute("volume") {
+                        inner.volume =
+                            parse_volume(volume).unwrap_or_else(|| ignored("volume", volume, 1.0));
+                    }
+                    element.prosody = outer.nest(inner);
+                }
+                "sub" => {
+                    if let Some(alias) = attribute("alias") {
+                        if !element.replaced {
+                            push_text(&mut segments, alias, outer, &mut boundary);
+                        }
+                        element.replaced = true;
+                    }
+                }
+                _ => debug!("Skipping the unsupported SSML tag <{}>", name),
+            }
+            if !self_closing {
+                stack.push(element);
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            if !stack.last().is_some_and(|e| e.replaced) {
+                let prosody = stack.last().map_or_else(Prosody::default, |e| e.prosody);
+                push_text(
+                    &mut segments,
+                    &unescape(&rest[..end])?,
+                    prosody,
+                    &mut boundary,
+                );
+            }
+            rest = &rest[end..];
+        }
+    }
+    if let Some(element) = stack.last() {
+        return Err(invalid(format!("<{}> is not closed", element.name)));
+    }
+    for segment in &mut segments {
+        if let Segment::Speech { text, .. } = segment {
+            text.truncate(text.trim_end().len());
+        }
+    }
+    Ok(segments)
+}
+
+fn invalid(reason: impl std::fmt::Display) -> Error {
+    Error::InvalidInput(format!("invalid SSML: {reason}"))
+}
+
+fn ignored<T>(attribute: &str, value: &str, default: T) -> T {
+    debug!("Ignoring the unsupported SSML {} \"{}\"", attribute, value);
+    default
+}
+
+/// Appends `text` to the last segment if possible, or starts a new one.
+///
+/// Whitespace is collapsed but kept at the ends, as it separates `text` from the text around it.
+fn push_text(segments: &mut Vec<Segment>, text: &str, prosody: Prosody, boundary: &mut bool) {
+    let mut collapsed = String::with_capacity(text.len());
+    if text.starts_with(char::is_whitespace) {
+        collapsed.push(' ');
+    }
+    for (i, word) in text.split_whitespace().enumerate() {
+        if i > 0 {
+            collapsed.push(' ');
+        }
+        collapsed.push_str(word);
+    }
+    if text.ends_with(char::is_whitespace) && collapsed != " " {
+        collapsed.push(' ');
+    }
+    match segments.last_mut() {
+        Some(Segment::Speech {
+            text: last,
+            prosody: last_prosody,
+        }) if !*boundary && *last_prosody == prosody => {
+            if last.ends_with(' ') {
+                last.push_str(collapsed.trim_start());
+            } else {
+                last.push_str(&collapsed);
+            }
+        }
+        _ if collapsed.trim().is_empty() => return,
+        _ => segments.push(Segment::Speech {
+            text: collapsed.trim_start().to_owned(),
+            prosody,
+        }),
+    }
+    *boundary = false;
+}
+
+/// Finds the `>` that ends the tag at the start of `tag`, skipping quoted attribute values.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '>') => return Some(i),
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_attributes(mut attributes: &str) -> Result<Vec<(String, String)>, Error> {
+    let mut parsed = Vec::new();
+    loop {
+        attributes = attributes.trim_start();
+        if attributes.is_empty() {
+            return Ok(parsed);
+        }
+        let (key, value) = attributes
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("expected a value for {attributes}")))?;
+        let value = value.trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|c| matches!(c, '"' | '\''))
+            .ok_or_else(|| invalid(format!("the value of {} is not quoted", key.trim())))?;
+        let end = value[1..]
+            .find(quote)
+            .ok_or_else(|| invalid(format!("the value of {} is not closed", key.trim())))?;
+        parsed.push((key.trim().to_owned(), unescape(&value[1..end + 1])?));
+        attributes = &value[end + 2..];
+    }
+}
+
+/// Replaces the XML character references in `text`.
+fn unescape(text: &str) -> Result<String, Error> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(';')
+            .ok_or_else(|| invalid("a character reference is not closed"))?;
+        let reference = &rest[start + 1..start + end];
+        let c = match reference {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => reference
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| reference.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        unescaped
+            .push(c.ok_or_else(|| invalid(format!("unknown character reference &{reference};")))?);
+        rest = &rest[start + end + 1..];
+    }
+    unescaped.push_str(rest);
+    Ok(unescaped)
+}
+
+/// The duration of a `<break>`, which is a medium break without attributes.
+fn break_duration(time: Option<&str>, strength: Option<&str>) -> Duration {
+    if let Some(time) = time {
+        if let Some(duration) = parse_time(time) {
+            return duration;
+        }
+        debug!("Ignoring the unsupported SSML time \"{}\"", time);
+    }
+    let millis = match strength.unwrap_or("medium") {
+        "none" => 0,
+        "x-weak" => 100,
+        "weak" => 250,
+        "strong" => 750,
+        "x-strong" => 1000,
+        strength => {
+            if strength != "medium" {
+                debug!("Ignoring the unsupported SSML strength \"{}\"", strength);
+            }
+            500
+        }
+    };
+    Duration::from_millis(millis)
+}
+
+pub(crate) fn parse_time(time: &str) -> Option<Duration> {
+    let time = time.trim();
+    let seconds = match time.strip_suffix("ms") {
+        Some(millis) => millis.parse::<f64>().ok()? / 1000.0,
+        None => time.strip_suffix('s')?.parse().ok()?,
+    };
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// Parses a percentage such as `150%` or, if `relative`, `+50%`, into a factor.
+fn parse_percentage(value: &str) -> Option<f32> {
+    let percent: f32 = value.strip_suffix('%')?.parse().ok()?;
+    if value.starts_with(['+', '-']) {
+        Some(1.0 + percent / 100.0)
+    } else {
+        Some(percent / 100.0)
+    }
+}
+
+fn parse_rate(rate: &str) -> Option<f32> {
+    let rate = match rate.trim() {
+        "x-slow" => 0.5,
+        "slow" => 0.75,
+        "medium" | "default" => 1.0,
+        "fast" => 1.25,
+        "x-fast" => 1.5,
+        rate => parse_percentage(rate).or_else(|| rate.parse().ok())?,
+    };
+    (rate > 0.0).then_some(rate)
+}
+
+/// Parses a pitch into a shift of the logarithmic pitch voicevox uses.
+fn parse_pitch(pitch: &str) -> Option<f32> {
+    match pitch.trim() {
+        "x-low" => Some(-0.1),
+        "low" => Some(-0.05),
+        "medium" | "default" => Some(0.0),
+        "high" => Some(0.05),
+        "x-high" => Some(0.1),
+        pitch => {
+            if let Some(semitones) = pitch.strip_suffix("st") {
+                return Some(semitones.parse::<f32>().ok()? * std::f32::consts::LN_2 / 12.0);
+            }
+            let factor = parse_percentage(pitch)?;
+            (factor > 0.0).then(|| factor.ln())
+        }
+    }
+}
+
+fn parse_volume(volume: &str) -> Option<f32> {
+    let volume = match volume.trim() {
+        "silent" => 0.0,
+        "x-soft" => 0.25,
+        "soft" => 0.5,
+        "medium" | "default" => 1.0,
+        "loud" => 1.5,
+        "x-loud" => 2.0,
+        volume => match volume.strip_suffix("dB") {
+            Some(db) => 10f32.powf(db.parse::<f32>().ok()? / 20.0),
+            None => parse_percentage(volume)?,
+        },
+    };
+    (volume >= 0.0).then_some(volume)
+}
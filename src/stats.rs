@@ -0,0 +1,84 @@
+use std::time::{Duration, Instant};
+
+use crate::{cache::CacheKey, Error, StyleId, TtsOptions, VoiceVox, Wav};
+
+/// Where the time of a synthesis went, see [`VoiceVox::tts_with_stats`].
+///
+/// Useful for tracking latency across voicevox core versions and devices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SynthesisStats {
+    /// Creating the [`AudioQuery`](crate::AudioQuery), which analyzes the text and predicts
+    /// the phoneme lengths and the pitch.
+    pub query_time: Duration,
+    /// Synthesizing the audio from the query.
+    pub inference_time: Duration,
+    /// The whole call, including loading the model on demand and parsing the wav.
+    pub total_time: Duration,
+    /// The number of characters of the text.
+    pub input_chars: usize,
+    /// The number of samples per channel of the wav.
+    pub output_samples: usize,
+    /// Whether the wav was found in the [`SynthesisCache`](crate::SynthesisCache), in which
+    /// case the query and inference times are zero.
+    pub cache_hit: bool,
+}
+
+impl VoiceVox {
+    /// Same as [`VoiceVox::tts_wav`] but also returns where the time went.
+    ///
+    /// The text is synthesized in two steps with [`VoiceVox::audio_query`] and
+    /// [`VoiceVox::synthesis`] to time them separately, which results in the same audio.
+    ///
+    /// ```no_run
+    /// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, VoiceVox};
+    ///
+    /// # fn main() -> Result<(), voicevox_dyn::Error> {
+    /// let vv = VoiceVox::quickstart(ZUNDAMON_NORMAL)?;
+    /// let (wav, stats) = vv.tts_with_stats("こんにちは", ZUNDAMON_NORMAL, Default::default())?;
+    /// println!(
+    ///     "query {:?}, inference {:?}, total {:?}",
+    ///     stats.query_time, stats.inference_time, stats.total_time
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tts_with_stats(
+        &self,
+        text: impl AsRef<str>,
+        style_id: StyleId,
+        opts: TtsOptions,
+    ) -> Result<(Wav, SynthesisStats), Error> {
+        let start = Instant::now();
+        let text = text.as_ref();
+        let mut stats = SynthesisStats {
+            input_chars: text.chars().count(),
+            ..SynthesisStats::default()
+        };
+        let key = self
+            .cache
+            .as_ref()
+            .map(|cache| (cache, CacheKey::synthesis(text, style_id, opts)));
+
+        let wav = match key.as_ref().and_then(|(cache, key)| cache.get(key)) {
+            Some(wav) => {
+                stats.cache_hit = true;
+                Wav::parse_output(&wav)?
+            }
+            None => {
+                let query = self.audio_query(text, style_id, opts)?;
+                stats.query_time = start.elapsed();
+                let inference_start = Instant::now();
+                let output = self.synthesis_raw(&query, style_id, opts)?;
+                stats.inference_time = inference_start.elapsed();
+                if let Some((cache, key)) = key {
+                    cache.insert(key, output.to_vec());
+                }
+                Wav::parse_output(&output)?
+            }
+        };
+        stats.output_samples = wav.data.len() / wav.block_align().max(1) as usize;
+        stats.total_time = start.elapsed();
+        debug!("Synthesized {} characters: {:?}", stats.input_chars, stats);
+        Ok((wav, stats))
+    }
+}
@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+use crate::{text::split_chunks, Error, SynthesisOptions, VoiceVox, Wav};
+
+/// A sentence synthesized by [`VoiceVox::tts_stream`], or a chunk synthesized by
+/// [`LongText::stream`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioChunk {
+    /// The position of the sentence in the text, starting at 0.
+    pub index: usize,
+    /// The sentence that was synthesized.
+    pub text: String,
+    pub wav: Wav,
+}
+
+/// An iterator over the sentences of a text, synthesizing each one when it is requested.
+///
+/// Created with [`VoiceVox::tts_stream`].
+#[derive(Debug)]
+pub struct TtsStream<'a> {
+    vv: &'a VoiceVox,
+    speaker_id: u32,
+    opts: &'a SynthesisOptions,
+    sentences: std::iter::Enumerate<std::vec::IntoIter<String>>,
+}
+
+impl<'a> TtsStream<'a> {
+    pub(crate) fn new(
+        vv: &'a VoiceVox,
+        sentences: Vec<&str>,
+        speaker_id: u32,
+        opts: &'a SynthesisOptions,
+    ) -> Self {
+        let sentences: Vec<String> = sentences.into_iter().map(str::to_owned).collect();
+        Self {
+            vv,
+            speaker_id,
+            opts,
+            sentences: sentences.into_iter().enumerate(),
+        }
+    }
+
+    /// Synthesizes the remaining sentences and joins them with [`Wav::concat_crossfade`],
+    /// so they play back without clicks in between.
+    ///
+    /// Returns [`Error::InvalidInput`] if there is nothing left to synthesize. If the synthesis
+    /// is cancelled, [`Error::Cancelled`] holds the chunks synthesized before.
+    pub fn into_wav(self, fade: Duration) -> Result<Wav, Error> {
+        let mut chunks = Vec::with_capacity(self.len());
+        for chunk in self {
+            match chunk {
+                Ok(chunk) => chunks.push(chunk),
+                Err(e) => return Err(e.with_completed(chunks)),
+            }
+        }
+        let parts: Vec<Wav> = chunks.into_iter().map(|chunk| chunk.wav).collect();
+        if parts.is_empty() {
+            return Err(Error::InvalidInput(
+                "the text contains no sentences".to_owned(),
+            ));
+        }
+        Wav::concat_crossfade(&parts, fade)
+    }
+}
+
+impl Iterator for TtsStream<'_> {
+    type Item = Result<AudioChunk, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, text) = self.sentences.next()?;
+        let result = self
+            .vv
+            .tts_with_options(&text, self.speaker_id, self.opts)
+            .map(|wav| AudioChunk { index, text, wav });
+        if matches!(result, Err(Error::Cancelled { .. })) {
+            self.sentences.by_ref().for_each(drop);
+        }
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sentences.size_hint()
+    }
+}
+
+impl ExactSizeIterator for TtsStream<'_> {}
+
+/// Synthesizes texts of any length by splitting them into chunks with
+/// [`text::split_chunks`](crate::text::split_chunks) and synthesizing one chunk at a time.
+///
+/// Long texts take long to synthesize in one go and may exceed what voicevox can handle,
+/// short chunks keep the time to the first audio low.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, LongText, VoiceVox};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let vv = VoiceVox::quickstart(ZUNDAMON_NORMAL)?;
+/// let text = std::fs::read_to_string("story.txt")?;
+/// let wav = LongText::new()
+///     .max_chunk_len(50)
+///     .crossfade(Duration::from_millis(20))
+///     .synthesize(&vv, &text, ZUNDAMON_NORMAL, &Default::default())?;
+/// std::fs::write("story.wav", wav.to_bytes())?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LongText {
+    max_chunk_len: usize,
+    crossfade: Duration,
+}
+
+impl Default for LongText {
+    fn default() -> Self {
+        Self {
+            max_chunk_len: 100,
+            crossfade: Duration::ZERO,
+        }
+    }
+}
+
+impl LongText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The maximum number of characters synthesized at once, 100 by default.
+    pub fn max_chunk_len(mut self, chars: usize) -> Self {
+        self.max_chunk_len = chars;
+        self
+    }
+
+    /// How long consecutive chunks overlap in [`LongText::synthesize`], none by default.
+    pub fn crossfade(mut self, fade: Duration) -> Self {
+        self.crossfade = fade;
+        self
+    }
+
+    /// The chunks `text` is synthesized in.
+    pub fn chunks<'t>(&self, text: &'t str) -> Vec<&'t str> {
+        split_chunks(text, self.max_chunk_len)
+    }
+
+    /// Synthesizes `text` chunk by chunk as the returned iterator is advanced.
+    pub fn stream<'a>(
+        &self,
+        vv: &'a VoiceVox,
+        text: &str,
+        speaker_id: u32,
+        opts: &'a SynthesisOptions,
+    ) -> TtsStream<'a> {
+        TtsStream::new(vv, self.chunks(text), speaker_id, opts)
+    }
+
+    /// Synthesizes every chunk of `text` and joins them into one wav.
+    ///
+    /// Returns [`Error::InvalidInput`] if `text` is empty.
+    pub fn synthesize(
+        &self,
+        vv: &VoiceVox,
+        text: &str,
+        speaker_id: u32,
+        opts: &SynthesisOptions,
+    ) -> Result<Wav, Error> {
+        self.stream(vv, text, speaker_id, opts)
+            .into_wav(self.crossfade)
+    }
+}
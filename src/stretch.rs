@@ -0,0 +1,115 @@
+//! Time stretching with WSOLA, the waveform similarity based overlap-add.
+//!
+//! The output is assembled from windowed frames of the input at a fixed hop. Every frame is
+//! taken from around where the stretched position falls in the input, shifted to where it is
+//! most similar to the continuation of the previous frame, which keeps the pitch and avoids
+//! the phasing artifacts of plain overlap-add.
+
+use crate::{Error, Wav};
+
+/// The length of a frame in seconds.
+const FRAME_SECONDS: f64 = 0.03;
+/// How far frames may be shifted from their nominal position in seconds.
+const TOLERANCE_SECONDS: f64 = 0.01;
+
+impl Wav {
+    /// Changes the duration of the wav by `factor` without changing its pitch, e.g. `0.9` makes
+    /// it 10% shorter. The result is 16 bit.
+    ///
+    /// Unlike [`SpeechParams::speed`](crate::SpeechParams::speed) this works on the
+    /// synthesized audio, so it does not change how the voice pronounces the text.
+    ///
+    /// Returns [`Error::InvalidInput`] if `factor` is not a positive number.
+    ///
+    /// ```
+    /// use voicevox_dyn::Wav;
+    ///
+    /// let samples: Vec<f32> = (0..24000).map(|i| (i as f32 / 10.0).sin() * 0.5).collect();
+    /// let wav = Wav::from_samples_f32(24000, 1, &samples);
+    /// assert_eq!(wav.stretch(0.9)?.samples_i16().len(), 21600);
+    /// # Ok::<(), voicevox_dyn::Error>(())
+    /// ```
+    pub fn stretch(&self, factor: f64) -> Result<Wav, Error> {
+        if !(factor.is_finite() && factor > 0.0) {
+            return Err(Error::InvalidInput(format!(
+                "the stretch factor has to be a positive number, got {factor}"
+            )));
+        }
+
+        let input = self.channels_f32();
+        let len = input[0].len();
+        let output_len = (len as f64 * factor).round() as usize;
+        let frame = ((self.sample_rate as f64 * FRAME_SECONDS) as usize).max(2) & !1;
+        let tolerance = (self.sample_rate as f64 * TOLERANCE_SECONDS) as usize;
+        let hop = frame / 2;
+        let window: Vec<f32> = (0..frame)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / frame as f32).cos())
+            .collect();
+
+        // The input is padded with silence so frames and their search range never run out of it.
+        let padding = tolerance + frame + hop;
+        let padded: Vec<Vec<f32>> = input
+            .iter()
+            .map(|channel| {
+                let mut padded = vec![0.0; padding];
+                padded.extend_from_slice(channel);
+                padded.resize(padding + len + padding, 0.0);
+                padded
+            })
+            .collect();
+        // Similarity is measured on the sum of all channels.
+        let mix: Vec<f32> = (0..padded[0].len())
+            .map(|i| padded.iter().map(|channel| channel[i]).sum())
+            .collect();
+
+        let frames = output_len.div_ceil(hop) + 1;
+        let mut output = vec![vec![0.0; frames * hop + frame]; input.len()];
+        let mut weights = vec![0.0; frames * hop + frame];
+        let mut previous = padding;
+        for k in 0..frames {
+            let nominal = padding - hop + (k as f64 * hop as f64 / factor).round() as usize;
+            let nominal = nominal.min(padding + len);
+            let start = if k == 0 {
+                nominal
+            } else {
+                // The frame that would continue the previous one seamlessly.
+                let target = &mix[previous + hop..previous + hop + frame];
+                (nominal - tolerance..=nominal + tolerance)
+                    .max_by(|&a, &b| {
+                        correlation(target, &mix[a..a + frame])
+                            .total_cmp(&correlation(target, &mix[b..b + frame]))
+                    })
+                    .unwrap()
+            };
+            for (output, padded) in output.iter_mut().zip(&padded) {
+                for (i, w) in window.iter().enumerate() {
+                    output[k * hop + i] += padded[start + i] * w;
+                }
+            }
+            for (i, w) in window.iter().enumerate() {
+                weights[k * hop + i] += w;
+            }
+            previous = start;
+        }
+
+        // Frames are centered on their position, so the first half frame is skipped.
+        let offset = hop;
+        let output: Vec<Vec<f32>> = output
+            .into_iter()
+            .map(|channel| {
+                (offset..offset + output_len)
+                    .map(|i| channel[i] / weights[i].max(1e-3))
+                    .collect()
+            })
+            .collect();
+        Ok(Wav::from_samples_f32(
+            self.sample_rate,
+            self.channels,
+            &Wav::interleave(&output),
+        ))
+    }
+}
+
+fn correlation(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
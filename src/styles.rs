@@ -0,0 +1,48 @@
+//! Style ids of voices distributed with voicevox core 0.14.
+//!
+//! The ids are stable across releases, but not every installation contains every voice.
+//! [`VoiceVox::metas`](crate::VoiceVox::metas) lists the styles that are actually available and
+//! [`VoiceVox::default_style`](crate::VoiceVox::default_style) returns one that is guaranteed to exist.
+
+use crate::StyleId;
+
+/// 四国めたん (あまあま)
+pub const SHIKOKU_METAN_AMAAMA: StyleId = 0;
+/// 四国めたん (ノーマル)
+pub const SHIKOKU_METAN_NORMAL: StyleId = 2;
+/// 四国めたん (セクシー)
+pub const SHIKOKU_METAN_SEXY: StyleId = 4;
+/// 四国めたん (ツンツン)
+pub const SHIKOKU_METAN_TSUNTSUN: StyleId = 6;
+
+/// ずんだもん (あまあま)
+pub const ZUNDAMON_AMAAMA: StyleId = 1;
+/// ずんだもん (ノーマル)
+pub const ZUNDAMON_NORMAL: StyleId = 3;
+/// ずんだもん (セクシー)
+pub const ZUNDAMON_SEXY: StyleId = 5;
+/// ずんだもん (ツンツン)
+pub const ZUNDAMON_TSUNTSUN: StyleId = 7;
+/// ずんだもん (ささやき)
+pub const ZUNDAMON_SASAYAKI: StyleId = 22;
+
+/// 春日部つむぎ (ノーマル)
+pub const KASUKABE_TSUMUGI_NORMAL: StyleId = 8;
+/// 波音リツ (ノーマル)
+pub const NAMINE_RITSU_NORMAL: StyleId = 9;
+/// 雨晴はう (ノーマル)
+pub const AMEHARE_HAU_NORMAL: StyleId = 10;
+/// 玄野武宏 (ノーマル)
+pub const KURONO_TAKEHIRO_NORMAL: StyleId = 11;
+/// 白上虎太郎 (ふつう)
+pub const SHIRAKAMI_KOTAROU_NORMAL: StyleId = 12;
+/// 青山龍星 (ノーマル)
+pub const AOYAMA_RYUSEI_NORMAL: StyleId = 13;
+/// 冥鳴ひまり (ノーマル)
+pub const MEIMEI_HIMARI_NORMAL: StyleId = 14;
+/// 九州そら (ノーマル)
+pub const KYUSHU_SORA_NORMAL: StyleId = 16;
+/// もち子さん (ノーマル)
+pub const MOCHIKO_NORMAL: StyleId = 20;
+/// 剣崎雌雄 (ノーマル)
+pub const KENZAKI_MESUO_NORMAL: StyleId = 21;
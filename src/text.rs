@@ -0,0 +1,420 @@
+//! Helpers for preparing text for synthesis.
+
+use std::{borrow::Cow, ops::Range};
+
+use crate::{
+    ssml::{self, Prosody, Segment},
+    Error,
+};
+
+mod filter;
+mod kana;
+mod normalize;
+mod numbers;
+mod transliterate;
+
+pub use filter::{FilterChain, Handling, SymbolFilter, TextFilter, WordFilter};
+pub use kana::{lint_kana, KanaIssue};
+pub use normalize::Normalizer;
+pub use numbers::normalize_numbers;
+pub use transliterate::Transliterator;
+
+/// Characters that end a sentence.
+const SENTENCE_ENDS: &[char] = &['。', '！', '？', '!', '?', '．', '\n'];
+/// Closing brackets that belong to the sentence they follow, as in `「はい。」`.
+const CLOSING_BRACKETS: &[char] = &['」', '』', '）', ')', '】'];
+
+/// Splits `text` into sentences, keeping the punctuation that ends them.
+///
+/// Whitespace around sentences is trimmed and empty sentences are skipped.
+///
+/// ```
+/// use voicevox_dyn::text::split_sentences;
+///
+/// assert_eq!(
+///     split_sentences("こんにちは。「元気？」\nはい！ 元気です"),
+///     ["こんにちは。", "「元気？」", "はい！", "元気です"]
+/// );
+/// ```
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    sentence_ranges(text)
+        .into_iter()
+        .map(|range| &text[range])
+        .collect()
+}
+
+/// Splits `text` into chunks of at most `max_chars` characters for synthesis.
+///
+/// Sentences are split as in [`split_sentences`], sentences longer than `max_chars` are split
+/// further after commas and spaces and, if that is not enough, anywhere. Consecutive sentences
+/// are then joined into one chunk as long as it stays within `max_chars`, as synthesizing a
+/// few longer chunks is faster than many short ones.
+///
+/// ```
+/// use voicevox_dyn::text::split_chunks;
+///
+/// assert_eq!(
+///     split_chunks("はい。元気です。今日は、とてもいい天気ですね。", 11),
+///     ["はい。元気です。", "今日は、", "とてもいい天気ですね。"]
+/// );
+/// ```
+pub fn split_chunks(text: &str, max_chars: usize) -> Vec<&str> {
+    let max_chars = max_chars.max(1);
+    let len = |range: &Range<usize>| text[range.clone()].chars().count();
+    let pieces = sentence_ranges(text).into_iter().flat_map(|sentence| {
+        if len(&sentence) <= max_chars {
+            return vec![sentence];
+        }
+        let clauses = split_after(text, sentence, PAUSES)
+            .into_iter()
+            .flat_map(|clause| split_every(text, clause, max_chars));
+        merge(text, clauses, max_chars)
+    });
+    merge(text, pieces, max_chars)
+        .into_iter()
+        .map(|range| &text[range])
+        .collect()
+}
+
+/// Whether `sentence` is a question: it ends with `？` or `?`, or with the particle `か` or
+/// `かい` as in `行きますか。`.
+///
+/// ```
+/// use voicevox_dyn::text::is_question;
+///
+/// assert!(is_question("元気？"));
+/// assert!(is_question("「明日も来ますか。」"));
+/// assert!(!is_question("そうか。"));
+/// assert!(!is_question("元気です。"));
+/// ```
+pub fn is_question(sentence: &str) -> bool {
+    let (content, end) = split_end(sentence.trim());
+    end.contains(['？', '?'])
+        || (content.ends_with('か') || content.ends_with("かい"))
+            && !NOT_QUESTIONS.iter().any(|word| content.ends_with(word))
+}
+
+/// Words ending with `か` that do not make a sentence a question.
+const NOT_QUESTIONS: &[&str] = &[
+    "そうか",
+    "確か",
+    "ばか",
+    "バカ",
+    "まさか",
+    "なんか",
+    "どうか",
+];
+
+/// Ends the questions in `text` that have no question mark, as detected by [`is_question`],
+/// with `？`, which voicevox needs to raise their pitch at the end, see
+/// [`SynthesisOptions::auto_upspeak`](crate::SynthesisOptions::auto_upspeak).
+///
+/// ```
+/// use voicevox_dyn::text::mark_questions;
+///
+/// assert_eq!(mark_questions("晴れです。行きますか。"), "晴れです。行きますか？");
+/// ```
+pub fn mark_questions(text: &str) -> Cow<'_, str> {
+    let mut output = String::new();
+    // Everything before this has been copied to `output`.
+    let mut copied = 0;
+    for sentence in sentence_ranges(text) {
+        let (content, end) = split_end(&text[sentence.clone()]);
+        if !is_question(content) || end.contains(['？', '?']) {
+            continue;
+        }
+        let content_end = sentence.start + content.len();
+        output.push_str(&text[copied..content_end]);
+        output.push('？');
+        output.extend(end.chars().filter(|c| CLOSING_BRACKETS.contains(c)));
+        copied = sentence.end;
+    }
+    if copied == 0 {
+        return Cow::Borrowed(text);
+    }
+    output.push_str(&text[copied..]);
+    Cow::Owned(output)
+}
+
+/// Splits the punctuation and closing brackets off the end of `sentence`.
+fn split_end(sentence: &str) -> (&str, &str) {
+    let content =
+        sentence.trim_end_matches(|c| SENTENCE_ENDS.contains(&c) || CLOSING_BRACKETS.contains(&c));
+    (content, &sentence[content.len()..])
+}
+
+/// Characters within sentences that long sentences are split after.
+const PAUSES: &[char] = &['、', '，', ',', ' ', '　'];
+
+fn sentence_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        if !SENTENCE_ENDS.contains(&c) {
+            continue;
+        }
+        while let Some(&(_, c)) = chars.peek() {
+            if !SENTENCE_ENDS.contains(&c) && !CLOSING_BRACKETS.contains(&c) {
+                break;
+            }
+            chars.next();
+        }
+        let end = chars.peek().map_or(text.len(), |&(i, _)| i);
+        sentences.push(start..end);
+        start = end;
+    }
+    sentences.push(start..text.len());
+    sentences
+        .into_iter()
+        .filter_map(|range| trim(text, range))
+        .collect()
+}
+
+/// Splits `range` of `text` after every one of `separators`.
+fn split_after(text: &str, range: Range<usize>, separators: &[char]) -> Vec<Range<usize>> {
+    let mut pieces = Vec::new();
+    let mut start = range.start;
+    for (i, c) in text[range.clone()].char_indices() {
+        if separators.contains(&c) {
+            let end = range.start + i + c.len_utf8();
+            pieces.push(start..end);
+            start = end;
+        }
+    }
+    pieces.push(start..range.end);
+    pieces
+        .into_iter()
+        .filter_map(|range| trim(text, range))
+        .collect()
+}
+
+/// Splits `range` of `text` into pieces of `max_chars` characters.
+fn split_every(text: &str, range: Range<usize>, max_chars: usize) -> Vec<Range<usize>> {
+    let boundaries: Vec<usize> = text[range.clone()]
+        .char_indices()
+        .map(|(i, _)| range.start + i)
+        .step_by(max_chars)
+        .chain([range.end])
+        .collect();
+    boundaries.windows(2).map(|w| w[0]..w[1]).collect()
+}
+
+/// Joins consecutive ranges of `text` as long as they span at most `max_chars` characters.
+fn merge(
+    text: &str,
+    ranges: impl IntoIterator<Item = Range<usize>>,
+    max_chars: usize,
+) -> Vec<Range<usize>> {
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if text[last.start..range.end].chars().count() <= max_chars => {
+                last.end = range.end;
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Removes the whitespace around `range` of `text`, returning `None` if nothing is left.
+fn trim(text: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let piece = &text[range.clone()];
+    let start = range.start + (piece.len() - piece.trim_start().len());
+    let end = range.end - (piece.len() - piece.trim_end().len());
+    (start < end).then_some(start..end)
+}
+
+/// Replaces words annotated with ruby by their reading, so they are read as intended.
+///
+/// Understands the notations common in web novels and Aozora Bunko:
+/// - `|漢字《かんじ》` or `｜漢字《かんじ》` annotates everything after the bar, up to the next
+///   punctuation or whitespace, after which a bar without a reading is kept as it is,
+/// - `漢字《かんじ》` annotates the kanji before the reading,
+/// - `漢字（かんじ）` and `漢字(かんじ)` annotate the kanji before the parentheses, but only if
+///   the parentheses contain nothing but kana, so that other parentheses are kept.
+///
+/// ```
+/// use voicevox_dyn::text::apply_ruby;
+///
+/// assert_eq!(apply_ruby("|今日《きょう》は晴天（せいてん）です"), "きょうはせいてんです");
+/// assert_eq!(apply_ruby("東京（日本）"), "東京（日本）");
+/// assert_eq!(apply_ruby("A|B。漢字（かんじ）"), "A|B。かんじ");
+/// ```
+pub fn apply_ruby(text: &str) -> Cow<'_, str> {
+    let mut output = String::new();
+    // Everything before this has been copied to `output`.
+    let mut copied = 0;
+    let mut bar = None;
+    for (i, c) in text.char_indices() {
+        if i < copied {
+            continue;
+        }
+        let close = match c {
+            '|' | '｜' => {
+                bar = Some(i);
+                continue;
+            }
+            c if c.is_whitespace() || SENTENCE_ENDS.contains(&c) || matches!(c, '、' | '，') => {
+                bar = None;
+                continue;
+            }
+            '《' => '》',
+            '（' => '）',
+            '(' => ')',
+            _ => continue,
+        };
+        let start = i + c.len_utf8();
+        let Some(len) = text[start..].find(close) else {
+            continue;
+        };
+        let reading = &text[start..start + len];
+        if reading.is_empty() || (c != '《' && !reading.chars().all(is_kana)) {
+            continue;
+        }
+        let ruby_bar = if c == '《' { bar.take() } else { None };
+        let (base, bar_len) = match ruby_bar {
+            Some(bar) => (bar, text[bar..].chars().next().map_or(0, char::len_utf8)),
+            None => {
+                let kanji = text[copied..i]
+                    .chars()
+                    .rev()
+                    .take_while(|&c| is_kanji(c))
+                    .map(char::len_utf8)
+                    .sum::<usize>();
+                (i - kanji, 0)
+            }
+        };
+        if base + bar_len == i {
+            continue;
+        }
+        output.push_str(&text[copied..base]);
+        output.push_str(reading);
+        copied = start + len + close.len_utf8();
+        bar = None;
+    }
+    if copied == 0 {
+        return Cow::Borrowed(text);
+    }
+    output.push_str(&text[copied..]);
+    Cow::Owned(output)
+}
+
+fn is_kanji(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '々' | '〆' | 'ヶ' | '〇')
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{309F}' | '\u{30A0}'..='\u{30FF}')
+}
+
+/// Replaces inline readings such as `{重複|ちょうふく}` with the reading, for one-off
+/// pronunciations that do not belong into a dictionary.
+///
+/// Braces that do not contain exactly one `|` with text on both sides are kept as they are.
+///
+/// ```
+/// use voicevox_dyn::text::apply_inline_readings;
+///
+/// assert_eq!(
+///     apply_inline_readings("{重複|ちょうふく}した{AI|エーアイ}と{これ}"),
+///     "ちょうふくしたエーアイと{これ}"
+/// );
+/// ```
+pub fn apply_inline_readings(text: &str) -> Cow<'_, str> {
+    let mut output = String::new();
+    // Everything before this has been copied to `output`.
+    let mut copied = 0;
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        let offset = text.len() - rest.len() + start;
+        let Some(len) = text[offset + 1..].find(['{', '}']) else {
+            break;
+        };
+        let inner = &text[offset + 1..offset + 1 + len];
+        rest = &text[offset + 1 + len..];
+        if !rest.starts_with('}') {
+            continue;
+        }
+        let Some((surface, reading)) = inner.split_once('|') else {
+            continue;
+        };
+        if surface.trim().is_empty() || reading.trim().is_empty() || reading.contains('|') {
+            continue;
+        }
+        output.push_str(&text[copied..offset]);
+        output.push_str(reading.trim());
+        copied = offset + len + 2;
+    }
+    if copied == 0 {
+        return Cow::Borrowed(text);
+    }
+    output.push_str(&text[copied..]);
+    Cow::Owned(output)
+}
+
+/// Splits `text` at pause tags such as `[pause 500ms]` or `[pause 1.5s]`, see
+/// [`SynthesisOptions::pause_markup`](crate::SynthesisOptions::pause_markup).
+///
+/// Consecutive pauses are added up and text that is only whitespace is skipped.
+/// Returns [`Error::InvalidInput`] if a tag is not closed or has no valid length.
+///
+/// ```
+/// use std::time::Duration;
+/// use voicevox_dyn::{ssml::{Prosody, Segment}, text::parse_pauses};
+///
+/// assert_eq!(
+///     parse_pauses("こんにちは[pause 500ms]元気？")?,
+///     [
+///         Segment::Speech { text: "こんにちは".to_owned(), prosody: Prosody::default() },
+///         Segment::Break(Duration::from_millis(500)),
+///         Segment::Speech { text: "元気？".to_owned(), prosody: Prosody::default() },
+///     ]
+/// );
+/// # Ok::<(), voicevox_dyn::Error>(())
+/// ```
+pub fn parse_pauses(text: &str) -> Result<Vec<Segment>, Error> {
+    let mut segments = Vec::new();
+    let speech = |segments: &mut Vec<Segment>, text: &str| {
+        if !text.trim().is_empty() {
+            segments.push(Segment::Speech {
+                text: text.to_owned(),
+                prosody: Prosody::default(),
+            });
+        }
+    };
+    let mut rest = text;
+    while let Some(start) = find_pause(rest) {
+        let Some(len) = rest[start..].find(']') else {
+            return Err(Error::InvalidInput(format!(
+                "the pause tag at byte {} is not closed with ]",
+                text.len() - rest.len() + start
+            )));
+        };
+        let tag = &rest[start..start + len + 1];
+        let pause = ssml::parse_time(&tag["[pause".len()..len]).ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "{tag} has no valid length, expected e.g. [pause 500ms] or [pause 1s]"
+            ))
+        })?;
+        speech(&mut segments, &rest[..start]);
+        match segments.last_mut() {
+            Some(Segment::Break(previous)) => *previous += pause,
+            _ => segments.push(Segment::Break(pause)),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    speech(&mut segments, rest);
+    Ok(segments)
+}
+
+/// The byte offset of the first `[pause` tag in `text`.
+fn find_pause(text: &str) -> Option<usize> {
+    text.match_indices("[pause").map(|(i, _)| i).find(|&i| {
+        text[i + "[pause".len()..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_whitespace() || c == ']')
+    })
+}
@@ -0,0 +1,472 @@
+//! Rewriting text before synthesis, see [`TextFilter`].
+
+use std::{borrow::Cow, sync::Arc};
+
+/// A step that rewrites text before it is synthesized, e.g. to remove what should not be
+/// read aloud.
+///
+/// Filters are added to [`SynthesisOptions`](crate::SynthesisOptions) with
+/// [`SynthesisOptions::filter`](crate::SynthesisOptions::filter) or combined into a
+/// [`FilterChain`].
+///
+/// ```
+/// use std::borrow::Cow;
+/// use voicevox_dyn::text::TextFilter;
+///
+/// struct Laughter;
+///
+/// impl TextFilter for Laughter {
+///     fn filter<'t>(&self, text: &'t str) -> Cow<'t, str> {
+///         if text.contains("www") {
+///             Cow::Owned(text.replace("www", "わらわら"))
+///         } else {
+///             Cow::Borrowed(text)
+///         }
+///     }
+/// }
+///
+/// assert_eq!(Laughter.filter("面白いwww"), "面白いわらわら");
+/// ```
+pub trait TextFilter: Send + Sync {
+    /// Rewrites `text`, returning it borrowed if nothing had to be changed.
+    fn filter<'t>(&self, text: &'t str) -> Cow<'t, str>;
+}
+
+/// Closures are filters that return the rewritten text, or `None` if nothing had to be changed.
+///
+/// ```
+/// use voicevox_dyn::SynthesisOptions;
+///
+/// let opts = SynthesisOptions::new()
+///     .filter(|text: &str| text.contains("w").then(|| text.replace('w', "わら")));
+/// ```
+impl<F> TextFilter for F
+where
+    F: Fn(&str) -> Option<String> + Send + Sync,
+{
+    fn filter<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        match self(text) {
+            Some(text) => Cow::Owned(text),
+            None => Cow::Borrowed(text),
+        }
+    }
+}
+
+/// Filters applied one after another.
+///
+/// Cloning a chain is cheap, the filters are shared.
+#[derive(Clone, Default)]
+pub struct FilterChain {
+    filters: Vec<Arc<dyn TextFilter>>,
+}
+
+impl FilterChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `filter`, which receives the output of the filters before it.
+    pub fn with(mut self, filter: impl TextFilter + 'static) -> Self {
+        self.push(filter);
+        self
+    }
+
+    /// Same as [`FilterChain::with`] for chains that are not built in one go.
+    pub fn push(&mut self, filter: impl TextFilter + 'static) {
+        self.filters.push(Arc::new(filter));
+    }
+
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+}
+
+impl TextFilter for FilterChain {
+    fn filter<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        self.filters
+            .iter()
+            .fold(Cow::Borrowed(text), |text, filter| match text {
+                Cow::Borrowed(text) => filter.filter(text),
+                Cow::Owned(text) => Cow::Owned(filter.filter(&text).into_owned()),
+            })
+    }
+}
+
+impl std::fmt::Debug for FilterChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FilterChain")
+            .field("len", &self.filters.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Replaces prohibited words (NG words) before they are spoken, e.g. in chat messages read
+/// aloud on a stream.
+///
+/// Longer words are matched first and latin letters are matched regardless of case. With
+/// [`SynthesisOptions::normalizer`](crate::SynthesisOptions::normalizer) enabled the filter
+/// also sees full-width letters as their ASCII form.
+///
+/// ```
+/// use voicevox_dyn::text::{TextFilter, WordFilter};
+///
+/// let filter = WordFilter::new().word("バカ").word_with("NGワード", "");
+/// assert_eq!(filter.filter("バカって言うのはngワードです"), "ピーって言うのはです");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordFilter {
+    /// The words and their replacement if not the default one, longest first.
+    words: Vec<(String, Option<String>)>,
+    replacement: String,
+}
+
+impl Default for WordFilter {
+    fn default() -> Self {
+        Self {
+            words: Vec::new(),
+            replacement: "ピー".to_owned(),
+        }
+    }
+}
+
+impl WordFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces `word` with the default replacement.
+    pub fn word(self, word: impl Into<String>) -> Self {
+        self.insert(word.into(), None)
+    }
+
+    /// Replaces `word` with `replacement`, an empty replacement removes it.
+    pub fn word_with(self, word: impl Into<String>, replacement: impl Into<String>) -> Self {
+        self.insert(word.into(), Some(replacement.into()))
+    }
+
+    /// Replaces every word in `words` with the default replacement, e.g. from a list that is
+    /// maintained elsewhere.
+    pub fn words<S: Into<String>>(self, words: impl IntoIterator<Item = S>) -> Self {
+        words
+            .into_iter()
+            .fold(self, |filter, word| filter.word(word))
+    }
+
+    /// The replacement of words added without one, `ピー` by default.
+    pub fn replacement(mut self, replacement: impl Into<String>) -> Self {
+        self.replacement = replacement.into();
+        self
+    }
+
+    fn insert(mut self, word: String, replacement: Option<String>) -> Self {
+        if word.is_empty() {
+            return self;
+        }
+        self.words.retain(|(w, _)| !w.eq_ignore_ascii_case(&word));
+        let index = self.words.partition_point(|(w, _)| w.len() >= word.len());
+        self.words.insert(index, (word, replacement));
+        self
+    }
+
+    /// The word at the start of `rest`, if any.
+    fn find(&self, rest: &str) -> Option<&(String, Option<String>)> {
+        self.words.iter().find(|(word, _)| {
+            rest.get(..word.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(word))
+        })
+    }
+}
+
+impl TextFilter for WordFilter {
+    fn filter<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        if self.words.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        let mut output = String::new();
+        // Everything before this has been copied to `output`.
+        let mut copied = 0;
+        let mut i = 0;
+        while let Some(c) = text[i..].chars().next() {
+            match self.find(&text[i..]) {
+                Some((word, replacement)) => {
+                    output.push_str(&text[copied..i]);
+                    output.push_str(replacement.as_deref().unwrap_or(&self.replacement));
+                    i += word.len();
+                    copied = i;
+                }
+                None => i += c.len_utf8(),
+            }
+        }
+        if copied == 0 {
+            return Cow::Borrowed(text);
+        }
+        output.push_str(&text[copied..]);
+        Cow::Owned(output)
+    }
+}
+
+/// What [`SymbolFilter`] does with what it finds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Handling {
+    /// Leaves it to voicevox, which usually reads it letter by letter.
+    Keep,
+    /// Removes it.
+    Strip,
+    /// Reads the given text instead, e.g. `リンク` for URLs.
+    Replace(String),
+    /// Reads it in a form that can be spoken: the host of URLs, emails with `アットマーク` and
+    /// `ドット`, hashtags after `ハッシュタグ` and the names of common emoji.
+    SpellOut,
+}
+
+/// Handles the URLs, email addresses, emoji and hashtags that chat messages are full of and
+/// that turn into garbage when read as they are.
+///
+/// By default URLs are read as `リンク`, email addresses as `メールアドレス`, emoji are removed
+/// and hashtags are spelled out.
+///
+/// ```
+/// use voicevox_dyn::text::{Handling, SymbolFilter, TextFilter};
+///
+/// let filter = SymbolFilter::new().emoji(Handling::SpellOut);
+/// assert_eq!(
+///     filter.filter("見てね👍 https://example.com/foo #拡散希望"),
+///     "見てねいいね リンク ハッシュタグ拡散希望"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolFilter {
+    urls: Handling,
+    emails: Handling,
+    emoji: Handling,
+    hashtags: Handling,
+}
+
+impl Default for SymbolFilter {
+    fn default() -> Self {
+        Self {
+            urls: Handling::Replace("リンク".to_owned()),
+            emails: Handling::Replace("メールアドレス".to_owned()),
+            emoji: Handling::Strip,
+            hashtags: Handling::SpellOut,
+        }
+    }
+}
+
+/// The Japanese names of common emoji, for [`Handling::SpellOut`].
+const EMOJI_NAMES: &[(char, &str)] = &[
+    ('😀', "にっこり"),
+    ('😁', "にやり"),
+    ('😂', "うれし泣き"),
+    ('🤣', "大笑い"),
+    ('😊', "にこにこ"),
+    ('😍', "目がハート"),
+    ('😘', "投げキッス"),
+    ('😅', "苦笑い"),
+    ('😉', "ウインク"),
+    ('🤔', "考え中"),
+    ('😢', "涙"),
+    ('😭', "号泣"),
+    ('😡', "怒り"),
+    ('😱', "叫び"),
+    ('😴', "居眠り"),
+    ('🥺', "うるうる"),
+    ('🙏', "お願い"),
+    ('👍', "いいね"),
+    ('👏', "拍手"),
+    ('👀', "目"),
+    ('💪', "力こぶ"),
+    ('❤', "ハート"),
+    ('💕', "ハート"),
+    ('💔', "失恋"),
+    ('✨', "キラキラ"),
+    ('🔥', "炎"),
+    ('🎉', "クラッカー"),
+    ('🎂', "ケーキ"),
+    ('⭐', "星"),
+    ('🌸', "桜"),
+    ('💦', "汗"),
+    ('💯', "百点"),
+    ('🍣', "寿司"),
+    ('🍺', "ビール"),
+    ('☀', "晴れ"),
+    ('☔', "雨"),
+    ('🐱', "猫"),
+    ('🐶', "犬"),
+];
+
+impl SymbolFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How URLs starting with `http://`, `https://` or `www.` are read.
+    pub fn urls(mut self, handling: Handling) -> Self {
+        self.urls = handling;
+        self
+    }
+
+    /// How email addresses are read.
+    pub fn emails(mut self, handling: Handling) -> Self {
+        self.emails = handling;
+        self
+    }
+
+    /// How emoji are read, consecutive emoji are handled together.
+    pub fn emoji(mut self, handling: Handling) -> Self {
+        self.emoji = handling;
+        self
+    }
+
+    /// How hashtags such as `#拡散希望` are read.
+    pub fn hashtags(mut self, handling: Handling) -> Self {
+        self.hashtags = handling;
+        self
+    }
+
+    /// Finds what is handled at the start of `rest`, returning its length and handling.
+    fn find<'a>(&'a self, rest: &str, after_word: bool) -> Option<(usize, &'a Handling, Kind)> {
+        if !after_word {
+            if let Some(len) = url_len(rest) {
+                return Some((len, &self.urls, Kind::Url));
+            }
+            if let Some(len) = email_len(rest) {
+                return Some((len, &self.emails, Kind::Email));
+            }
+            if let Some(len) = hashtag_len(rest) {
+                return Some((len, &self.hashtags, Kind::Hashtag));
+            }
+        }
+        let len = rest
+            .char_indices()
+            .find(|&(_, c)| !is_emoji(c))
+            .map_or(rest.len(), |(i, _)| i);
+        (len > 0).then_some((len, &self.emoji, Kind::Emoji))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Url,
+    Email,
+    Hashtag,
+    Emoji,
+}
+
+impl TextFilter for SymbolFilter {
+    fn filter<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let mut output = String::new();
+        // Everything before this has been copied to `output`.
+        let mut copied = 0;
+        let mut i = 0;
+        while let Some(c) = text[i..].chars().next() {
+            // URLs and the like are often written right after Japanese text, but not after
+            // latin text they could be a part of.
+            let after_word = text[..i].chars().next_back().is_some_and(|c| {
+                c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '%' | '+' | '-' | '/' | '@')
+            });
+            let Some((len, handling, kind)) = self.find(&text[i..], after_word) else {
+                i += c.len_utf8();
+                continue;
+            };
+            let found = &text[i..i + len];
+            let replacement = match handling {
+                Handling::Keep => None,
+                Handling::Strip => Some(String::new()),
+                Handling::Replace(replacement) => Some(replacement.clone()),
+                Handling::SpellOut => Some(spell_out(found, kind)),
+            };
+            if let Some(replacement) = replacement {
+                output.push_str(&text[copied..i]);
+                output.push_str(&replacement);
+                copied = i + len;
+            }
+            i += len;
+        }
+        if copied == 0 {
+            return Cow::Borrowed(text);
+        }
+        output.push_str(&text[copied..]);
+        Cow::Owned(output)
+    }
+}
+
+fn spell_out(found: &str, kind: Kind) -> String {
+    match kind {
+        Kind::Url => {
+            let host = found
+                .split_once("://")
+                .map_or(found, |(_, rest)| rest)
+                .trim_start_matches("www.");
+            let host = host.split(['/', '?', '#', ':']).next().unwrap_or(host);
+            host.replace('.', "ドット")
+        }
+        Kind::Email => found.replace('@', "アットマーク").replace('.', "ドット"),
+        Kind::Hashtag => format!(
+            "ハッシュタグ{}",
+            &found[found.chars().next().map_or(0, char::len_utf8)..]
+        ),
+        Kind::Emoji => {
+            let mut names: Vec<&str> = found
+                .chars()
+                .filter_map(|c| EMOJI_NAMES.iter().find(|(e, _)| *e == c).map(|(_, n)| *n))
+                .collect();
+            names.dedup();
+            names.join("、")
+        }
+    }
+}
+
+fn url_len(text: &str) -> Option<usize> {
+    if !["http://", "https://", "www."]
+        .iter()
+        .any(|prefix| text.starts_with(prefix))
+    {
+        return None;
+    }
+    let len = text
+        .find(|c: char| !c.is_ascii_graphic() || matches!(c, '"' | '<' | '>'))
+        .unwrap_or(text.len());
+    // Punctuation right after a URL usually belongs to the sentence.
+    Some(
+        text[..len]
+            .trim_end_matches(['.', ',', '!', '?', ')', ']', '\''])
+            .len(),
+    )
+}
+
+fn email_len(text: &str) -> Option<usize> {
+    let local = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '%' | '+' | '-')))
+        .unwrap_or(text.len());
+    let domain = text[local..].strip_prefix('@')?;
+    let domain_len = domain
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-')))
+        .unwrap_or(domain.len());
+    let domain = domain[..domain_len].trim_end_matches(['.', '-']);
+    (local > 0 && domain.contains('.')).then_some(local + 1 + domain.len())
+}
+
+fn hashtag_len(text: &str) -> Option<usize> {
+    let tag = text.strip_prefix(['#', '＃'])?;
+    let len = tag
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == 'ー'))
+        .unwrap_or(tag.len());
+    // `#1` is a number rather than a hashtag.
+    let is_tag = len > 0 && !tag[..len].chars().all(|c| c.is_ascii_digit());
+    is_tag.then_some(text.len() - tag.len() + len)
+}
+
+/// Whether `c` is part of an emoji, including the joiners and modifiers between them.
+fn is_emoji(c: char) -> bool {
+    matches!(c,
+        '\u{1F000}'..='\u{1FAFF}'
+        | '\u{2600}'..='\u{27BF}'
+        | '\u{2B50}' | '\u{2B55}'
+        | '\u{200D}' | '\u{FE0F}' | '\u{20E3}'
+        | '\u{E0020}'..='\u{E007F}')
+}
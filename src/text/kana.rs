@@ -0,0 +1,124 @@
+//! Checking AquesTalk style kana before it is passed to voicevox, see [`lint_kana`].
+
+/// A problem with AquesTalk style kana found by [`lint_kana`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KanaIssue {
+    /// The position of the problem in characters, starting at 0.
+    pub position: usize,
+    pub reason: String,
+}
+
+impl std::fmt::Display for KanaIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at position {}", self.reason, self.position)
+    }
+}
+
+/// Small kana that form a mora with the kana before them.
+const SMALL_KANA: &[char] = &['ァ', 'ィ', 'ゥ', 'ェ', 'ォ', 'ャ', 'ュ', 'ョ', 'ヮ'];
+/// Kana that are moras on their own and can not be combined with small kana.
+const SPECIAL_MORAS: &[char] = &['ッ', 'ン', 'ー'];
+
+/// Checks AquesTalk style kana as passed with [`TtsOptions::kana`](crate::TtsOptions::kana),
+/// returning every problem with its position.
+///
+/// Voicevox only reports that it could not parse the kana, this tells what is wrong and where:
+/// characters other than katakana and `' / 、 _ ？`, accent phrases without exactly one accent
+/// mark `'`, empty accent phrases, `_` that is not followed by a mora, `？` in the middle of an
+/// accent phrase and small kana that do not follow a kana they can be combined with.
+///
+/// ```
+/// use voicevox_dyn::text::lint_kana;
+///
+/// assert!(lint_kana("コンニチワ'/ゲ'ンキ？").is_empty());
+///
+/// let issues = lint_kana("コンニチワ/ゲ'ンキ");
+/// assert_eq!(issues[0].position, 5);
+/// assert_eq!(issues[0].reason, "the accent phrase has no accent mark '");
+/// ```
+pub fn lint_kana(kana: &str) -> Vec<KanaIssue> {
+    let mut issues = Vec::new();
+    let mut issue = |position: usize, reason: &str| {
+        issues.push(KanaIssue {
+            position,
+            reason: reason.to_owned(),
+        })
+    };
+    if kana.is_empty() {
+        issue(0, "the kana is empty");
+    }
+    let chars: Vec<char> = kana.chars().collect();
+    let mut moras = 0;
+    let mut accent = false;
+    let mut question = None;
+    for (position, &c) in chars.iter().enumerate() {
+        let previous = position.checked_sub(1).map(|i| chars[i]);
+        if !matches!(c, '/' | '、') {
+            if let Some(question) = question.take() {
+                issue(question, "？ has to end the accent phrase");
+            }
+        }
+        match c {
+            _ if SMALL_KANA.contains(&c) => {
+                if !previous.is_some_and(|p| {
+                    is_katakana(p) && !SMALL_KANA.contains(&p) && !SPECIAL_MORAS.contains(&p)
+                }) {
+                    issue(
+                        position,
+                        "small kana have to follow a kana they can be combined with",
+                    );
+                }
+            }
+            'ー' if moras == 0 => {
+                issue(position, "ー has to follow a mora");
+                moras += 1;
+            }
+            _ if is_katakana(c) => moras += 1,
+            '\'' if moras == 0 => issue(position, "the accent mark ' has to follow a mora"),
+            '\'' if accent => issue(position, "an accent phrase can only have one accent mark '"),
+            '\'' => accent = true,
+            '_' => {
+                if !chars.get(position + 1).is_some_and(|&next| {
+                    is_katakana(next) && !SMALL_KANA.contains(&next) && next != 'ー'
+                }) {
+                    issue(
+                        position,
+                        "_ has to be followed by the mora it makes unvoiced",
+                    );
+                }
+            }
+            '？' if moras == 0 => issue(position, "？ has to follow a mora"),
+            '？' => question = Some(position),
+            '/' | '、' => {
+                end_phrase(position, moras, accent, &mut issue);
+                moras = 0;
+                accent = false;
+                question = None;
+            }
+            c => issue(
+                position,
+                &format!(
+                    "'{c}' is not valid AquesTalk style kana, only katakana and the symbols \
+                     ' / 、 _ ？ are allowed"
+                ),
+            ),
+        }
+    }
+    if !kana.is_empty() {
+        end_phrase(chars.len(), moras, accent, &mut issue);
+    }
+    issues
+}
+
+/// Checks the accent phrase that ends at `position`.
+fn end_phrase(position: usize, moras: usize, accent: bool, issue: &mut impl FnMut(usize, &str)) {
+    if moras == 0 {
+        issue(position, "the accent phrase is empty");
+    } else if !accent {
+        issue(position, "the accent phrase has no accent mark '");
+    }
+}
+
+fn is_katakana(c: char) -> bool {
+    matches!(c, 'ァ'..='ヴ' | 'ー')
+}
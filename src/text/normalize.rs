@@ -0,0 +1,78 @@
+//! Unicode normalization before synthesis, see [`Normalizer`].
+
+use std::borrow::Cow;
+
+use unicode_normalization::{is_nfkc_quick, IsNormalized, UnicodeNormalization};
+
+use super::TextFilter;
+
+/// Characters that NFKC would replace with something that is read differently.
+const DEFAULT_EXCEPTIONS: &[char] = &['～', '…', '‥'];
+
+/// Normalizes text to NFKC, so that full-width and half-width letters and digits, half-width
+/// katakana and variants such as `①` or `㍻` are read the same as their common form.
+///
+/// Characters added with [`Normalizer::except`] are kept as they are. By default these are
+/// `～`, which NFKC turns into `~`, and `…` and `‥`, which it turns into dots.
+///
+/// ```
+/// use voicevox_dyn::text::{Normalizer, TextFilter};
+///
+/// let normalizer = Normalizer::new();
+/// assert_eq!(normalizer.filter("ＶＯＩＣＥＶＯＸ１２３ｶﾞ～"), "VOICEVOX123ガ～");
+/// assert_eq!(normalizer.filter("こんにちは"), "こんにちは");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Normalizer {
+    exceptions: Vec<char>,
+}
+
+impl Default for Normalizer {
+    fn default() -> Self {
+        Self {
+            exceptions: DEFAULT_EXCEPTIONS.to_vec(),
+        }
+    }
+}
+
+impl Normalizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A normalizer without the default exceptions.
+    pub fn without_exceptions() -> Self {
+        Self {
+            exceptions: Vec::new(),
+        }
+    }
+
+    /// Keeps `c` as it is instead of normalizing it.
+    pub fn except(mut self, c: char) -> Self {
+        if !self.exceptions.contains(&c) {
+            self.exceptions.push(c);
+        }
+        self
+    }
+}
+
+impl TextFilter for Normalizer {
+    fn filter<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        if is_nfkc_quick(text.chars()) == IsNormalized::Yes {
+            return Cow::Borrowed(text);
+        }
+        let mut output = String::with_capacity(text.len());
+        let mut start = 0;
+        for (i, exception) in text.match_indices(self.exceptions.as_slice()) {
+            output.extend(text[start..i].nfkc());
+            output.push_str(exception);
+            start = i + exception.len();
+        }
+        output.extend(text[start..].nfkc());
+        if output == text {
+            Cow::Borrowed(text)
+        } else {
+            Cow::Owned(output)
+        }
+    }
+}
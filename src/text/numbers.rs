@@ -0,0 +1,653 @@
+//! Reading numbers, dates, times, counters and units, see [`normalize_numbers`].
+
+use std::borrow::Cow;
+
+/// How the reading of a number changes in front of a counter.
+#[derive(Clone, Copy)]
+enum Rule {
+    /// The number is read as usual.
+    Plain,
+    /// Numbers ending in 1, 6, 8, 10 or 100 are shortened, as in `いっこ`, h-row counters turn
+    /// into p-row. With `voiced`, the counter is voiced after `ん` other than 4, as in `さんぼん`.
+    Geminate { voiced: bool },
+    /// Like [`Rule::Geminate`] but the counter turns into p-row after every `ん`, as in `さんぷん`.
+    Minutes,
+    /// 4, 7 and 9 are read `よ`, `しち` and `く`, as in `よじ`.
+    Hour,
+    /// 4 and 9 are read `よ` and `きゅう`, as in `よねん`.
+    Year,
+    /// 4, 7 and 9 are read `し`, `しち` and `く`, as in `しがつ`.
+    Month,
+    /// Days of the month and counted days, as in `みっか`.
+    Day,
+    /// People, as in `ひとり`.
+    Person,
+    /// Counting things with native numbers, as in `ふたつ`.
+    Native,
+}
+
+/// Counters and units, longer ones first so that e.g. `時間` is found before `時`.
+const COUNTERS: &[(&str, &str, Rule)] = &[
+    ("兆", "ちょう", Rule::Geminate { voiced: false }),
+    ("億", "おく", Rule::Plain),
+    ("万", "まん", Rule::Plain),
+    ("ヶ月", "かげつ", Rule::Geminate { voiced: false }),
+    ("か月", "かげつ", Rule::Geminate { voiced: false }),
+    ("カ月", "かげつ", Rule::Geminate { voiced: false }),
+    ("ヵ月", "かげつ", Rule::Geminate { voiced: false }),
+    ("時間", "じかん", Rule::Hour),
+    ("週間", "しゅうかん", Rule::Geminate { voiced: false }),
+    (
+        "km/h",
+        "キロメートルまいじ",
+        Rule::Geminate { voiced: false },
+    ),
+    ("kHz", "キロヘルツ", Rule::Geminate { voiced: false }),
+    ("km", "キロメートル", Rule::Geminate { voiced: false }),
+    ("kg", "キログラム", Rule::Geminate { voiced: false }),
+    ("cm", "センチメートル", Rule::Geminate { voiced: false }),
+    ("mm", "ミリメートル", Rule::Plain),
+    ("mg", "ミリグラム", Rule::Plain),
+    ("mL", "ミリリットル", Rule::Plain),
+    ("ml", "ミリリットル", Rule::Plain),
+    ("ms", "ミリびょう", Rule::Plain),
+    ("KB", "キロバイト", Rule::Geminate { voiced: false }),
+    ("MB", "メガバイト", Rule::Plain),
+    ("GB", "ギガバイト", Rule::Plain),
+    ("TB", "テラバイト", Rule::Plain),
+    ("Hz", "ヘルツ", Rule::Plain),
+    ("dB", "デシベル", Rule::Plain),
+    ("°C", "ど", Rule::Plain),
+    ("m", "メートル", Rule::Plain),
+    ("g", "グラム", Rule::Plain),
+    ("L", "リットル", Rule::Plain),
+    ("%", "パーセント", Rule::Geminate { voiced: false }),
+    ("％", "パーセント", Rule::Geminate { voiced: false }),
+    ("℃", "ど", Rule::Plain),
+    ("本", "ほん", Rule::Geminate { voiced: true }),
+    ("匹", "ひき", Rule::Geminate { voiced: true }),
+    ("杯", "はい", Rule::Geminate { voiced: true }),
+    ("階", "かい", Rule::Geminate { voiced: true }),
+    ("軒", "けん", Rule::Geminate { voiced: true }),
+    ("個", "こ", Rule::Geminate { voiced: false }),
+    ("回", "かい", Rule::Geminate { voiced: false }),
+    ("件", "けん", Rule::Geminate { voiced: false }),
+    ("冊", "さつ", Rule::Geminate { voiced: false }),
+    ("歳", "さい", Rule::Geminate { voiced: false }),
+    ("才", "さい", Rule::Geminate { voiced: false }),
+    ("週", "しゅう", Rule::Geminate { voiced: false }),
+    ("分", "ふん", Rule::Minutes),
+    ("時", "じ", Rule::Hour),
+    ("年", "ねん", Rule::Year),
+    ("円", "えん", Rule::Year),
+    ("月", "がつ", Rule::Month),
+    ("日", "にち", Rule::Day),
+    ("人", "にん", Rule::Person),
+    ("つ", "", Rule::Native),
+    ("秒", "びょう", Rule::Plain),
+    ("度", "ど", Rule::Plain),
+    ("番", "ばん", Rule::Plain),
+    ("枚", "まい", Rule::Plain),
+    ("台", "だい", Rule::Plain),
+];
+
+/// Currency symbols written in front of the amount.
+const CURRENCIES: &[(char, &str)] = &[
+    ('¥', "えん"),
+    ('￥', "えん"),
+    ('$', "ドル"),
+    ('€', "ユーロ"),
+];
+
+const DIGITS: [&str; 10] = [
+    "ぜろ",
+    "いち",
+    "に",
+    "さん",
+    "よん",
+    "ご",
+    "ろく",
+    "なな",
+    "はち",
+    "きゅう",
+];
+
+/// Writes numbers, dates, times and numbers with counters or units as they are read, so that
+/// voicevox does not have to guess.
+///
+/// - `2024/05/03` is read as a date and `12:30` as a time,
+/// - counters and units change the reading of the number in front of them, as in `3本` or `6%`,
+/// - `1,000` is one thousand and `3.5` is read with `てん`,
+/// - numbers starting with 0, such as phone numbers, are read digit by digit.
+///
+/// ```
+/// use voicevox_dyn::text::normalize_numbers;
+///
+/// assert_eq!(
+///     normalize_numbers("2024/05/03に3.5kmを12分で"),
+///     "にせんにじゅうよねんごがつみっかにさんてんごキロメートルをじゅうにふんで"
+/// );
+/// ```
+///
+/// Full-width digits and separators, as typed with a Japanese input method, are read like
+/// ASCII ones. Groups without three digits are not thousands separators and numbers with more
+/// than one point, such as versions, are kept as they are.
+///
+/// ```
+/// # use voicevox_dyn::text::normalize_numbers;
+/// assert_eq!(normalize_numbers("３．５km"), "さんてんごキロメートル");
+/// assert_eq!(normalize_numbers("3．5km"), "さんてんごキロメートル");
+/// assert_eq!(normalize_numbers("１２：３０"), "じゅうにじさんじゅっぷん");
+/// assert_eq!(normalize_numbers("５０％"), "ごじゅっパーセント");
+/// assert_eq!(normalize_numbers("1,000円"), "せんえん");
+/// assert_eq!(normalize_numbers("1，000円"), "せんえん");
+/// assert_eq!(normalize_numbers("１，０００"), "せん");
+/// assert_eq!(
+///     normalize_numbers("1,234,567"),
+///     "ひゃくにじゅうさんまんよんせんごひゃくろくじゅうなな"
+/// );
+/// assert_eq!(normalize_numbers("¥1,500"), "せんごひゃくえん");
+/// assert_eq!(normalize_numbers("1,23"), "いち,にじゅうさん");
+/// assert_eq!(normalize_numbers("0.5"), "れいてんご");
+/// assert_eq!(normalize_numbers("v1.2.3"), "v1.2.3");
+/// assert_eq!(normalize_numbers("1.2.3"), "1.2.3");
+/// ```
+///
+/// Counters change the reading of the number and some numbers have readings of their own:
+///
+/// ```
+/// # use voicevox_dyn::text::normalize_numbers;
+/// assert_eq!(normalize_numbers("1個"), "いっこ");
+/// assert_eq!(normalize_numbers("6個"), "ろっこ");
+/// assert_eq!(normalize_numbers("３本"), "さんぼん");
+/// assert_eq!(normalize_numbers("1本"), "いっぽん");
+/// assert_eq!(normalize_numbers("3分"), "さんぷん");
+/// assert_eq!(normalize_numbers("4時"), "よじ");
+/// assert_eq!(normalize_numbers("4月"), "しがつ");
+/// assert_eq!(normalize_numbers("9時間"), "くじかん");
+/// assert_eq!(normalize_numbers("1人"), "ひとり");
+/// assert_eq!(normalize_numbers("2人"), "ふたり");
+/// assert_eq!(normalize_numbers("3つ"), "みっつ");
+/// assert_eq!(normalize_numbers("4月1日"), "しがつついたち");
+/// assert_eq!(normalize_numbers("20日"), "はつか");
+/// assert_eq!(normalize_numbers("5min"), "ごmin");
+/// assert_eq!(normalize_numbers("-3度"), "まいなすさんど");
+/// assert_eq!(normalize_numbers("090"), "ぜろきゅうぜろ");
+/// ```
+pub fn normalize_numbers(text: &str) -> Cow<'_, str> {
+    let mut output = String::new();
+    // Everything before this has been copied to `output`.
+    let mut copied = 0;
+    // Where the last month ended, `1日` right after a month is `ついたち`.
+    let mut month_end = None;
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        let read = if i > 0 && is_continuation(&text[..i]) {
+            None
+        } else {
+            read_date(&text[i..])
+                .or_else(|| read_time(&text[i..]))
+                .or_else(|| read_currency(&text[i..]))
+                .or_else(|| read_counted(&text[i..], month_end == Some(i)))
+        };
+        match read {
+            Some((len, reading, month)) => {
+                output.push_str(&text[copied..i]);
+                output.push_str(&reading);
+                i += len;
+                copied = i;
+                month_end = month.then_some(i);
+            }
+            None => i += c.len_utf8(),
+        }
+    }
+    if copied == 0 {
+        return Cow::Borrowed(text);
+    }
+    output.push_str(&text[copied..]);
+    Cow::Owned(output)
+}
+
+/// Whether a number starting after `before` is part of something else, such as `v1.2.3`.
+fn is_continuation(before: &str) -> bool {
+    before
+        .chars()
+        .next_back()
+        .is_some_and(|c| digit(c).is_some() || c.is_ascii_alphabetic() || matches!(c, '.' | '_'))
+}
+
+fn digit(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' => c.to_digit(10),
+        '０'..='９' => Some(c as u32 - '０' as u32),
+        _ => None,
+    }
+}
+
+/// The leading digits of `text` and their length in bytes.
+fn digits(text: &str) -> (String, usize) {
+    let mut digits = String::new();
+    let mut len = 0;
+    for c in text.chars() {
+        let Some(d) = digit(c) else { break };
+        digits.push(char::from_digit(d, 10).unwrap_or('0'));
+        len += c.len_utf8();
+    }
+    (digits, len)
+}
+
+/// Parses the integer at the start of `text` with at most `max_digits` digits.
+fn small_integer(text: &str, max_digits: usize) -> Option<(u64, usize)> {
+    let (digits, len) = digits(text);
+    if digits.is_empty() || digits.len() > max_digits {
+        return None;
+    }
+    Some((digits.parse().ok()?, len))
+}
+
+/// `2024/05/03`, `2024-5-3` or `2024.05.03`.
+fn read_date(text: &str) -> Option<(usize, String, bool)> {
+    let (year, mut len) = digits(text);
+    if year.len() != 4 {
+        return None;
+    }
+    let separator = text[len..]
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '/' | '-' | '.'))?;
+    len += 1;
+    let (month, month_len) = small_integer(&text[len..], 2)?;
+    len += month_len;
+    text[len..].strip_prefix(separator)?;
+    len += 1;
+    let (day, day_len) = small_integer(&text[len..], 2)?;
+    len += day_len;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || digit_follows(&text[len..]) {
+        return None;
+    }
+    let reading = [
+        counted(&integer(year.parse().ok()?), "ねん", Rule::Year),
+        counted(&integer(month), "がつ", Rule::Month),
+        day_of_month(day),
+    ]
+    .concat();
+    Some((len, reading, false))
+}
+
+/// `12:30` or `12:30:15`.
+fn read_time(text: &str) -> Option<(usize, String, bool)> {
+    let (hour, mut len) = small_integer(text, 2)?;
+    let colon = text[len..]
+        .chars()
+        .next()
+        .filter(|c| matches!(c, ':' | '：'))?;
+    let mut parts = Vec::new();
+    for _ in 0..2 {
+        let Some(rest) = text[len..].strip_prefix(colon) else {
+            break;
+        };
+        let (value, value_len) = digits(rest);
+        if value.len() != 2 {
+            break;
+        }
+        parts.push(value.parse::<u64>().ok()?);
+        len += colon.len_utf8() + value_len;
+    }
+    if parts.is_empty()
+        || hour > 48
+        || parts.iter().any(|&v| v >= 60)
+        || digit_follows(&text[len..])
+    {
+        return None;
+    }
+    let mut reading = counted(&integer(hour), "じ", Rule::Hour);
+    if parts[0] > 0 {
+        reading += &counted(&integer(parts[0]), "ふん", Rule::Minutes);
+    }
+    if let Some(&seconds) = parts.get(1).filter(|&&s| s > 0) {
+        reading += &counted(&integer(seconds), "びょう", Rule::Plain);
+    }
+    Some((len, reading, false))
+}
+
+/// `¥1,000` or `$5`.
+fn read_currency(text: &str) -> Option<(usize, String, bool)> {
+    let symbol = text.chars().next()?;
+    let &(_, unit) = CURRENCIES.iter().find(|(c, _)| *c == symbol)?;
+    let (len, number) = read_number(&text[symbol.len_utf8()..])?;
+    let rule = if unit == "えん" {
+        Rule::Year
+    } else {
+        Rule::Plain
+    };
+    let reading = match number {
+        Number::Integer(reading) => counted(&reading, unit, rule),
+        Number::Other(reading) => reading + unit,
+    };
+    Some((symbol.len_utf8() + len, reading, false))
+}
+
+/// A number, optionally negative and followed by a counter or unit.
+fn read_counted(text: &str, after_month: bool) -> Option<(usize, String, bool)> {
+    let (sign_len, sign) = match text.chars().next()? {
+        c @ ('-' | '−') if text[c.len_utf8()..].starts_with(|c| digit(c).is_some()) => {
+            (c.len_utf8(), "まいなす")
+        }
+        _ => (0, ""),
+    };
+    let (len, number) = read_number(&text[sign_len..])?;
+    let mut len = sign_len + len;
+    let rest = &text[len..];
+    let counter = COUNTERS.iter().find(|(suffix, ..)| {
+        rest.starts_with(suffix)
+            // `5m` but not `5min`.
+            && !(suffix.is_ascii()
+                && rest[suffix.len()..].starts_with(|c: char| c.is_ascii_alphabetic()))
+    });
+    let mut month = false;
+    let reading = match (number, counter) {
+        (Number::Integer(reading), Some(&(suffix, counter, rule))) => {
+            len += suffix.len();
+            month = suffix == "月";
+            match rule {
+                Rule::Day if reading == "いち" && !after_month => "いちにち".to_owned(),
+                Rule::Day => match reading_value(&reading) {
+                    Some(day) => day_of_month(day),
+                    None => reading + counter,
+                },
+                _ => counted(&reading, counter, rule),
+            }
+        }
+        (Number::Other(reading), Some(&(suffix, counter, _))) if !counter.is_empty() => {
+            len += suffix.len();
+            reading + counter
+        }
+        (Number::Integer(reading) | Number::Other(reading), _) => reading,
+    };
+    Some((len, format!("{sign}{reading}"), month))
+}
+
+enum Number {
+    /// The reading of an integer that counters can change.
+    Integer(String),
+    /// A decimal or a number read digit by digit.
+    Other(String),
+}
+
+/// Reads the number at the start of `text`, with thousands separators and decimals.
+fn read_number(text: &str) -> Option<(usize, Number)> {
+    let (mut int, mut len) = digits(text);
+    if int.is_empty() {
+        return None;
+    }
+    // Thousands separators, only if every group has three digits.
+    if int.len() <= 3 && !int.starts_with('0') {
+        let mut grouped = int.clone();
+        let mut grouped_len = len;
+        while let Some(sep) = text[grouped_len..]
+            .chars()
+            .next()
+            .filter(|c| matches!(c, ',' | '，'))
+        {
+            let (group, group_len) = digits(&text[grouped_len + sep.len_utf8()..]);
+            if group.len() != 3 {
+                break;
+            }
+            grouped += &group;
+            grouped_len += sep.len_utf8() + group_len;
+        }
+        (int, len) = (grouped, grouped_len);
+    }
+    let point = text[len..]
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '.' | '．'))
+        .map(char::len_utf8);
+    let fraction = point
+        .map(|point| (point, digits(&text[len + point..])))
+        .filter(|(_, (fraction, _))| !fraction.is_empty());
+    if let Some((point, (fraction, fraction_len))) = fraction {
+        // `1.2.3` is not a number.
+        if text[len + point + fraction_len..].starts_with(['.', '．']) {
+            return None;
+        }
+        let int = match int.as_str() {
+            "0" => "れい".to_owned(),
+            _ => spelled(&int),
+        };
+        let fraction: String = fraction
+            .bytes()
+            .map(|d| DIGITS[(d - b'0') as usize])
+            .collect();
+        return Some((
+            len + point + fraction_len,
+            Number::Other(format!("{int}てん{fraction}")),
+        ));
+    }
+    let number = match int.len() {
+        1 => Number::Integer(DIGITS[(int.as_bytes()[0] - b'0') as usize].to_owned()),
+        2..=16 if !int.starts_with('0') => Number::Integer(integer(int.parse().ok()?)),
+        _ => Number::Other(int.bytes().map(|d| DIGITS[(d - b'0') as usize]).collect()),
+    };
+    Some((len, number))
+}
+
+/// Reads `digits` as an integer, or digit by digit if it starts with 0 or is very long.
+fn spelled(digits: &str) -> String {
+    match digits.parse() {
+        Ok(value) if digits.len() <= 16 && !(digits.len() > 1 && digits.starts_with('0')) => {
+            integer(value)
+        }
+        _ => digits
+            .bytes()
+            .map(|d| DIGITS[(d - b'0') as usize])
+            .collect(),
+    }
+}
+
+fn digit_follows(text: &str) -> bool {
+    text.starts_with(|c| digit(c).is_some())
+}
+
+/// The reading of `value`, which has to be below 10^16.
+fn integer(value: u64) -> String {
+    if value == 0 {
+        return DIGITS[0].to_owned();
+    }
+    let mut reading = String::new();
+    for (i, unit) in ["ちょう", "おく", "まん", ""].into_iter().enumerate() {
+        let group = value / 10u64.pow(4 * (3 - i as u32)) % 10000;
+        if group == 0 {
+            continue;
+        }
+        let mut group_reading = below_10000(group, !unit.is_empty());
+        if unit == "ちょう" {
+            geminate(&mut group_reading);
+        }
+        reading += &group_reading;
+        reading += unit;
+    }
+    reading
+}
+
+/// The reading of `value` below 10000, `higher` if a larger unit like `まん` follows.
+fn below_10000(value: u64, higher: bool) -> String {
+    let [thousands, hundreds, tens, ones] =
+        [1000, 100, 10, 1].map(|place| (value / place % 10) as usize);
+    let mut reading = String::new();
+    match thousands {
+        0 => {}
+        1 if higher => reading += "いっせん",
+        1 => reading += "せん",
+        3 => reading += "さんぜん",
+        8 => reading += "はっせん",
+        d => reading += &format!("{}せん", DIGITS[d]),
+    }
+    match hundreds {
+        0 => {}
+        1 => reading += "ひゃく",
+        3 => reading += "さんびゃく",
+        6 => reading += "ろっぴゃく",
+        8 => reading += "はっぴゃく",
+        d => reading += &format!("{}ひゃく", DIGITS[d]),
+    }
+    if tens > 1 {
+        reading += DIGITS[tens];
+    }
+    if tens > 0 {
+        reading += "じゅう";
+    }
+    if ones > 0 {
+        reading += DIGITS[ones];
+    }
+    reading
+}
+
+/// Parses a reading created by [`integer`] back into its value, if it is at most 31.
+fn reading_value(reading: &str) -> Option<u64> {
+    (1..=31).find(|&day| integer(day) == reading)
+}
+
+/// Shortens the last word of `reading` before a counter, returning whether it was shortened.
+fn geminate(reading: &mut String) -> bool {
+    for (long, short) in [
+        ("いち", "いっ"),
+        ("ろく", "ろっ"),
+        ("はち", "はっ"),
+        ("じゅう", "じゅっ"),
+        ("ひゃく", "ひゃっ"),
+    ] {
+        if let Some(stem) = reading.strip_suffix(long) {
+            *reading = format!("{stem}{short}");
+            return true;
+        }
+    }
+    false
+}
+
+/// Replaces the ending `from` of `reading` with `to`.
+fn replace_suffix(reading: &mut String, from: &str, to: &str) {
+    if let Some(stem) = reading.strip_suffix(from) {
+        *reading = format!("{stem}{to}");
+    }
+}
+
+/// Changes the first kana of `counter` into the p-row, or the voiced row if `!plosive`.
+fn change_row(counter: &str, plosive: bool) -> String {
+    let mut chars = counter.chars();
+    let Some(first) = chars.next() else {
+        return String::new();
+    };
+    let changed = match (first, plosive) {
+        ('は', true) => 'ぱ',
+        ('ひ', true) => 'ぴ',
+        ('ふ', true) => 'ぷ',
+        ('へ', true) => 'ぺ',
+        ('ほ', true) => 'ぽ',
+        ('は', false) => 'ば',
+        ('ひ', false) => 'び',
+        ('ふ', false) => 'ぶ',
+        ('へ', false) => 'べ',
+        ('ほ', false) => 'ぼ',
+        ('か', false) => 'が',
+        ('き', false) => 'ぎ',
+        ('く', false) => 'ぐ',
+        ('け', false) => 'げ',
+        ('こ', false) => 'ご',
+        ('さ', false) => 'ざ',
+        ('し', false) => 'じ',
+        ('す', false) => 'ず',
+        ('せ', false) => 'ぜ',
+        ('そ', false) => 'ぞ',
+        (c, _) => c,
+    };
+    std::iter::once(changed).chain(chars).collect()
+}
+
+/// The reading of the integer `reading` followed by `counter`.
+fn counted(reading: &str, counter: &str, rule: Rule) -> String {
+    let mut reading = reading.to_owned();
+    let counter = match rule {
+        Rule::Plain | Rule::Day => counter.to_owned(),
+        Rule::Geminate { voiced } => {
+            if geminate(&mut reading) {
+                change_row(counter, true)
+            } else if voiced && reading.ends_with('ん') && !reading.ends_with("よん") {
+                change_row(counter, false)
+            } else {
+                counter.to_owned()
+            }
+        }
+        Rule::Minutes => {
+            if geminate(&mut reading) || reading.ends_with('ん') {
+                change_row(counter, true)
+            } else {
+                counter.to_owned()
+            }
+        }
+        Rule::Hour | Rule::Month => {
+            let four = if matches!(rule, Rule::Hour) {
+                "よ"
+            } else {
+                "し"
+            };
+            replace_suffix(&mut reading, "よん", four);
+            replace_suffix(&mut reading, "なな", "しち");
+            replace_suffix(&mut reading, "きゅう", "く");
+            counter.to_owned()
+        }
+        Rule::Year => {
+            replace_suffix(&mut reading, "よん", "よ");
+            counter.to_owned()
+        }
+        Rule::Person => {
+            match reading.as_str() {
+                "いち" => return "ひとり".to_owned(),
+                "に" => return "ふたり".to_owned(),
+                _ => {}
+            }
+            replace_suffix(&mut reading, "よん", "よ");
+            replace_suffix(&mut reading, "なな", "しち");
+            counter.to_owned()
+        }
+        Rule::Native => {
+            const NATIVE: [&str; 10] = [
+                "ひとつ",
+                "ふたつ",
+                "みっつ",
+                "よっつ",
+                "いつつ",
+                "むっつ",
+                "ななつ",
+                "やっつ",
+                "ここのつ",
+                "とお",
+            ];
+            return match reading_value(&reading).filter(|&n| n <= 10) {
+                Some(n) => NATIVE[n as usize - 1].to_owned(),
+                None => format!("{reading}つ"),
+            };
+        }
+    };
+    reading + &counter
+}
+
+/// The reading of a day of the month, or a number of days.
+fn day_of_month(day: u64) -> String {
+    match day {
+        1 => "ついたち".to_owned(),
+        2 => "ふつか".to_owned(),
+        3 => "みっか".to_owned(),
+        4 => "よっか".to_owned(),
+        5 => "いつか".to_owned(),
+        6 => "むいか".to_owned(),
+        7 => "なのか".to_owned(),
+        8 => "ようか".to_owned(),
+        9 => "ここのか".to_owned(),
+        10 => "とおか".to_owned(),
+        14 => "じゅうよっか".to_owned(),
+        20 => "はつか".to_owned(),
+        24 => "にじゅうよっか".to_owned(),
+        day => integer(day) + "にち",
+    }
+}
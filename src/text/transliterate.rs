@@ -0,0 +1,467 @@
+//! Reading latin words in katakana, see [`Transliterator`].
+
+use std::{borrow::Cow, collections::HashMap};
+
+/// Common English words and names and how they are read in Japanese.
+const WORDS: &[(&str, &str)] = &[
+    ("account", "アカウント"),
+    ("amazon", "アマゾン"),
+    ("and", "アンド"),
+    ("android", "アンドロイド"),
+    ("app", "アプリ"),
+    ("apple", "アップル"),
+    ("birthday", "バースデー"),
+    ("blog", "ブログ"),
+    ("bluetooth", "ブルートゥース"),
+    ("bug", "バグ"),
+    ("button", "ボタン"),
+    ("camera", "カメラ"),
+    ("channel", "チャンネル"),
+    ("chat", "チャット"),
+    ("chrome", "クローム"),
+    ("click", "クリック"),
+    ("client", "クライアント"),
+    ("cloud", "クラウド"),
+    ("code", "コード"),
+    ("coffee", "コーヒー"),
+    ("comment", "コメント"),
+    ("computer", "コンピューター"),
+    ("data", "データ"),
+    ("discord", "ディスコード"),
+    ("download", "ダウンロード"),
+    ("error", "エラー"),
+    ("file", "ファイル"),
+    ("follow", "フォロー"),
+    ("for", "フォー"),
+    ("free", "フリー"),
+    ("game", "ゲーム"),
+    ("github", "ギットハブ"),
+    ("good", "グッド"),
+    ("google", "グーグル"),
+    ("happy", "ハッピー"),
+    ("hello", "ハロー"),
+    ("home", "ホーム"),
+    ("image", "イメージ"),
+    ("internet", "インターネット"),
+    ("iphone", "アイフォーン"),
+    ("java", "ジャバ"),
+    ("javascript", "ジャバスクリプト"),
+    ("key", "キー"),
+    ("link", "リンク"),
+    ("linux", "リナックス"),
+    ("live", "ライブ"),
+    ("login", "ログイン"),
+    ("logout", "ログアウト"),
+    ("love", "ラブ"),
+    ("mail", "メール"),
+    ("make", "メイク"),
+    ("meeting", "ミーティング"),
+    ("member", "メンバー"),
+    ("menu", "メニュー"),
+    ("microsoft", "マイクロソフト"),
+    ("model", "モデル"),
+    ("morning", "モーニング"),
+    ("movie", "ムービー"),
+    ("music", "ミュージック"),
+    ("name", "ネーム"),
+    ("network", "ネットワーク"),
+    ("new", "ニュー"),
+    ("news", "ニュース"),
+    ("nice", "ナイス"),
+    ("night", "ナイト"),
+    ("nintendo", "ニンテンドー"),
+    ("online", "オンライン"),
+    ("open", "オープン"),
+    ("page", "ページ"),
+    ("password", "パスワード"),
+    ("phone", "フォン"),
+    ("photo", "フォト"),
+    ("play", "プレイ"),
+    ("player", "プレイヤー"),
+    ("post", "ポスト"),
+    ("power", "パワー"),
+    ("project", "プロジェクト"),
+    ("python", "パイソン"),
+    ("rust", "ラスト"),
+    ("sale", "セール"),
+    ("server", "サーバー"),
+    ("service", "サービス"),
+    ("setting", "セッティング"),
+    ("settings", "セッティングス"),
+    ("share", "シェア"),
+    ("shop", "ショップ"),
+    ("site", "サイト"),
+    ("smart", "スマート"),
+    ("software", "ソフトウェア"),
+    ("sony", "ソニー"),
+    ("start", "スタート"),
+    ("steam", "スチーム"),
+    ("stop", "ストップ"),
+    ("store", "ストア"),
+    ("stream", "ストリーム"),
+    ("super", "スーパー"),
+    ("switch", "スイッチ"),
+    ("system", "システム"),
+    ("take", "テイク"),
+    ("team", "チーム"),
+    ("test", "テスト"),
+    ("thank", "サンク"),
+    ("thanks", "サンクス"),
+    ("the", "ザ"),
+    ("time", "タイム"),
+    ("tokyo", "トーキョー"),
+    ("twitch", "ツイッチ"),
+    ("twitter", "ツイッター"),
+    ("update", "アップデート"),
+    ("upload", "アップロード"),
+    ("user", "ユーザー"),
+    ("version", "バージョン"),
+    ("video", "ビデオ"),
+    ("voice", "ボイス"),
+    ("voicevox", "ボイスボックス"),
+    ("vtuber", "ブイチューバー"),
+    ("web", "ウェブ"),
+    ("wifi", "ワイファイ"),
+    ("windows", "ウィンドウズ"),
+    ("with", "ウィズ"),
+    ("world", "ワールド"),
+    ("year", "イヤー"),
+    ("yes", "イエス"),
+    ("you", "ユー"),
+    ("youtube", "ユーチューブ"),
+];
+
+/// How capital letters are read when an acronym is spelled out.
+const LETTERS: [&str; 26] = [
+    "エー",
+    "ビー",
+    "シー",
+    "ディー",
+    "イー",
+    "エフ",
+    "ジー",
+    "エイチ",
+    "アイ",
+    "ジェー",
+    "ケー",
+    "エル",
+    "エム",
+    "エヌ",
+    "オー",
+    "ピー",
+    "キュー",
+    "アール",
+    "エス",
+    "ティー",
+    "ユー",
+    "ブイ",
+    "ダブリュー",
+    "エックス",
+    "ワイ",
+    "ゼット",
+];
+
+/// Romaji syllables in Hepburn and Kunrei-shiki, longer ones first.
+const SYLLABLES: &[(&str, &str)] = &[
+    ("kya", "キャ"),
+    ("kyu", "キュ"),
+    ("kyo", "キョ"),
+    ("sha", "シャ"),
+    ("shi", "シ"),
+    ("shu", "シュ"),
+    ("she", "シェ"),
+    ("sho", "ショ"),
+    ("sya", "シャ"),
+    ("syu", "シュ"),
+    ("syo", "ショ"),
+    ("cha", "チャ"),
+    ("chi", "チ"),
+    ("chu", "チュ"),
+    ("che", "チェ"),
+    ("cho", "チョ"),
+    ("tya", "チャ"),
+    ("tyu", "チュ"),
+    ("tyo", "チョ"),
+    ("tsu", "ツ"),
+    ("nya", "ニャ"),
+    ("nyu", "ニュ"),
+    ("nyo", "ニョ"),
+    ("hya", "ヒャ"),
+    ("hyu", "ヒュ"),
+    ("hyo", "ヒョ"),
+    ("mya", "ミャ"),
+    ("myu", "ミュ"),
+    ("myo", "ミョ"),
+    ("rya", "リャ"),
+    ("ryu", "リュ"),
+    ("ryo", "リョ"),
+    ("gya", "ギャ"),
+    ("gyu", "ギュ"),
+    ("gyo", "ギョ"),
+    ("zya", "ジャ"),
+    ("zyu", "ジュ"),
+    ("zyo", "ジョ"),
+    ("bya", "ビャ"),
+    ("byu", "ビュ"),
+    ("byo", "ビョ"),
+    ("pya", "ピャ"),
+    ("pyu", "ピュ"),
+    ("pyo", "ピョ"),
+    ("ka", "カ"),
+    ("ki", "キ"),
+    ("ku", "ク"),
+    ("ke", "ケ"),
+    ("ko", "コ"),
+    ("sa", "サ"),
+    ("si", "シ"),
+    ("su", "ス"),
+    ("se", "セ"),
+    ("so", "ソ"),
+    ("ta", "タ"),
+    ("ti", "チ"),
+    ("tu", "ツ"),
+    ("te", "テ"),
+    ("to", "ト"),
+    ("na", "ナ"),
+    ("ni", "ニ"),
+    ("nu", "ヌ"),
+    ("ne", "ネ"),
+    ("no", "ノ"),
+    ("ha", "ハ"),
+    ("hi", "ヒ"),
+    ("hu", "フ"),
+    ("fu", "フ"),
+    ("he", "ヘ"),
+    ("ho", "ホ"),
+    ("fa", "ファ"),
+    ("fi", "フィ"),
+    ("fe", "フェ"),
+    ("fo", "フォ"),
+    ("ma", "マ"),
+    ("mi", "ミ"),
+    ("mu", "ム"),
+    ("me", "メ"),
+    ("mo", "モ"),
+    ("ya", "ヤ"),
+    ("yu", "ユ"),
+    ("yo", "ヨ"),
+    ("ra", "ラ"),
+    ("ri", "リ"),
+    ("ru", "ル"),
+    ("re", "レ"),
+    ("ro", "ロ"),
+    ("wa", "ワ"),
+    ("wo", "ヲ"),
+    ("ga", "ガ"),
+    ("gi", "ギ"),
+    ("gu", "グ"),
+    ("ge", "ゲ"),
+    ("go", "ゴ"),
+    ("za", "ザ"),
+    ("zi", "ジ"),
+    ("zu", "ズ"),
+    ("ze", "ゼ"),
+    ("zo", "ゾ"),
+    ("ja", "ジャ"),
+    ("ji", "ジ"),
+    ("ju", "ジュ"),
+    ("je", "ジェ"),
+    ("jo", "ジョ"),
+    ("da", "ダ"),
+    ("di", "ヂ"),
+    ("du", "ヅ"),
+    ("de", "デ"),
+    ("do", "ド"),
+    ("ba", "バ"),
+    ("bi", "ビ"),
+    ("bu", "ブ"),
+    ("be", "ベ"),
+    ("bo", "ボ"),
+    ("pa", "パ"),
+    ("pi", "ピ"),
+    ("pu", "プ"),
+    ("pe", "ペ"),
+    ("po", "ポ"),
+    ("va", "ヴァ"),
+    ("vi", "ヴィ"),
+    ("vu", "ヴ"),
+    ("ve", "ヴェ"),
+    ("vo", "ヴォ"),
+    ("a", "ア"),
+    ("i", "イ"),
+    ("u", "ウ"),
+    ("e", "エ"),
+    ("o", "オ"),
+];
+
+/// Rewrites latin words in katakana, so that `OpenAIのAPI` is read as `オープンエーアイのエーピーアイ`
+/// instead of letter by letter.
+///
+/// Every word is first looked up in a table of common English words, which can be extended
+/// with [`Transliterator::word`]. Words written in camel case are looked up part by part.
+/// Parts that are not in the table are spelled out if they are in capitals and otherwise
+/// read as romaji, if they are valid romaji. Everything else is kept as it is.
+///
+/// ```
+/// use voicevox_dyn::text::Transliterator;
+///
+/// let transliterator = Transliterator::new().word("rustacean", "ラストエイシャン");
+/// assert_eq!(
+///     transliterator.transliterate("OpenAIのAPIでsushiを食べるrustacean"),
+///     "オープンエーアイのエーピーアイでスシを食べるラストエイシャン"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Transliterator {
+    /// Lowercase words and their katakana.
+    words: HashMap<String, String>,
+    romaji: bool,
+}
+
+impl Default for Transliterator {
+    fn default() -> Self {
+        Self {
+            words: WORDS
+                .iter()
+                .map(|&(word, katakana)| (word.to_owned(), katakana.to_owned()))
+                .collect(),
+            romaji: true,
+        }
+    }
+}
+
+impl Transliterator {
+    /// A transliterator with the built-in table of common words.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A transliterator without any words, only spelling out capitals and reading romaji.
+    pub fn empty() -> Self {
+        Self {
+            words: HashMap::new(),
+            romaji: true,
+        }
+    }
+
+    /// Reads `word` as `katakana`, ignoring the case of `word`.
+    pub fn word(mut self, word: impl AsRef<str>, katakana: impl Into<String>) -> Self {
+        self.words
+            .insert(word.as_ref().to_lowercase(), katakana.into());
+        self
+    }
+
+    /// Whether words that are not in the table are read as romaji, e.g. `sushi` as `スシ`.
+    /// Defaults to `true`.
+    pub fn romaji(mut self, romaji: bool) -> Self {
+        self.romaji = romaji;
+        self
+    }
+
+    /// Rewrites the latin words of `text` in katakana.
+    pub fn transliterate<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let mut output = String::new();
+        // Everything before this has been copied to `output`.
+        let mut copied = 0;
+        let mut rest = text;
+        while let Some(start) = rest.find(|c: char| c.is_ascii_alphabetic()) {
+            let len = rest[start..]
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len() - start);
+            let word = &rest[start..start + len];
+            let offset = text.len() - rest.len();
+            rest = &rest[start + len..];
+            if let Some(katakana) = self.word_katakana(word) {
+                output.push_str(&text[copied..offset + start]);
+                output.push_str(&katakana);
+                copied = offset + start + len;
+            }
+        }
+        if copied == 0 {
+            return Cow::Borrowed(text);
+        }
+        output.push_str(&text[copied..]);
+        Cow::Owned(output)
+    }
+
+    /// The katakana for `word`, keeping the parts that can not be transliterated.
+    fn word_katakana(&self, word: &str) -> Option<String> {
+        if let Some(katakana) = self.words.get(&word.to_ascii_lowercase()) {
+            return Some(katakana.clone());
+        }
+        let mut transliterated = false;
+        let katakana = camel_case_parts(word)
+            .into_iter()
+            .map(|part| match self.part_katakana(part) {
+                Some(katakana) => {
+                    transliterated = true;
+                    katakana
+                }
+                None => part.to_owned(),
+            })
+            .collect();
+        transliterated.then_some(katakana)
+    }
+
+    fn part_katakana(&self, part: &str) -> Option<String> {
+        if let Some(katakana) = self.words.get(&part.to_ascii_lowercase()) {
+            Some(katakana.clone())
+        } else if part.bytes().all(|b| b.is_ascii_uppercase()) {
+            Some(part.bytes().map(|b| LETTERS[(b - b'A') as usize]).collect())
+        } else if self.romaji {
+            romaji(&part.to_ascii_lowercase())
+        } else {
+            None
+        }
+    }
+}
+
+/// Splits `word` where the case changes, e.g. `OpenAIKey` into `Open`, `AI` and `Key`.
+fn camel_case_parts(word: &str) -> Vec<&str> {
+    let bytes = word.as_bytes();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in 1..bytes.len() {
+        let lower_to_upper = bytes[i - 1].is_ascii_lowercase() && bytes[i].is_ascii_uppercase();
+        // The last capital of `AIKey` starts the next part.
+        let acronym_end = bytes[i - 1].is_ascii_uppercase()
+            && bytes[i].is_ascii_uppercase()
+            && bytes.get(i + 1).is_some_and(u8::is_ascii_lowercase);
+        if lower_to_upper || acronym_end {
+            parts.push(&word[start..i]);
+            start = i;
+        }
+    }
+    parts.push(&word[start..]);
+    parts
+}
+
+/// Reads the lowercase `word` as romaji, if it is valid romaji.
+fn romaji(word: &str) -> Option<String> {
+    let bytes = word.as_bytes();
+    let mut katakana = String::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &word[i..];
+        if let Some(&(syllable, kana)) = SYLLABLES.iter().find(|(s, _)| rest.starts_with(s)) {
+            katakana.push_str(kana);
+            i += syllable.len();
+        } else if bytes[i] == b'n' {
+            katakana.push('ン');
+            // `nn` is a single `ン` unless a vowel follows, as in `konnichiwa`.
+            let vowel_follows = bytes.get(i + 2).is_some_and(|b| b"aiueoy".contains(b));
+            i += if rest.starts_with("nn") && !vowel_follows {
+                2
+            } else {
+                1
+            };
+        } else if bytes.get(i + 1) == Some(&bytes[i]) || rest.starts_with("tch") {
+            // Doubled consonants, as in `kitte` or `matcha`.
+            katakana.push('ッ');
+            i += 1;
+        } else {
+            return None;
+        }
+    }
+    Some(katakana)
+}
@@ -0,0 +1,88 @@
+//! A [`VoiceVox`] that tracks initialization in its type.
+//!
+//! [`VoiceVox<Loaded>`] can only be initialized, which turns it into a [`VoiceVox<Ready>`].
+//! Only [`VoiceVox<Ready>`] gives access to [`load_model`](crate::VoiceVox::load_model),
+//! [`tts`](crate::VoiceVox::tts) and the other methods that need an initialized core,
+//! so calling them too early is a compile time error instead of an
+//! [`UninitializedStatusError`](crate::ResultCode::UninitializedStatusError).
+//!
+//! ```no_run
+//! use voicevox_dyn::{styles::ZUNDAMON_NORMAL, typestate::VoiceVox, AccelerationMode};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let vv = VoiceVox::load()?.init(AccelerationMode::Auto, 0, false)?;
+//! vv.load_model(ZUNDAMON_NORMAL)?;
+//! let wav = vv.tts("こんにちは", ZUNDAMON_NORMAL, Default::default())?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{ffi::OsStr, marker::PhantomData, ops::Deref};
+
+use crate::{AccelerationMode, Error};
+
+/// State of a [`VoiceVox`] that has been loaded, but not initialized yet.
+#[derive(Debug)]
+pub struct Loaded;
+
+/// State of a [`VoiceVox`] that has been initialized.
+#[derive(Debug)]
+pub struct Ready;
+
+/// See the [module level documentation](self).
+pub struct VoiceVox<S> {
+    inner: crate::VoiceVox,
+    _state: PhantomData<S>,
+}
+
+impl VoiceVox<Loaded> {
+    /// See [`crate::VoiceVox::load`].
+    pub fn load() -> Result<Self, Error> {
+        crate::VoiceVox::load().map(Self::new)
+    }
+
+    /// See [`crate::VoiceVox::load_with_args`].
+    pub fn load_with_args<S: AsRef<OsStr>>(
+        args: impl IntoIterator<Item = S>,
+    ) -> Result<Self, Error> {
+        crate::VoiceVox::load_with_args(args).map(Self::new)
+    }
+
+    fn new(inner: crate::VoiceVox) -> Self {
+        Self {
+            inner,
+            _state: PhantomData,
+        }
+    }
+
+    /// Initializes the voicevox runtime, see [`crate::VoiceVox::init`].
+    pub fn init(
+        mut self,
+        acceleration_mode: AccelerationMode,
+        cpu_num_threads: u16,
+        load_all_models: bool,
+    ) -> Result<VoiceVox<Ready>, Error> {
+        self.inner
+            .init(acceleration_mode, cpu_num_threads, load_all_models)?;
+        Ok(VoiceVox {
+            inner: self.inner,
+            _state: PhantomData,
+        })
+    }
+}
+
+impl VoiceVox<Ready> {
+    /// Returns the untyped [`crate::VoiceVox`], e.g. to call [`crate::VoiceVox::into_handle`].
+    pub fn into_inner(self) -> crate::VoiceVox {
+        self.inner
+    }
+}
+
+/// An initialized `VoiceVox` supports everything [`crate::VoiceVox`] does.
+impl Deref for VoiceVox<Ready> {
+    type Target = crate::VoiceVox;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
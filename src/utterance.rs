@@ -0,0 +1,190 @@
+use std::{ops::Range, time::Duration};
+
+use crate::{AccentPhrase, AudioQuery, Error, Mora, TtsOptions, VoiceVox, Wav};
+
+/// An [`AudioQuery`] being tuned like in the VOICEVOX editor, created with
+/// [`VoiceVox::utterance`].
+///
+/// Changing an accent regenerates the pitch and the lengths of the moras of its accent phrase,
+/// the other changes are made to the query directly.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use voicevox_dyn::{styles::ZUNDAMON_NORMAL, VoiceVox};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let vv = VoiceVox::quickstart(ZUNDAMON_NORMAL)?;
+/// let mut utterance = vv.utterance("橋の端を歩く", ZUNDAMON_NORMAL, Default::default())?;
+/// utterance.set_accent(0, 1)?;
+/// utterance.set_pause_after(1, Duration::from_millis(300))?;
+/// utterance.scale_pitch(0..1, 1.5)?;
+/// std::fs::write("speech.wav", utterance.synthesize()?.to_bytes())?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Utterance<'a> {
+    vv: &'a VoiceVox,
+    speaker_id: u32,
+    opts: TtsOptions,
+    query: AudioQuery,
+}
+
+impl<'a> Utterance<'a> {
+    /// Tunes an existing `query`, e.g. one that was saved as JSON.
+    pub fn from_query(
+        vv: &'a VoiceVox,
+        query: AudioQuery,
+        speaker_id: u32,
+        opts: TtsOptions,
+    ) -> Self {
+        Self {
+            vv,
+            speaker_id,
+            opts,
+            query,
+        }
+    }
+
+    pub fn accent_phrases(&self) -> &[AccentPhrase] {
+        &self.query.accent_phrases
+    }
+
+    pub fn query(&self) -> &AudioQuery {
+        &self.query
+    }
+
+    /// The query, e.g. for changing the [`SpeechParams`](crate::SpeechParams).
+    pub fn query_mut(&mut self) -> &mut AudioQuery {
+        &mut self.query
+    }
+
+    pub fn into_query(self) -> AudioQuery {
+        self.query
+    }
+
+    /// Accents mora `mora` of accent phrase `phrase`, both starting at 0, and regenerates the
+    /// pitch and the lengths of the phrase's moras. On error, the query is left unchanged.
+    pub fn set_accent(&mut self, phrase: usize, mora: usize) -> Result<(), Error> {
+        let moras = self.phrase(phrase)?.moras.len();
+        if mora >= moras {
+            return Err(Error::InvalidInput(format!(
+                "accent phrase {phrase} has {moras} moras, there is no mora {mora}"
+            )));
+        }
+        let mut phrases = self.query.accent_phrases.clone();
+        phrases[phrase].accent = mora + 1;
+
+        let opts = TtsOptions {
+            kana: true,
+            ..self.opts
+        };
+        let mut generated = self
+            .vv
+            .audio_query(to_kana(&phrases), self.speaker_id, opts)?;
+        if generated.accent_phrases.len() != phrases.len() {
+            return Err(Error::InvalidOutput(format!(
+                "voicevox split the accent phrases differently: {}",
+                generated.kana
+            )));
+        }
+        let generated = generated.accent_phrases.swap_remove(phrase);
+        let edited = &mut self.query.accent_phrases[phrase];
+        edited.moras = generated.moras;
+        edited.accent = generated.accent;
+        Ok(())
+    }
+
+    /// Sets the pause after accent phrase `phrase`, starting at 0. A pause of zero removes it.
+    pub fn set_pause_after(&mut self, phrase: usize, pause: Duration) -> Result<(), Error> {
+        self.phrase(phrase)?;
+        let edited = &mut self.query.accent_phrases[phrase];
+        if pause.is_zero() {
+            edited.pause_mora = None;
+            return Ok(());
+        }
+        let mora = edited.pause_mora.get_or_insert_with(|| Mora {
+            text: "、".to_owned(),
+            consonant: None,
+            consonant_length: None,
+            vowel: "pau".to_owned(),
+            vowel_length: 0.0,
+            pitch: 0.0,
+        });
+        mora.vowel_length = pause.as_secs_f32();
+        Ok(())
+    }
+
+    /// Scales how far the pitch of the voiced moras of the accent phrases in `phrases` moves
+    /// from their average, like [`AudioQuery::intonation_scale`] does for the whole query.
+    /// The pitch is clamped to [`Mora::PITCH_RANGE`].
+    pub fn scale_pitch(&mut self, phrases: Range<usize>, factor: f32) -> Result<(), Error> {
+        let len = self.query.accent_phrases.len();
+        let Some(edited) = self.query.accent_phrases.get_mut(phrases.clone()) else {
+            return Err(Error::InvalidInput(format!(
+                "there are only {len} accent phrases, {phrases:?} is out of range"
+            )));
+        };
+        let voiced = || {
+            edited
+                .iter()
+                .flat_map(|phrase| &phrase.moras)
+                .filter(|mora| mora.is_voiced())
+        };
+        let count = voiced().count();
+        if count == 0 {
+            return Ok(());
+        }
+        let average = voiced().map(|mora| mora.pitch).sum::<f32>() / count as f32;
+        for mora in edited.iter_mut().flat_map(|phrase| &mut phrase.moras) {
+            mora.set_pitch(average + (mora.pitch - average) * factor);
+        }
+        Ok(())
+    }
+
+    /// Synthesizes the tuned query.
+    pub fn synthesize(&self) -> Result<Wav, Error> {
+        Wav::parse_output(
+            &self
+                .vv
+                .synthesis_raw(&self.query, self.speaker_id, self.opts)?,
+        )
+    }
+
+    fn phrase(&self, phrase: usize) -> Result<&AccentPhrase, Error> {
+        self.query.accent_phrases.get(phrase).ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "there are only {} accent phrases, there is no accent phrase {phrase}",
+                self.query.accent_phrases.len()
+            ))
+        })
+    }
+}
+
+/// Writes `phrases` as AquesTalk style kana, see [`TtsOptionsBuilder::kana`](crate::TtsOptionsBuilder::kana).
+fn to_kana(phrases: &[AccentPhrase]) -> String {
+    let mut kana = String::new();
+    for (i, phrase) in phrases.iter().enumerate() {
+        for (j, mora) in phrase.moras.iter().enumerate() {
+            // Voicevox writes unvoiced vowels in upper case.
+            if matches!(&*mora.vowel, "A" | "I" | "U" | "E" | "O") {
+                kana.push('_');
+            }
+            kana.push_str(&mora.text);
+            if j + 1 == phrase.accent {
+                kana.push('\'');
+            }
+        }
+        if phrase.is_interrogative {
+            kana.push('？');
+        }
+        if i + 1 < phrases.len() {
+            kana.push(if phrase.pause_mora.is_some() {
+                '、'
+            } else {
+                '/'
+            });
+        }
+    }
+    kana
+}
@@ -0,0 +1,561 @@
+use std::{ops::Range, time::Duration};
+
+use crate::Error;
+
+/// A parsed PCM wav file.
+///
+/// Voicevox synthesizes 16 bit mono PCM at the sampling rate of the [`AudioQuery`](crate::AudioQuery),
+/// 24 kHz by default.
+///
+/// ```
+/// use voicevox_dyn::Wav;
+///
+/// let wav = Wav {
+///     sample_rate: 24000,
+///     channels: 1,
+///     bits_per_sample: 16,
+///     data: vec![0; 480],
+/// };
+/// assert_eq!(Wav::parse(&wav.to_bytes())?, wav);
+/// # Ok::<(), voicevox_dyn::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wav {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    /// The interleaved PCM samples in little endian.
+    pub data: Vec<u8>,
+}
+
+impl Wav {
+    /// Parses a RIFF wav file containing integer PCM samples.
+    ///
+    /// Returns [`Error::InvalidInput`] if `bytes` is not such a file.
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        parse(bytes).map_err(|reason| Error::InvalidInput(format!("invalid wav: {}", reason)))
+    }
+
+    /// Parses a wav synthesized by voicevox, reporting failures as [`Error::InvalidOutput`].
+    pub(crate) fn parse_output(bytes: &[u8]) -> Result<Self, Error> {
+        parse(bytes).map_err(|reason| Error::InvalidOutput(format!("invalid wav: {}", reason)))
+    }
+
+    /// Encodes the wav into a RIFF file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.data.len());
+        bytes.extend_from_slice(&self.header(Some(self.data.len() as u32)));
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    /// The RIFF header for `data_len` bytes of samples in the format of the wav.
+    ///
+    /// Without a length, the sizes are set to `u32::MAX`, which players treat as a stream
+    /// that lasts until the end of the file.
+    pub(crate) fn header(&self, data_len: Option<u32>) -> [u8; HEADER_LEN] {
+        let (riff_len, data_len) = match data_len {
+            Some(len) => (len.saturating_add(HEADER_LEN as u32 - 8), len),
+            None => (u32::MAX, u32::MAX),
+        };
+        let mut header = [0; HEADER_LEN];
+        let fields: [&[u8]; 13] = [
+            b"RIFF",
+            &riff_len.to_le_bytes(),
+            b"WAVE",
+            b"fmt ",
+            &16u32.to_le_bytes(),
+            &PCM.to_le_bytes(),
+            &self.channels.to_le_bytes(),
+            &self.sample_rate.to_le_bytes(),
+            &(self.sample_rate * self.block_align() as u32).to_le_bytes(),
+            &self.block_align().to_le_bytes(),
+            &self.bits_per_sample.to_le_bytes(),
+            b"data",
+            &data_len.to_le_bytes(),
+        ];
+        let mut position = 0;
+        for field in fields {
+            header[position..position + field.len()].copy_from_slice(field);
+            position += field.len();
+        }
+        header
+    }
+
+    /// The length of the wav.
+    pub fn duration(&self) -> Duration {
+        let frames = self.data.len() / self.block_align() as usize;
+        Duration::from_secs_f64(frames as f64 / self.sample_rate as f64)
+    }
+
+    /// The number of bytes of one sample of every channel.
+    pub fn block_align(&self) -> u16 {
+        self.channels * self.bits_per_sample.div_ceil(8)
+    }
+
+    /// Creates a 16 bit wav from interleaved samples.
+    pub fn from_samples_i16(sample_rate: u32, channels: u16, samples: &[i16]) -> Self {
+        Self {
+            sample_rate,
+            channels,
+            bits_per_sample: 16,
+            data: samples.iter().flat_map(|s| s.to_le_bytes()).collect(),
+        }
+    }
+
+    /// Creates a 16 bit wav from interleaved samples in `-1.0..=1.0`, clamping samples outside of it.
+    pub fn from_samples_f32(sample_rate: u32, channels: u16, samples: &[f32]) -> Self {
+        let samples: Vec<i16> = samples.iter().map(|&s| f32_to_i16(s)).collect();
+        Self::from_samples_i16(sample_rate, channels, &samples)
+    }
+
+    /// The interleaved samples, converted to 16 bit if necessary.
+    ///
+    /// ```
+    /// use voicevox_dyn::Wav;
+    ///
+    /// let wav = Wav::from_samples_i16(24000, 2, &[1, -1, 2, -2]);
+    /// assert_eq!(wav.samples_i16(), [1, -1, 2, -2]);
+    /// assert_eq!(Wav::deinterleave(&wav.samples_i16(), 2), [[1, 2], [-1, -2]]);
+    /// ```
+    pub fn samples_i16(&self) -> Vec<i16> {
+        match self.bits_per_sample {
+            16 => self
+                .data
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                .collect(),
+            _ => self.samples_f32().into_iter().map(f32_to_i16).collect(),
+        }
+    }
+
+    /// The interleaved samples in `-1.0..=1.0`.
+    ///
+    /// # Panics
+    /// If `bits_per_sample` is not 8, 16, 24 or 32, which [`Wav::parse`] never returns.
+    pub fn samples_f32(&self) -> Vec<f32> {
+        let data = &self.data;
+        match self.bits_per_sample {
+            8 => data.iter().map(|&b| (b as f32 - 128.0) / 128.0).collect(),
+            16 => data
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+                .collect(),
+            24 => data
+                .chunks_exact(3)
+                .map(|b| i32::from_le_bytes([0, b[0], b[1], b[2]]) as f32 / 2147483648.0)
+                .collect(),
+            32 => data
+                .chunks_exact(4)
+                .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.0)
+                .collect(),
+            bits => panic!("unsupported sample size of {bits} bits"),
+        }
+    }
+
+    /// The samples of every channel as `f32`, see [`Wav::samples_f32`].
+    pub fn channels_f32(&self) -> Vec<Vec<f32>> {
+        Self::deinterleave(&self.samples_f32(), self.channels)
+    }
+
+    /// Splits interleaved samples into one `Vec` per channel.
+    pub fn deinterleave<T: Copy>(samples: &[T], channels: u16) -> Vec<Vec<T>> {
+        let channels = channels as usize;
+        (0..channels)
+            .map(|channel| {
+                samples
+                    .iter()
+                    .skip(channel)
+                    .step_by(channels)
+                    .copied()
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Joins `parts` into one wav, with `gap` of silence between consecutive parts.
+    ///
+    /// Returns [`Error::InvalidInput`] if `parts` is empty or the parts differ in sampling rate,
+    /// channels or bits per sample.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use voicevox_dyn::Wav;
+    ///
+    /// let a = Wav::from_samples_i16(1000, 1, &[1, 2]);
+    /// let b = Wav::from_samples_i16(1000, 1, &[3]);
+    /// let wav = Wav::concat(&[a, b], Duration::from_millis(3))?;
+    /// assert_eq!(wav.samples_i16(), [1, 2, 0, 0, 0, 3]);
+    /// # Ok::<(), voicevox_dyn::Error>(())
+    /// ```
+    pub fn concat(parts: &[Wav], gap: Duration) -> Result<Wav, Error> {
+        let first = Wav::common_format(parts)?;
+
+        let silence = first.silence(gap);
+        let len = parts.iter().map(|part| part.data.len()).sum::<usize>()
+            + silence.len() * (parts.len() - 1);
+        let mut data = Vec::with_capacity(len);
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                data.extend_from_slice(&silence);
+            }
+            data.extend_from_slice(&part.data);
+        }
+        Ok(Wav {
+            data,
+            ..first.clone_format()
+        })
+    }
+
+    /// Joins `parts` into one wav, overlapping consecutive parts by `fade` during which one
+    /// fades out while the next one fades in. The result is 16 bit.
+    ///
+    /// This avoids the clicks at the boundaries of speech synthesized in chunks.
+    /// The fade is shortened for parts shorter than it.
+    ///
+    /// Returns [`Error::InvalidInput`] like [`Wav::concat`].
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use voicevox_dyn::Wav;
+    ///
+    /// let a = Wav::from_samples_i16(1000, 1, &[1000, 1000, 1000]);
+    /// let b = Wav::from_samples_i16(1000, 1, &[0, 0, 0]);
+    /// let wav = Wav::concat_crossfade(&[a, b], Duration::from_millis(2))?;
+    /// assert_eq!(wav.samples_i16(), [1000, 750, 250, 0]);
+    /// # Ok::<(), voicevox_dyn::Error>(())
+    /// ```
+    pub fn concat_crossfade(parts: &[Wav], fade: Duration) -> Result<Wav, Error> {
+        let first = Wav::common_format(parts)?;
+        let fade = (fade.as_secs_f64() * first.sample_rate as f64).round() as usize;
+
+        let mut output = first.channels_f32();
+        for part in &parts[1..] {
+            let part = part.channels_f32();
+            let overlap = fade.min(output[0].len()).min(part[0].len());
+            for (output, part) in output.iter_mut().zip(part) {
+                let start = output.len() - overlap;
+                for (i, (output, part)) in output[start..].iter_mut().zip(&part).enumerate() {
+                    let t = (i as f32 + 0.5) / overlap as f32;
+                    *output = *output * (1.0 - t) + part * t;
+                }
+                output.extend_from_slice(&part[overlap..]);
+            }
+        }
+        Ok(Wav::from_samples_f32(
+            first.sample_rate,
+            first.channels,
+            &Wav::interleave(&output),
+        ))
+    }
+
+    /// Returns the first of `parts`, checking that all of them have the same format.
+    fn common_format(parts: &[Wav]) -> Result<&Wav, Error> {
+        let first = parts
+            .first()
+            .ok_or_else(|| Error::InvalidInput("no wavs to concatenate".to_owned()))?;
+        if let Some(part) = parts.iter().find(|part| !first.same_format(part)) {
+            return Err(Error::InvalidInput(format!(
+                "cannot concatenate wavs of different formats, {} Hz, {} channels, {} bits and {} Hz, {} channels, {} bits",
+                first.sample_rate,
+                first.channels,
+                first.bits_per_sample,
+                part.sample_rate,
+                part.channels,
+                part.bits_per_sample
+            )));
+        }
+        Ok(first)
+    }
+
+    /// Summarizes the wav in `buckets` consecutive parts of equal length, e.g. one per pixel
+    /// column of a waveform display.
+    ///
+    /// Every bucket covers all channels. Buckets without samples, because there are more
+    /// buckets than samples, are all zeros.
+    ///
+    /// ```
+    /// use voicevox_dyn::Wav;
+    ///
+    /// let wav = Wav::from_samples_f32(24000, 1, &[0.5, -0.5, 0.25, 0.25]);
+    /// let peaks = wav.peaks(2);
+    /// assert_eq!((peaks[0].min, peaks[0].max, peaks[0].rms), (-0.5, 0.5, 0.5));
+    /// assert_eq!((peaks[1].min, peaks[1].max, peaks[1].rms), (0.25, 0.25, 0.25));
+    /// ```
+    pub fn peaks(&self, buckets: usize) -> Vec<Peak> {
+        let samples = self.samples_f32();
+        let frames = samples.len() / self.channels as usize;
+        (0..buckets)
+            .map(|i| {
+                let start = i * frames / buckets * self.channels as usize;
+                let end = (i + 1) * frames / buckets * self.channels as usize;
+                let bucket = &samples[start..end];
+                if bucket.is_empty() {
+                    return Peak::default();
+                }
+                let square_sum: f32 = bucket.iter().map(|s| s * s).sum();
+                Peak {
+                    min: bucket.iter().copied().fold(f32::INFINITY, f32::min),
+                    max: bucket.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+                    rms: (square_sum / bucket.len() as f32).sqrt(),
+                }
+            })
+            .collect()
+    }
+
+    /// Removes the samples at the start and end that are quieter than `threshold_db`
+    /// relative to full scale, e.g. `-50.0`.
+    ///
+    /// A wav that is silent throughout becomes empty.
+    ///
+    /// ```
+    /// use voicevox_dyn::Wav;
+    ///
+    /// let wav = Wav::from_samples_i16(24000, 1, &[0, 3, 1000, 0, -2000, 2, 0]);
+    /// assert_eq!(wav.trim_silence(-40.0).samples_i16(), [1000, 0, -2000]);
+    /// ```
+    pub fn trim_silence(&self, threshold_db: f32) -> Wav {
+        let threshold = 10f32.powf(threshold_db / 20.0);
+        let samples = self.samples_f32();
+        let loud = |frame: &[f32]| frame.iter().any(|sample| sample.abs() > threshold);
+        let mut frames = samples.chunks_exact(self.channels as usize);
+        let start = frames.clone().position(loud);
+        let end = frames.rposition(loud);
+        let block_align = self.block_align() as usize;
+        let data = match (start, end) {
+            (Some(start), Some(end)) => {
+                self.data[start * block_align..(end + 1) * block_align].to_vec()
+            }
+            _ => Vec::new(),
+        };
+        Wav {
+            data,
+            ..self.clone_format()
+        }
+    }
+
+    /// Whether `other` has the same sampling rate, channels and bits per sample.
+    pub(crate) fn same_format(&self, other: &Wav) -> bool {
+        self.sample_rate == other.sample_rate
+            && self.channels == other.channels
+            && self.bits_per_sample == other.bits_per_sample
+    }
+
+    /// An empty wav of the same format.
+    pub(crate) fn clone_format(&self) -> Wav {
+        Wav {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            bits_per_sample: self.bits_per_sample,
+            data: Vec::new(),
+        }
+    }
+
+    /// The bytes of `duration` of silence in the format of the wav.
+    pub(crate) fn silence(&self, duration: Duration) -> Vec<u8> {
+        let frames = (duration.as_secs_f64() * self.sample_rate as f64).round() as usize;
+        // 8 bit samples are unsigned and centered around 128.
+        let zero = if self.bits_per_sample == 8 { 0x80 } else { 0 };
+        vec![zero; frames * self.block_align() as usize]
+    }
+
+    /// Resamples the wav to `sample_rate` with [rubato](https://docs.rs/rubato),
+    /// e.g. to the 48 kHz most VoIP applications expect. The result is 16 bit.
+    ///
+    /// Returns [`Error::InvalidInput`] if `sample_rate` is 0.
+    #[cfg(feature = "resample")]
+    pub fn resample(&self, sample_rate: u32) -> Result<Wav, Error> {
+        use rubato::{FftFixedInOut, Resampler};
+
+        if sample_rate == self.sample_rate {
+            return Ok(Wav::from_samples_i16(
+                sample_rate,
+                self.channels,
+                &self.samples_i16(),
+            ));
+        }
+
+        let input = self.channels_f32();
+        let frames = input[0].len();
+        let mut resampler = FftFixedInOut::<f32>::new(
+            self.sample_rate as usize,
+            sample_rate as usize,
+            1024,
+            self.channels as usize,
+        )
+        .map_err(|e| Error::InvalidInput(e.to_string()))?;
+        let to_error = |e: rubato::ResampleError| Error::InvalidInput(e.to_string());
+
+        // The resampler delays its output, so it is fed silence until everything came out.
+        let delay = resampler.output_delay();
+        let expected =
+            (frames as u64 * sample_rate as u64).div_ceil(self.sample_rate as u64) as usize;
+        let mut output = vec![Vec::with_capacity(delay + expected); input.len()];
+        let mut position = 0;
+        while output[0].len() < delay + expected {
+            let next = resampler.input_frames_next();
+            let chunk: Vec<_> = input
+                .iter()
+                .map(|channel| &channel[position.min(frames)..(position + next).min(frames)])
+                .collect();
+            let resampled = if position + next <= frames {
+                resampler.process(&chunk, None)
+            } else if position < frames {
+                resampler.process_partial(Some(&chunk), None)
+            } else {
+                resampler.process_partial::<&[f32]>(None, None)
+            }
+            .map_err(to_error)?;
+            position += next;
+            for (output, resampled) in output.iter_mut().zip(resampled) {
+                output.extend(resampled);
+            }
+        }
+
+        let output: Vec<_> = output
+            .into_iter()
+            .map(|channel| channel[delay..delay + expected].to_vec())
+            .collect();
+        Ok(Wav::from_samples_f32(
+            sample_rate,
+            self.channels,
+            &Wav::interleave(&output),
+        ))
+    }
+
+    /// Interleaves the samples of every channel, the inverse of [`Wav::deinterleave`].
+    ///
+    /// Channels shorter than the first one are padded with `T::default()`.
+    pub fn interleave<T: Copy + Default>(channels: &[Vec<T>]) -> Vec<T> {
+        let len = channels.first().map_or(0, Vec::len);
+        (0..len)
+            .flat_map(|i| {
+                channels
+                    .iter()
+                    .map(move |channel| channel.get(i).copied().unwrap_or_default())
+            })
+            .collect()
+    }
+}
+
+/// The range and level of a part of a wav, see [`Wav::peaks`].
+///
+/// All values are in `-1.0..=1.0` like [`Wav::samples_f32`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Peak {
+    pub min: f32,
+    pub max: f32,
+    /// The root mean square, a measure of the loudness.
+    pub rms: f32,
+}
+
+fn f32_to_i16(sample: f32) -> i16 {
+    (sample * 32768.0)
+        .round()
+        .clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+/// The length of the header written by [`Wav::to_bytes`].
+pub(crate) const HEADER_LEN: usize = 44;
+/// The format tag of integer PCM.
+const PCM: u16 = 1;
+/// The format tag of `WAVE_FORMAT_EXTENSIBLE`, whose sub format is checked instead.
+const EXTENSIBLE: u16 = 0xFFFE;
+
+fn parse(bytes: &[u8]) -> Result<Wav, String> {
+    let header = parse_header(bytes)?;
+    Ok(Wav {
+        sample_rate: header.sample_rate,
+        channels: header.channels,
+        bits_per_sample: header.bits_per_sample,
+        data: bytes[header.data].to_vec(),
+    })
+}
+
+/// The format of a wav file and where its samples are, without copying them.
+pub(crate) struct Header {
+    pub(crate) sample_rate: u32,
+    pub(crate) channels: u16,
+    pub(crate) bits_per_sample: u16,
+    /// The range of the samples in the file.
+    pub(crate) data: Range<usize>,
+}
+
+impl Header {
+    /// The number of samples of every channel.
+    pub(crate) fn frames(&self) -> usize {
+        self.data.len() / (self.channels as usize * self.bits_per_sample as usize / 8)
+    }
+}
+
+pub(crate) fn parse_header(bytes: &[u8]) -> Result<Header, String> {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("missing RIFF/WAVE header".to_owned());
+    }
+
+    let mut format = None;
+    let mut rest = &bytes[12..];
+    while rest.len() >= 8 {
+        let id = &rest[..4];
+        let size = u32::from_le_bytes(rest[4..8].try_into().unwrap()) as usize;
+        let body = &rest[8..];
+        if id == b"data" {
+            let (channels, sample_rate, bits_per_sample) =
+                format.ok_or("the data chunk comes before the fmt chunk")?;
+            // Streaming writers leave the size of the data chunk at 0 or u32::MAX,
+            // in which case the data extends to the end of the file.
+            let size = if size == 0 {
+                body.len()
+            } else {
+                size.min(body.len())
+            };
+            let block_align = channels as usize * bits_per_sample as usize / 8;
+            let start = bytes.len() - body.len();
+            return Ok(Header {
+                sample_rate,
+                channels,
+                bits_per_sample,
+                data: start..start + size - size % block_align,
+            });
+        }
+        if body.len() < size {
+            return Err(format!(
+                "the {:?} chunk is truncated",
+                String::from_utf8_lossy(id)
+            ));
+        }
+        if id == b"fmt " {
+            format = Some(parse_format(&body[..size])?);
+        }
+        // Chunks are padded to an even size.
+        rest = &body[(size + size % 2).min(body.len())..];
+    }
+    Err("missing data chunk".to_owned())
+}
+
+/// Returns `(channels, sample rate, bits per sample)`.
+fn parse_format(fmt: &[u8]) -> Result<(u16, u32, u16), String> {
+    if fmt.len() < 16 {
+        return Err("the fmt chunk is too short".to_owned());
+    }
+    let u16_at = |i: usize| u16::from_le_bytes([fmt[i], fmt[i + 1]]);
+    let mut tag = u16_at(0);
+    if tag == EXTENSIBLE && fmt.len() >= 26 {
+        tag = u16_at(24);
+    }
+    if tag != PCM {
+        return Err(format!(
+            "unsupported format {tag:#x}, only integer PCM is supported"
+        ));
+    }
+    let channels = u16_at(2);
+    let sample_rate = u32::from_le_bytes(fmt[4..8].try_into().unwrap());
+    let bits_per_sample = u16_at(14);
+    if channels == 0 || sample_rate == 0 {
+        return Err("zero channels or sample rate".to_owned());
+    }
+    if !matches!(bits_per_sample, 8 | 16 | 24 | 32) {
+        return Err(format!("unsupported sample size of {bits_per_sample} bits"));
+    }
+    Ok((channels, sample_rate, bits_per_sample))
+}
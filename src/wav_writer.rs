@@ -0,0 +1,139 @@
+use std::io::{Seek, SeekFrom, Write};
+
+use crate::{
+    wav::{self, HEADER_LEN},
+    Error, Wav,
+};
+
+/// Writes a wav file piece by piece, e.g. the sentences of [`VoiceVox::tts_stream`](crate::VoiceVox::tts_stream)
+/// to an HTTP response as soon as each one is synthesized.
+///
+/// The header is written up front with the length of the samples unknown, which players treat
+/// as a stream lasting until the end of the file. After that, only the samples of every
+/// piece are written, without their headers. If the writer is seekable, e.g. a file,
+/// [`StreamingWavWriter::finalize`] fills in the actual length.
+///
+/// ```
+/// use std::io::Cursor;
+/// use voicevox_dyn::{StreamingWavWriter, Wav};
+///
+/// let mut writer = StreamingWavWriter::new(Cursor::new(Vec::new()), 24000, 1, 16)?;
+/// writer.write(&Wav::from_samples_i16(24000, 1, &[1, 2]))?;
+/// writer.write_bytes(&Wav::from_samples_i16(24000, 1, &[3]).to_bytes())?;
+/// let bytes = writer.finalize()?.into_inner();
+/// assert_eq!(Wav::parse(&bytes)?, Wav::from_samples_i16(24000, 1, &[1, 2, 3]));
+/// # Ok::<(), voicevox_dyn::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct StreamingWavWriter<W: Write> {
+    writer: W,
+    format: Wav,
+    data_len: u64,
+}
+
+impl<W: Write> StreamingWavWriter<W> {
+    /// Writes the header for samples in the given format, voicevox synthesizes 16 bit mono
+    /// at 24 kHz by default.
+    pub fn new(
+        mut writer: W,
+        sample_rate: u32,
+        channels: u16,
+        bits_per_sample: u16,
+    ) -> Result<Self, Error> {
+        let format = Wav {
+            sample_rate,
+            channels,
+            bits_per_sample,
+            data: Vec::new(),
+        };
+        writer
+            .write_all(&format.header(None))
+            .map_err(Error::io("failed to write the wav header"))?;
+        Ok(Self {
+            writer,
+            format,
+            data_len: 0,
+        })
+    }
+
+    /// Appends the samples of `wav`.
+    ///
+    /// Returns [`Error::InvalidInput`] if `wav` has a different format than the writer.
+    pub fn write(&mut self, wav: &Wav) -> Result<(), Error> {
+        self.check_format(wav)?;
+        self.write_pcm(&wav.data)
+    }
+
+    fn check_format(&self, wav: &Wav) -> Result<(), Error> {
+        if !self.format.same_format(wav) {
+            return Err(Error::InvalidInput(format!(
+                "cannot write a wav of {} Hz, {} channels, {} bits to a stream of {} Hz, {} channels, {} bits",
+                wav.sample_rate,
+                wav.channels,
+                wav.bits_per_sample,
+                self.format.sample_rate,
+                self.format.channels,
+                self.format.bits_per_sample
+            )));
+        }
+        Ok(())
+    }
+
+    /// Appends the samples of a complete wav file, e.g. the result of
+    /// [`VoiceVox::tts`](crate::VoiceVox::tts), skipping its header.
+    ///
+    /// Returns [`Error::InvalidInput`] if `bytes` is not a wav file or has a different format
+    /// than the writer.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let header = wav::parse_header(bytes)
+            .map_err(|reason| Error::InvalidInput(format!("invalid wav: {}", reason)))?;
+        self.check_format(&Wav {
+            sample_rate: header.sample_rate,
+            channels: header.channels,
+            bits_per_sample: header.bits_per_sample,
+            data: Vec::new(),
+        })?;
+        self.write_pcm(&bytes[header.data])
+    }
+
+    /// Appends interleaved little endian samples in the format of the writer.
+    pub fn write_pcm(&mut self, pcm: &[u8]) -> Result<(), Error> {
+        self.writer
+            .write_all(pcm)
+            .map_err(Error::io("failed to write the wav samples"))?;
+        self.data_len += pcm.len() as u64;
+        Ok(())
+    }
+
+    /// The number of bytes of samples written so far.
+    pub fn data_len(&self) -> u64 {
+        self.data_len
+    }
+
+    /// Flushes and returns the writer, leaving the length in the header unknown.
+    pub fn into_inner(mut self) -> Result<W, Error> {
+        self.writer
+            .flush()
+            .map_err(Error::io("failed to flush the wav"))?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write + Seek> StreamingWavWriter<W> {
+    /// Fills in the length of the samples in the header and returns the writer,
+    /// positioned at the end of the wav.
+    ///
+    /// Wavs longer than 4 GiB keep the unknown length.
+    pub fn finalize(mut self) -> Result<W, Error> {
+        if let Ok(data_len) = u32::try_from(self.data_len) {
+            let header = self.format.header(Some(data_len));
+            let start = self.data_len as i64 + HEADER_LEN as i64;
+            self.writer
+                .seek(SeekFrom::Current(-start))
+                .and_then(|_| self.writer.write_all(&header))
+                .and_then(|()| self.writer.seek(SeekFrom::Current(data_len as i64)))
+                .map_err(Error::io("failed to update the wav header"))?;
+        }
+        self.into_inner()
+    }
+}